@@ -0,0 +1,222 @@
+use crate::{LinkContext, Locale};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, de::Error as _};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use thiserror::Error;
+
+/// The name of the config file looked up in the checked directory
+pub const CONFIG_FILE_NAME: &str = "queensac.toml";
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Failed to read {0}: {1}")]
+    Read(PathBuf, #[source] std::io::Error),
+    #[error("Invalid config file {0}: {1}")]
+    Parse(PathBuf, #[source] toml::de::Error),
+}
+
+/// Options of the command line read from a `queensac.toml` file.
+///
+/// Every option is optional, options given on the command line take precedence over the
+/// file. Keys are written in kebab-case like the flags, e.g.
+///
+/// ```toml
+/// exclude = ["^https://localhost", "example\\.com"]
+/// timeout = 10
+/// fail-on = "redirect"
+///
+/// [pull-request]
+/// sign-off = true
+/// ```
+///
+/// Options whose values are defined by the command line, such as `format` and `fail-on`,
+/// are kept as strings and parsed along with the flags.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// Patterns of URLs that are not checked
+    #[serde(deserialize_with = "regexes")]
+    pub exclude: Vec<Regex>,
+    /// Seconds to wait for a response to each request
+    pub timeout: Option<u64>,
+    /// Links checked at the same time
+    pub concurrency: Option<usize>,
+    /// Retries of requests that failed without a response
+    pub retries: Option<u32>,
+    /// Requests in flight to each host
+    pub per_host_limit: Option<usize>,
+    /// Format of the results printed to stdout
+    pub format: Option<String>,
+    /// Which links make the run fail
+    pub fail_on: Option<String>,
+    #[serde(deserialize_with = "parsed")]
+    pub locale: Option<Locale>,
+    pub skip_string_literals: Option<bool>,
+    /// Link contexts that are reported but never fixed
+    #[serde(deserialize_with = "parsed_list")]
+    pub report_only: Option<Vec<LinkContext>>,
+    pub sitemap: Option<bool>,
+    pub suggest: Option<bool>,
+    pub search_url: Option<String>,
+    pub pull_request: PullRequestConfig,
+    pub log: LogConfig,
+}
+
+/// Options of the logs, under `[log]`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LogConfig {
+    /// Format of the logs
+    pub format: Option<String>,
+    /// Timezone of the log timestamps, an IANA name, a fixed offset, or `local`
+    pub timezone: Option<String>,
+}
+
+/// Options of the fix pull requests, under `[pull-request]`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct PullRequestConfig {
+    pub sign_off: Option<bool>,
+    /// Trailers in `Key: value` form added to fix commits
+    pub trailers: Vec<String>,
+    pub request_reviews: Option<bool>,
+    pub cooldown_days: Option<i64>,
+}
+
+impl Config {
+    /// Parses the content of a config file.
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+
+    /// Reads a config file.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the config file
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_path_buf(), e))?;
+        Self::parse(&content).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))
+    }
+
+    /// Reads the `queensac.toml` file of a directory, if it has one.
+    ///
+    /// # Arguments
+    /// * `dir` - The directory to look in, e.g. the root of a checkout
+    ///
+    /// # Returns
+    /// The config, or `None` if the directory has no config file.
+    pub fn discover(dir: &Path) -> Result<Option<Self>, ConfigError> {
+        let path = dir.join(CONFIG_FILE_NAME);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Self::load(&path).map(Some)
+    }
+}
+
+fn regexes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Regex>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|pattern| Regex::new(pattern).map_err(D::Error::custom))
+        .collect()
+}
+
+fn parsed<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr<Err: Display>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| value.parse().map_err(D::Error::custom))
+        .transpose()
+}
+
+fn parsed_list<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr<Err: Display>,
+{
+    Option::<Vec<String>>::deserialize(deserializer)?
+        .map(|values| {
+            values
+                .iter()
+                .map(|value| value.parse().map_err(D::Error::custom))
+                .collect()
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse(
+            r#"
+            exclude = ["^https://localhost", "example\\.com"]
+            timeout = 10
+            concurrency = 16
+            per-host-limit = 2
+            fail-on = "redirect"
+            locale = "ko"
+            report-only = ["string", "comment"]
+
+            [pull-request]
+            sign-off = true
+            trailers = ["Refs: #42"]
+
+            [log]
+            format = "json"
+            timezone = "Asia/Seoul"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.exclude.len(), 2);
+        assert!(config.exclude[0].is_match("https://localhost:8080"));
+        assert_eq!(config.timeout, Some(10));
+        assert_eq!(config.concurrency, Some(16));
+        assert_eq!(config.retries, None);
+        assert_eq!(config.per_host_limit, Some(2));
+        assert_eq!(config.fail_on.as_deref(), Some("redirect"));
+        assert_eq!(config.format, None);
+        assert_eq!(config.locale, Some(Locale::Ko));
+        assert_eq!(
+            config.report_only,
+            Some(vec![LinkContext::StringLiteral, LinkContext::Comment])
+        );
+        assert_eq!(config.pull_request.sign_off, Some(true));
+        assert_eq!(config.pull_request.trailers, vec!["Refs: #42"]);
+        assert_eq!(config.pull_request.cooldown_days, None);
+        assert_eq!(config.log.format.as_deref(), Some("json"));
+        assert_eq!(config.log.timezone.as_deref(), Some("Asia/Seoul"));
+    }
+
+    #[test]
+    fn test_parse_config_rejects_invalid_values() {
+        assert!(Config::parse("exclude = [\"(\"]").is_err());
+        assert!(Config::parse("locale = \"fr\"").is_err());
+        assert!(Config::parse("report-only = [\"html\"]").is_err());
+        assert!(Config::parse("timeot = 10").is_err());
+    }
+
+    #[test]
+    fn test_discover_config_file() {
+        let dir = std::env::temp_dir().join(format!("queensac-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        assert!(Config::discover(&dir).unwrap().is_none());
+
+        std::fs::write(dir.join(CONFIG_FILE_NAME), "timeout = 3").unwrap();
+        let config = Config::discover(&dir).unwrap().unwrap();
+        assert_eq!(config.timeout, Some(3));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,49 @@
+use crate::SourceError;
+use thiserror::Error;
+
+/// The error of the link checking functions of the library, such as [`check_links`].
+///
+/// Errors of the other parts of the library convert into it, so that code calling several
+/// of them can propagate their errors with `?` and still match on what failed.
+///
+/// [`check_links`]: crate::check_links
+#[derive(Debug, Error)]
+pub enum QueensacError {
+    #[error("Git operation failed: {0}")]
+    Git(#[from] git2::Error),
+    /// A request to a forge API, such as the GitHub API, failed
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    /// A forge API answered with something that cannot be used, such as a truncated tree
+    #[error("GitHub API error: {0}")]
+    GitHub(String),
+}
+
+impl From<SourceError> for QueensacError {
+    fn from(e: SourceError) -> Self {
+        match e {
+            SourceError::Git(e) => Self::Git(e),
+            SourceError::Http(e) => Self::Http(e),
+            SourceError::GitHub(message) => Self::GitHub(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_error_conversion() {
+        let error = QueensacError::from(SourceError::GitHub("tree truncated".to_string()));
+        assert!(matches!(&error, QueensacError::GitHub(message) if message == "tree truncated"));
+        assert_eq!(error.to_string(), "GitHub API error: tree truncated");
+
+        let http_error = reqwest::Client::new().get("not a url").build().unwrap_err();
+        let error = QueensacError::from(SourceError::from(http_error));
+        assert!(matches!(error, QueensacError::Http(e) if e.is_builder()));
+
+        let error = QueensacError::from(SourceError::Git(git2::Error::from_str("no HEAD")));
+        assert!(matches!(error, QueensacError::Git(_)));
+    }
+}
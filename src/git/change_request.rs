@@ -0,0 +1,48 @@
+use crate::{FileChange, PrError, PullRequestGenerator};
+
+/// A code hosting service that link fixes are proposed to, as pull requests on GitHub
+/// or merge requests on GitLab.
+///
+/// Implementations apply the fixes to the cloned repository on a new branch, push it,
+/// and open a change request for it, so that the same fix pipeline can target any host.
+pub trait ChangeRequestBackend {
+    /// Creates a change request with link fixes.
+    ///
+    /// # Arguments
+    /// * `fixes` - The list of file changes to apply
+    ///
+    /// # Returns
+    /// The URL of the change request.
+    fn create_fix_pr(
+        &self,
+        fixes: Vec<FileChange>,
+    ) -> impl Future<Output = Result<String, PrError>>;
+
+    /// Closes open change requests created by queensac that are no longer needed.
+    ///
+    /// # Arguments
+    /// * `current_branch` - The branch of the change request to keep open, if any
+    /// * `reason` - Why the change requests are closed, posted as a comment
+    ///
+    /// # Returns
+    /// The number of closed change requests.
+    fn close_stale_prs(
+        &self,
+        current_branch: Option<&str>,
+        reason: &str,
+    ) -> impl Future<Output = Result<usize, PrError>>;
+}
+
+impl ChangeRequestBackend for PullRequestGenerator {
+    async fn create_fix_pr(&self, fixes: Vec<FileChange>) -> Result<String, PrError> {
+        PullRequestGenerator::create_fix_pr(self, fixes).await
+    }
+
+    async fn close_stale_prs(
+        &self,
+        current_branch: Option<&str>,
+        reason: &str,
+    ) -> Result<usize, PrError> {
+        PullRequestGenerator::close_stale_prs(self, current_branch, reason).await
+    }
+}
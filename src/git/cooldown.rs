@@ -0,0 +1,154 @@
+use crate::PrError;
+use chrono::{DateTime, TimeDelta, Utc};
+use std::{collections::HashMap, path::PathBuf};
+
+/// Limits fix pull requests to at most one per repository within a cooldown window.
+///
+/// The time the last pull request was opened for each repository is persisted in a JSON
+/// state file, so the limit holds across runs of the CLI.
+#[derive(Debug, Clone)]
+pub struct PrCooldown {
+    state_path: PathBuf,
+    window: TimeDelta,
+}
+
+impl PrCooldown {
+    /// Creates a cooldown policy.
+    ///
+    /// # Arguments
+    /// * `state_path` - The JSON file the last pull request times are stored in
+    /// * `window` - How long to wait after a pull request before opening another one
+    pub fn new(state_path: PathBuf, window: TimeDelta) -> Self {
+        Self { state_path, window }
+    }
+
+    /// Returns the default state file, `queensac/pr_cooldown.json` in the user's state directory.
+    pub fn default_state_path() -> PathBuf {
+        state_file_path("pr_cooldown.json")
+    }
+
+    /// Returns when the next pull request may be opened for a repository, if it is
+    /// still in its cooldown window.
+    ///
+    /// # Arguments
+    /// * `repo_key` - Identifies the repository, e.g. `github.com/owner/repo`
+    /// * `now` - The current time
+    pub fn next_allowed(
+        &self,
+        repo_key: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<DateTime<Utc>>, PrError> {
+        let state = self.read_state()?;
+        Ok(state
+            .get(repo_key)
+            .map(|opened_at| *opened_at + self.window)
+            .filter(|next_allowed| *next_allowed > now))
+    }
+
+    /// Records that a pull request was opened for a repository.
+    ///
+    /// # Arguments
+    /// * `repo_key` - Identifies the repository, e.g. `github.com/owner/repo`
+    /// * `opened_at` - When the pull request was opened
+    pub fn record(&self, repo_key: &str, opened_at: DateTime<Utc>) -> Result<(), PrError> {
+        let mut state = self.read_state()?;
+        state.insert(repo_key.to_string(), opened_at);
+
+        if let Some(parent) = self.state_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                PrError::File(format!("Failed to create {}: {e}", parent.display()))
+            })?;
+        }
+        let content = serde_json::to_string_pretty(&state)
+            .map_err(|e| PrError::File(format!("Failed to serialize cooldown state: {e}")))?;
+        std::fs::write(&self.state_path, content).map_err(|e| {
+            PrError::File(format!(
+                "Failed to write {}: {e}",
+                self.state_path.display()
+            ))
+        })
+    }
+
+    fn read_state(&self) -> Result<HashMap<String, DateTime<Utc>>, PrError> {
+        let content = match std::fs::read_to_string(&self.state_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(e) => {
+                return Err(PrError::File(format!(
+                    "Failed to read {}: {e}",
+                    self.state_path.display()
+                )));
+            }
+        };
+
+        serde_json::from_str(&content).map_err(|e| {
+            PrError::File(format!(
+                "Invalid cooldown state in {}: {e}",
+                self.state_path.display()
+            ))
+        })
+    }
+}
+
+/// Returns the path of a file in queensac's state directory.
+///
+/// Uses `$XDG_STATE_HOME`, then `$HOME/.local/state`, then the temporary directory.
+pub(crate) fn state_file_path(file_name: &str) -> PathBuf {
+    let state_dir = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+        .unwrap_or_else(std::env::temp_dir);
+
+    state_dir.join("queensac").join(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cooldown_window() {
+        let state_path = std::env::temp_dir().join(format!(
+            "queensac_cooldown_test_{}.json",
+            Utc::now().timestamp_nanos_opt().unwrap()
+        ));
+        let cooldown = PrCooldown::new(state_path.clone(), TimeDelta::days(7));
+        let opened_at = Utc::now();
+
+        assert_eq!(
+            cooldown
+                .next_allowed("github.com/reddevilmidzy/kingsac", opened_at)
+                .unwrap(),
+            None
+        );
+
+        cooldown
+            .record("github.com/reddevilmidzy/kingsac", opened_at)
+            .unwrap();
+
+        let next_allowed = opened_at + TimeDelta::days(7);
+        assert_eq!(
+            cooldown
+                .next_allowed(
+                    "github.com/reddevilmidzy/kingsac",
+                    opened_at + TimeDelta::days(1)
+                )
+                .unwrap(),
+            Some(next_allowed)
+        );
+        assert_eq!(
+            cooldown
+                .next_allowed("github.com/reddevilmidzy/kingsac", next_allowed)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            cooldown
+                .next_allowed("github.com/reddevilmidzy/queensac", opened_at)
+                .unwrap(),
+            None
+        );
+
+        std::fs::remove_file(state_path).unwrap();
+    }
+}
@@ -1,5 +1,24 @@
-use git2::{Commit, Delta, DiffFindOptions, ErrorCode, Repository};
-use std::path;
+use git2::{Commit, Delta, DiffFindOptions, ErrorCode, Repository, Tree};
+use std::{collections::VecDeque, path};
+
+/// The file mode of symlinks in git trees
+const SYMLINK_FILE_MODE: i32 = 0o120000;
+
+/// The maximum number of symlinks followed when resolving a path, to stop at symlink loops
+const MAX_SYMLINK_HOPS: usize = 8;
+
+/// Describes how a path was found in a repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathMatch {
+    /// The path exists as given
+    Exact,
+    /// The path only exists with different letter case, which breaks on case-sensitive
+    /// hosts such as GitHub. Contains the correctly-cased path.
+    CaseMismatch(String),
+    /// The path goes through a symlink, which GitHub does not follow. Contains the path
+    /// the symlink resolves to.
+    Symlink(String),
+}
 
 /// Represents the result of searching for the last commit that touched a target path.
 ///
@@ -9,8 +28,9 @@ use std::path;
 /// # Fields
 /// * `commit` - The commit that last touched the target path
 /// * `renamed_path` - If the target path was renamed in this commit, contains the new path.
-///   For files, this is the full new file path. For directories, this is the new directory
-///   path with a trailing slash. If the path was not renamed, this is `None`.
+///   For files, this is the full new file path. For directories, this is the full new
+///   directory path. A file whose directory was moved gets its path in the moved directory.
+///   If the path was not renamed, this is `None`.
 pub struct CommitSearchResult<'a> {
     /// The commit that last touched the target path
     pub commit: Commit<'a>,
@@ -55,9 +75,9 @@ pub fn find_last_commit_id<'a>(
             let mut find_opts = DiffFindOptions::new();
             find_opts.rename_threshold(50); // Git default threshold 50%
             diff.find_similar(Some(&mut find_opts))?;
+            let mut touched = false;
+            let mut moved_to = None;
             for delta in diff.deltas() {
-                let mut renamed_path = None;
-
                 // file check
                 if let Some(file_path) = delta.new_file().path()
                     && file_path == target_path
@@ -67,38 +87,66 @@ pub fn find_last_commit_id<'a>(
                         renamed_path: None,
                     });
                 }
-                // directory check
-                if let Some(old_path) = delta.old_file().path()
-                    && old_path.starts_with(target_path)
+                let Some(old_path) = delta.old_file().path() else {
+                    continue;
+                };
+                touched |= old_path.starts_with(target_path);
+                if delta.status() != Delta::Renamed {
+                    continue;
+                }
+                let Some(new_path) = delta.new_file().path() else {
+                    continue;
+                };
+                if old_path == target_path {
+                    moved_to = Some(new_path.to_path_buf());
+                    break;
+                }
+                // directory check: the target, or the directory containing it, was moved
+                if moved_to.is_none()
+                    && let Some((old_dir, new_dir)) = moved_directory(old_path, new_path)
+                    && !old_dir.as_os_str().is_empty()
+                    && let Ok(rest) = target_path.strip_prefix(old_dir)
                 {
-                    if old_path == target_path && delta.status() == Delta::Renamed {
-                        renamed_path = delta
-                            .new_file()
-                            .path()
-                            .and_then(|p| p.to_str())
-                            .map(|s| s.to_string());
-                    } else if delta.status() == Delta::Renamed
-                        && let Some(path) = delta.new_file().path()
-                        && let Some(parent) = path.parent()
-                    {
-                        let mut dir = parent.to_string_lossy().to_string();
-                        if !dir.ends_with('/') && !dir.ends_with('\\') {
-                            dir.push('/');
-                        }
-                        renamed_path = Some(dir);
-                    }
-
-                    return Ok(CommitSearchResult {
-                        commit,
-                        renamed_path,
-                    });
+                    moved_to = Some(new_dir.join(rest));
                 }
             }
+            // A rename next to the target, e.g. of a sibling file, does not move the target
+            moved_to.take_if(|path| path == target_path);
+
+            if touched || moved_to.is_some() {
+                return Ok(CommitSearchResult {
+                    commit,
+                    renamed_path: moved_to.map(|path| path.to_string_lossy().into_owned()),
+                });
+            }
         }
     }
     Err(git2::Error::from_str("File not found"))
 }
 
+/// Returns the directory a renamed file was moved out of and the directory it was moved
+/// into, by stripping the trailing path components the old and new paths have in common.
+///
+/// For example, `foo/intrinsics/mod.rs` renamed to `bar/intrinsics/mod.rs` means `foo` was
+/// moved to `bar`. If the file name changed, the file itself was renamed rather than its
+/// directory moved, and `None` is returned.
+fn moved_directory<'p>(
+    old_path: &'p path::Path,
+    new_path: &'p path::Path,
+) -> Option<(&'p path::Path, &'p path::Path)> {
+    if old_path.file_name() != new_path.file_name() {
+        return None;
+    }
+    let (mut old_dir, mut new_dir) = (old_path, new_path);
+    while old_dir.file_name().is_some() && old_dir.file_name() == new_dir.file_name() {
+        match (old_dir.parent(), new_dir.parent()) {
+            (Some(old_parent), Some(new_parent)) => (old_dir, new_dir) = (old_parent, new_parent),
+            _ => break,
+        }
+    }
+    Some((old_dir, new_dir))
+}
+
 /// Checks if a file exists in the repository at the given path
 ///
 /// # Arguments
@@ -120,9 +168,106 @@ pub fn file_exists_in_repo(repo: &Repository, file_path: &str) -> Result<bool, g
     }
 }
 
+/// Finds a path in the checked out commit of a repository, allowing for letter case
+/// differences and following symlinks.
+///
+/// # Arguments
+/// * `repo` - The repository to search in
+/// * `file_path` - The path of the file or directory to find
+///
+/// # Returns
+/// * `Ok(Some(PathMatch))` - How the path was found
+/// * `Ok(None)` - If the path does not exist, not even with different case
+/// * `Err(git2::Error)` - If there was an error accessing the repository
+pub fn find_path_in_repo(
+    repo: &Repository,
+    file_path: &str,
+) -> Result<Option<PathMatch>, git2::Error> {
+    let root = repo.head()?.peel_to_tree()?;
+    let mut pending: VecDeque<String> = file_path
+        .split('/')
+        .filter(|component| !component.is_empty())
+        .map(str::to_string)
+        .collect();
+    let mut resolved: Vec<String> = Vec::new();
+    let mut symlink_hops = 0;
+
+    while let Some(component) = pending.pop_front() {
+        match component.as_str() {
+            "." => continue,
+            ".." => {
+                if resolved.pop().is_none() {
+                    return Ok(None);
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        let Some(dir) = subtree(repo, &root, &resolved)? else {
+            return Ok(None);
+        };
+        let entry = dir.get_name(&component).or_else(|| {
+            dir.iter().find(|entry| {
+                entry
+                    .name()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(&component))
+            })
+        });
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+
+        if entry.filemode() == SYMLINK_FILE_MODE {
+            symlink_hops += 1;
+            let blob = entry.to_object(repo)?.peel_to_blob()?;
+            let Ok(target) = std::str::from_utf8(blob.content()) else {
+                return Ok(None);
+            };
+            // Absolute targets point outside of the repository
+            if symlink_hops > MAX_SYMLINK_HOPS || target.starts_with('/') {
+                return Ok(None);
+            }
+            for target_component in target.split('/').rev() {
+                if !target_component.is_empty() {
+                    pending.push_front(target_component.to_string());
+                }
+            }
+            continue;
+        }
+        resolved.push(entry.name().unwrap_or(&component).to_string());
+    }
+
+    let resolved = resolved.join("/");
+    Ok(Some(if symlink_hops > 0 {
+        PathMatch::Symlink(resolved)
+    } else if resolved == file_path.trim_matches('/') {
+        PathMatch::Exact
+    } else {
+        PathMatch::CaseMismatch(resolved)
+    }))
+}
+
+/// Returns the tree at the given path components, or `None` if it is not a directory.
+fn subtree<'r>(
+    repo: &'r Repository,
+    root: &Tree<'r>,
+    components: &[String],
+) -> Result<Option<Tree<'r>>, git2::Error> {
+    if components.is_empty() {
+        return Ok(Some(root.clone()));
+    }
+    let entry = match root.get_path(path::Path::new(&components.join("/"))) {
+        Ok(entry) => entry,
+        Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    Ok(entry.to_object(repo)?.into_tree().ok())
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{GitHubUrl, RepoManager};
+    use crate::{CloneOptions, GitHubUrl, RepoManager, git::repo::TempDirGuard};
 
     use super::*;
     use serial_test::serial;
@@ -165,7 +310,7 @@ mod tests {
             None,
             None,
         );
-        let repo_manager = RepoManager::from(&github_url)?;
+        let repo_manager = RepoManager::from_with_options(&github_url, CloneOptions::full())?;
 
         // 1. Find the commit where test_for_multiple_moves.rs was moved to foo/test_for_multiple_moves.rs
         let result = find_last_commit_id("test_for_multiple_moves.rs", repo_manager.get_repo())?;
@@ -200,4 +345,134 @@ mod tests {
 
         Ok(())
     }
+
+    /// Commits the given files as the whole tree of a new commit on HEAD.
+    fn commit_tree(repo: &Repository, files: &[(&str, &str)]) {
+        let workdir = repo.workdir().unwrap();
+        let mut index = repo.index().unwrap();
+        index.clear().unwrap();
+        for (file_path, content) in files {
+            let full_path = workdir.join(file_path);
+            std::fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+            std::fs::write(full_path, content).unwrap();
+            index.add_path(path::Path::new(file_path)).unwrap();
+        }
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("queensac", "queensac@example.com").unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Update",
+            &tree,
+            &parent.iter().collect::<Vec<_>>(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_find_path_in_repo_with_case_mismatch_and_symlink() {
+        let guard = TempDirGuard::new(std::env::temp_dir().join(format!(
+            "queensac_path_match_test_{}",
+            chrono::Utc::now().timestamp_nanos_opt().unwrap()
+        )))
+        .unwrap();
+        let repo = Repository::init(guard.get_path()).unwrap();
+        let guide = repo.blob(b"# Guide\n").unwrap();
+        let mut docs = repo.treebuilder(None).unwrap();
+        docs.insert("Guide.md", guide, 0o100644).unwrap();
+        let docs = docs.write().unwrap();
+        let manual = repo.blob(b"docs").unwrap();
+        let mut root = repo.treebuilder(None).unwrap();
+        root.insert("docs", docs, 0o040000).unwrap();
+        root.insert("manual", manual, SYMLINK_FILE_MODE).unwrap();
+        let tree = repo.find_tree(root.write().unwrap()).unwrap();
+        let signature = git2::Signature::now("queensac", "queensac@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+            .unwrap();
+
+        assert_eq!(
+            find_path_in_repo(&repo, "docs/Guide.md").unwrap(),
+            Some(PathMatch::Exact)
+        );
+        assert_eq!(
+            find_path_in_repo(&repo, "DOCS/guide.md").unwrap(),
+            Some(PathMatch::CaseMismatch("docs/Guide.md".to_string()))
+        );
+        assert_eq!(
+            find_path_in_repo(&repo, "manual/Guide.md").unwrap(),
+            Some(PathMatch::Symlink("docs/Guide.md".to_string()))
+        );
+        assert_eq!(find_path_in_repo(&repo, "docs/missing.md").unwrap(), None);
+        assert_eq!(find_path_in_repo(&repo, "docs/Guide.md/x").unwrap(), None);
+    }
+
+    #[test]
+    fn test_track_directory_move() {
+        let guard = TempDirGuard::new(std::env::temp_dir().join(format!(
+            "queensac_directory_move_test_{}",
+            chrono::Utc::now().timestamp_nanos_opt().unwrap()
+        )))
+        .unwrap();
+        let repo = Repository::init(guard.get_path()).unwrap();
+        let intro = "# Introduction\n\nHow to get started with the API.\n";
+        let auth = "# Authentication\n\nHow to authenticate API requests.\n";
+        commit_tree(
+            &repo,
+            &[("docs/api/intro.md", intro), ("docs/api/auth.md", auth)],
+        );
+        commit_tree(
+            &repo,
+            &[
+                ("reference/api/intro.md", intro),
+                ("reference/api/auth.md", auth),
+            ],
+        );
+
+        let result = find_last_commit_id("docs/api", &repo).unwrap();
+        assert_eq!(result.renamed_path, Some("reference/api".to_string()));
+        let result = find_last_commit_id("docs/api/auth.md", &repo).unwrap();
+        assert_eq!(
+            result.renamed_path,
+            Some("reference/api/auth.md".to_string())
+        );
+
+        let repo_manager = RepoManager::open_local(guard.get_path()).unwrap();
+        let url = GitHubUrl::new(
+            "reddevilmidzy".to_string(),
+            "kingsac".to_string(),
+            Some("main".to_string()),
+            Some("docs/api".to_string()),
+        );
+        assert_eq!(
+            repo_manager.find_current_location(&url).unwrap(),
+            Some("reference/api".to_string())
+        );
+    }
+
+    #[test]
+    fn test_sibling_rename_does_not_move_target() {
+        let guard = TempDirGuard::new(std::env::temp_dir().join(format!(
+            "queensac_sibling_rename_test_{}",
+            chrono::Utc::now().timestamp_nanos_opt().unwrap()
+        )))
+        .unwrap();
+        let repo = Repository::init(guard.get_path()).unwrap();
+        let content = "# Guide\n\nHow to get started with the API.\n";
+        commit_tree(&repo, &[("docs/a.md", content)]);
+        commit_tree(&repo, &[("docs/b.md", content)]);
+
+        assert!(find_last_commit_id("docs/missing.md", &repo).is_err());
+
+        let repo_manager = RepoManager::open_local(guard.get_path()).unwrap();
+        let url = GitHubUrl::new(
+            "reddevilmidzy".to_string(),
+            "kingsac".to_string(),
+            Some("main".to_string()),
+            Some("docs/missing.md".to_string()),
+        );
+        assert_eq!(repo_manager.find_current_location(&url).unwrap(), None);
+    }
 }
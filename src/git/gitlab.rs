@@ -0,0 +1,387 @@
+use crate::{
+    ChangeRequestBackend, FileChange, GitLabUrl, LinkChecker, Locale, Message, PrError,
+    RepoManager,
+    git::pr_generator::{
+        AUTHOR_EMAIL, AUTHOR_NAME, BRANCH_PREFIX, apply_fixes, fix_commit_message, fix_description,
+        generate_branch_name, verify_fixes,
+    },
+};
+use serde::Deserialize;
+use tracing::{error, info};
+
+/// A merge request as returned by the GitLab API.
+#[derive(Debug, Deserialize)]
+struct MergeRequest {
+    iid: u64,
+    source_branch: String,
+    web_url: String,
+}
+
+/// Generates merge requests for link fixes in a GitLab repository.
+pub struct MergeRequestGenerator {
+    repo_manager: RepoManager,
+    gitlab_url: GitLabUrl,
+    base_branch: String,
+    client: reqwest::Client,
+    /// The base URL of the GitLab REST API, e.g. `https://gitlab.com/api/v4`
+    api_url: String,
+    access_token: String,
+    /// The checker used to verify replacement URLs before they are applied
+    link_checker: LinkChecker,
+    /// The language of merge request titles and descriptions
+    locale: Locale,
+}
+
+impl MergeRequestGenerator {
+    /// Creates a new MergeRequestGenerator authenticated with a project access token.
+    ///
+    /// # Arguments
+    /// * `repo_manager` - The repository manager instance
+    /// * `gitlab_url` - The GitLab URL of the repository
+    /// * `access_token` - A project access token with the `api` and `write_repository` scopes
+    /// * `base_branch` - The target branch for the merge request
+    pub fn new(
+        repo_manager: RepoManager,
+        gitlab_url: GitLabUrl,
+        access_token: String,
+        base_branch: String,
+    ) -> Result<Self, PrError> {
+        let api_url = format!("https://{}/api/v4", gitlab_url.host());
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| PrError::Config(format!("Failed to build HTTP client: {e}")))?;
+
+        Ok(Self {
+            repo_manager,
+            gitlab_url,
+            base_branch,
+            client,
+            api_url,
+            access_token,
+            link_checker: LinkChecker::default(),
+            locale: Locale::default(),
+        })
+    }
+
+    /// Creates a new MergeRequestGenerator from environment variables.
+    ///
+    /// # Environment Variables
+    /// * `QUEENSAC_GITLAB_TOKEN` - The project access token
+    /// * `QUEENSAC_GITLAB_API_URL` - Base URL of the GitLab API, defaults to `https://<host>/api/v4`
+    pub fn from_env(
+        repo_manager: RepoManager,
+        gitlab_url: GitLabUrl,
+        base_branch: String,
+    ) -> Result<Self, PrError> {
+        let access_token = std::env::var("QUEENSAC_GITLAB_TOKEN").map_err(|_| {
+            PrError::Config("Missing environment variable: QUEENSAC_GITLAB_TOKEN".to_string())
+        })?;
+
+        let mut generator = Self::new(repo_manager, gitlab_url, access_token, base_branch)?;
+        if let Ok(api_url) = std::env::var("QUEENSAC_GITLAB_API_URL") {
+            generator.api_url = api_url.trim_end_matches('/').to_string();
+        }
+        Ok(generator)
+    }
+
+    /// Sets the checker used to verify replacement URLs before they are applied.
+    pub fn set_link_checker(&mut self, link_checker: LinkChecker) {
+        self.link_checker = link_checker;
+    }
+
+    /// Sets the language of merge request titles and descriptions.
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.locale = locale;
+    }
+
+    /// Creates a merge request with link fixes.
+    ///
+    /// Each replacement URL is checked before being applied. Fixes whose replacement is itself
+    /// broken are not applied and are listed in the merge request description instead.
+    ///
+    /// # Arguments
+    /// * `fixes` - The list of file changes to apply
+    pub async fn create_fix_mr(&self, fixes: Vec<FileChange>) -> Result<String, PrError> {
+        let (fixes, skipped) = verify_fixes(&self.link_checker, fixes).await;
+
+        let branch_name = generate_branch_name();
+        self.repo_manager.create_branch(&branch_name).await?;
+        self.repo_manager.checkout_branch(&branch_name).await?;
+
+        let changes = apply_fixes(&self.repo_manager, fixes).await?;
+        if changes.is_empty() {
+            info!("No file changes to commit. Skipping push and MR creation.");
+            return Err(PrError::Config("No changes to create MR".to_string()));
+        }
+
+        for change in &changes {
+            self.repo_manager.add_file(&change.file_path).await?;
+        }
+        self.repo_manager
+            .commit(
+                &fix_commit_message(&changes, &[]),
+                AUTHOR_NAME,
+                AUTHOR_EMAIL,
+            )
+            .await?;
+        self.repo_manager
+            .push("origin", &branch_name, &self.access_token)
+            .await?;
+
+        let mr_url = self.open_merge_request(&branch_name, &skipped).await?;
+
+        if let Err(e) = self
+            .close_stale_mrs(Some(&branch_name), &format!("Superseded by {mr_url}."))
+            .await
+        {
+            error!("Failed to close superseded MRs: {}", e);
+        }
+
+        info!("Successfully created MR: {}", mr_url);
+        Ok(mr_url)
+    }
+
+    /// Closes open queensac merge requests that are no longer needed.
+    ///
+    /// Every open merge request from a queensac branch other than `current_branch` is
+    /// commented on with `reason`, closed, and has its branch deleted.
+    ///
+    /// # Arguments
+    /// * `current_branch` - The branch of the merge request to keep open, if any
+    /// * `reason` - Why the merge requests are closed, posted as a comment
+    ///
+    /// # Returns
+    /// The number of closed merge requests.
+    pub async fn close_stale_mrs(
+        &self,
+        current_branch: Option<&str>,
+        reason: &str,
+    ) -> Result<usize, PrError> {
+        let merge_requests: Vec<MergeRequest> = self
+            .client
+            .get(self.project_endpoint("/merge_requests"))
+            .query(&[("state", "opened"), ("per_page", "100")])
+            .header("PRIVATE-TOKEN", &self.access_token)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| PrError::GitLab(format!("Failed to list MRs: {e}")))?
+            .json()
+            .await
+            .map_err(|e| PrError::GitLab(format!("Failed to parse MRs: {e}")))?;
+
+        let mut closed = 0;
+        for mr in merge_requests {
+            let branch = mr.source_branch.as_str();
+            if !branch.starts_with(BRANCH_PREFIX) || Some(branch) == current_branch {
+                continue;
+            }
+
+            self.send(
+                self.client
+                    .post(self.project_endpoint(&format!("/merge_requests/{}/notes", mr.iid)))
+                    .form(&[("body", reason)]),
+                "comment on MR",
+            )
+            .await?;
+            self.send(
+                self.client
+                    .put(self.project_endpoint(&format!("/merge_requests/{}", mr.iid)))
+                    .form(&[("state_event", "close")]),
+                "close MR",
+            )
+            .await?;
+            let branch_path =
+                url::form_urlencoded::byte_serialize(branch.as_bytes()).collect::<String>();
+            self.send(
+                self.client
+                    .delete(self.project_endpoint(&format!("/repository/branches/{branch_path}"))),
+                "delete branch",
+            )
+            .await?;
+
+            info!("Closed stale MR !{} and deleted {}", mr.iid, branch);
+            closed += 1;
+        }
+
+        Ok(closed)
+    }
+
+    /// Opens a merge request from the fix branch via the GitLab API.
+    ///
+    /// # Arguments
+    /// * `branch_name` - The branch containing the fixes
+    /// * `skipped` - Fixes that were not applied because their replacement could not be verified
+    async fn open_merge_request(
+        &self,
+        branch_name: &str,
+        skipped: &[FileChange],
+    ) -> Result<String, PrError> {
+        let description = fix_description(self.locale, Message::MergeRequest, "MR", skipped);
+        let mr: MergeRequest = self
+            .client
+            .post(self.project_endpoint("/merge_requests"))
+            .header("PRIVATE-TOKEN", &self.access_token)
+            .form(&[
+                ("source_branch", branch_name),
+                ("target_branch", self.base_branch.as_str()),
+                ("title", self.locale.text(Message::FixTitle)),
+                ("description", description.as_str()),
+                ("remove_source_branch", "true"),
+            ])
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| PrError::GitLab(format!("Failed to create MR: {e}")))?
+            .json()
+            .await
+            .map_err(|e| PrError::GitLab(format!("Failed to parse MR: {e}")))?;
+
+        info!("Successfully created MR !{}", mr.iid);
+        Ok(mr.web_url)
+    }
+
+    /// Sends an authenticated request whose response body is not needed.
+    async fn send(&self, request: reqwest::RequestBuilder, action: &str) -> Result<(), PrError> {
+        request
+            .header("PRIVATE-TOKEN", &self.access_token)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| PrError::GitLab(format!("Failed to {action}: {e}")))?;
+        Ok(())
+    }
+
+    /// Returns the URL of a project API endpoint, e.g. `/merge_requests`.
+    fn project_endpoint(&self, path: &str) -> String {
+        let project_path = self.gitlab_url.project_path();
+        let project_id =
+            url::form_urlencoded::byte_serialize(project_path.as_bytes()).collect::<String>();
+        format!("{}/projects/{}{}", self.api_url, project_id, path)
+    }
+}
+
+impl ChangeRequestBackend for MergeRequestGenerator {
+    async fn create_fix_pr(&self, fixes: Vec<FileChange>) -> Result<String, PrError> {
+        self.create_fix_mr(fixes).await
+    }
+
+    async fn close_stale_prs(
+        &self,
+        current_branch: Option<&str>,
+        reason: &str,
+    ) -> Result<usize, PrError> {
+        self.close_stale_mrs(current_branch, reason).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::mock_github::FixtureRepo;
+    use wiremock::matchers::{body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn generator_for(mock_server: &MockServer, repo_manager: RepoManager) -> MergeRequestGenerator {
+        let gitlab_url = GitLabUrl::parse("https://gitlab.com/group/subgroup/kingsac").unwrap();
+        let mut generator = MergeRequestGenerator::new(
+            repo_manager,
+            gitlab_url,
+            "glpat-test".to_string(),
+            "main".to_string(),
+        )
+        .unwrap();
+        generator.api_url = format!("{}/api/v4", mock_server.uri());
+        generator
+    }
+
+    #[test]
+    fn test_project_endpoint() {
+        let fixture = FixtureRepo::new(&[("README.md", "# kingsac\n")]);
+        let gitlab_url = GitLabUrl::parse("https://gitlab.com/group/subgroup/kingsac").unwrap();
+        let generator = MergeRequestGenerator::new(
+            fixture.clone_as("kingsac"),
+            gitlab_url,
+            "glpat-test".to_string(),
+            "main".to_string(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            generator.project_endpoint("/merge_requests"),
+            "https://gitlab.com/api/v4/projects/group%2Fsubgroup%2Fkingsac/merge_requests"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_open_merge_request() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v4/projects/group%2Fsubgroup%2Fkingsac/merge_requests"))
+            .and(header("PRIVATE-TOKEN", "glpat-test"))
+            .and(body_string_contains("source_branch=queensac-test"))
+            .respond_with(ResponseTemplate::new(201).set_body_string(
+                r#"{"iid": 7, "source_branch": "queensac-test", "web_url": "https://gitlab.com/group/subgroup/kingsac/-/merge_requests/7"}"#,
+            ))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let fixture = FixtureRepo::new(&[("README.md", "# kingsac\n")]);
+        let generator = generator_for(&mock_server, fixture.clone_as("kingsac"));
+
+        let mr_url = generator
+            .open_merge_request("queensac-test", &[])
+            .await
+            .unwrap();
+        assert_eq!(
+            mr_url,
+            "https://gitlab.com/group/subgroup/kingsac/-/merge_requests/7"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_close_stale_mrs_keeps_current_branch() {
+        let mock_server = MockServer::start().await;
+        let project = "/api/v4/projects/group%2Fsubgroup%2Fkingsac";
+        Mock::given(method("GET"))
+            .and(path(format!("{project}/merge_requests")))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"[
+                    {"iid": 1, "source_branch": "queensac-old", "web_url": "https://gitlab.com/mr/1"},
+                    {"iid": 2, "source_branch": "queensac-new", "web_url": "https://gitlab.com/mr/2"},
+                    {"iid": 3, "source_branch": "feature", "web_url": "https://gitlab.com/mr/3"}
+                ]"#,
+            ))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(format!("{project}/merge_requests/1/notes")))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path(format!("{project}/merge_requests/1")))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path(format!("{project}/repository/branches/queensac-old")))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let fixture = FixtureRepo::new(&[("README.md", "# kingsac\n")]);
+        let generator = generator_for(&mock_server, fixture.clone_as("kingsac"));
+
+        let closed = generator
+            .close_stale_mrs(Some("queensac-new"), "Superseded.")
+            .await
+            .unwrap();
+        assert_eq!(closed, 1);
+    }
+}
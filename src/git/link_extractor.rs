@@ -1,12 +1,122 @@
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
-use crate::RepoManager;
+use crate::{
+    RepoManager, RepoSource, SourceError, SourceFile,
+    git::tokenizer::{Segment, source_syntax, tokenize_line},
+    read_head_files,
+};
 
 const REGEX_DOMAIN: &str = r"https?://(www\.)?[-a-zA-Z0-9@:%._+~#=]{1,256}\.[a-zA-Z0-9()]{1,6}\b([-a-zA-Z0-9()@:%_+.~#?&/=]*)";
 const REGEX_IP_ADDRESS: &str = r"https?://(localhost|(?:\d{1,3}\.){3}\d{1,3})(?::\d+)?";
 
+/// The context a link was found in.
+///
+/// Links in code and configuration often point at API endpoints or package registries
+/// rather than documentation, so they may need different fix semantics than doc links.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinkContext {
+    /// Documentation markup such as Markdown, reStructuredText, or plain text files
+    Markdown,
+    /// Configuration files such as TOML, YAML, or JSON
+    Config,
+    /// Code outside of comments and string literals, or files of unknown type
+    Text,
+    /// A comment in a source file
+    Comment,
+    /// A string literal in a source file, often an API endpoint rather than documentation
+    StringLiteral,
+}
+
+impl LinkContext {
+    /// Returns the name of the context as used on the command line and in logs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Markdown => "markdown",
+            Self::Config => "config",
+            Self::Text => "text",
+            Self::Comment => "comment",
+            Self::StringLiteral => "string",
+        }
+    }
+
+    /// Returns the context of links in a non-source file, based on its extension.
+    fn from_file_path(file_path: &str) -> Self {
+        let extension = file_path.rsplit_once('.').map(|(_, ext)| ext);
+        match extension {
+            Some("md" | "mdx" | "markdown" | "rst" | "adoc" | "txt") => Self::Markdown,
+            Some("toml" | "yaml" | "yml" | "json" | "ini" | "cfg" | "xml") => Self::Config,
+            _ => Self::Text,
+        }
+    }
+}
+
+impl std::fmt::Display for LinkContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for LinkContext {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "markdown" => Ok(Self::Markdown),
+            "config" => Ok(Self::Config),
+            "text" => Ok(Self::Text),
+            "comment" => Ok(Self::Comment),
+            "string" => Ok(Self::StringLiteral),
+            other => Err(format!("Unknown link context: {other}")),
+        }
+    }
+}
+
+/// Decides which broken links may be fixed automatically, based on their context.
+///
+/// Links in report-only contexts are still checked and reported, but never rewritten.
 #[derive(Debug, Clone)]
+pub struct FixPolicy {
+    /// Contexts whose links are only reported
+    pub report_only: Vec<LinkContext>,
+}
+
+impl FixPolicy {
+    /// Returns whether links found in the given context may be fixed automatically.
+    pub fn allows_fix(&self, context: LinkContext) -> bool {
+        !self.report_only.contains(&context)
+    }
+}
+
+impl Default for FixPolicy {
+    /// Only reports links in string literals, which are usually API endpoints.
+    fn default() -> Self {
+        Self {
+            report_only: vec![LinkContext::StringLiteral],
+        }
+    }
+}
+
+/// Options controlling which links are extracted from a repository.
+#[derive(Debug, Clone)]
+pub struct ExtractOptions {
+    /// Whether links inside string literals of source files are extracted
+    pub check_string_literals: bool,
+    /// Links whose URL matches any of these patterns are left out
+    pub exclude: Vec<Regex>,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            check_string_literals: true,
+            exclude: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// Represents a hyperlink found in a repository, along with its location.
 pub struct LinkInfo {
     /// The URL string. This should be a valid HTTP or HTTPS URL.
@@ -15,11 +125,24 @@ pub struct LinkInfo {
     pub file_path: String,
     /// The 1-based line number in the file where the URL was found.
     pub line_number: usize,
+    /// The 0-based byte offset of the URL within its line.
+    pub column: usize,
+    /// The 0-based offset of the URL within its line in Unicode code points, the way
+    /// editors and code scanning tools count columns.
+    #[serde(default)]
+    pub char_column: usize,
+    /// The lexical context the URL was found in.
+    pub context: LinkContext,
 }
 
+/// Links are equal if they are the same occurrence, so that every copy of a URL is
+/// kept and can be fixed.
 impl PartialEq for LinkInfo {
     fn eq(&self, other: &Self) -> bool {
         self.url == other.url
+            && self.file_path == other.file_path
+            && self.line_number == other.line_number
+            && self.column == other.column
     }
 }
 
@@ -28,60 +151,103 @@ impl Eq for LinkInfo {}
 impl std::hash::Hash for LinkInfo {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.url.hash(state);
+        self.file_path.hash(state);
+        self.line_number.hash(state);
+        self.column.hash(state);
     }
 }
 
 pub fn extract_links_from_repo(
     repo_manager: &RepoManager,
+    options: &ExtractOptions,
 ) -> Result<HashSet<LinkInfo>, git2::Error> {
-    let mut all_links = HashSet::new();
-    if let Ok(head) = repo_manager.get_repo().head()
-        && let Ok(tree) = head.peel_to_tree()
-    {
-        tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
-            if let Some(name) = entry.name() {
-                let file_path = if dir.is_empty() {
-                    name.to_string()
-                } else {
-                    format!("{dir}/{name}").replace("//", "/")
-                };
+    let files = read_head_files(repo_manager.get_repo())?;
+    Ok(find_links_in_files(files, options))
+}
 
-                if let Ok(blob) = entry.to_object(repo_manager.get_repo())
-                    && let Ok(blob) = blob.peel_to_blob()
-                    && let Ok(content) = String::from_utf8(blob.content().to_vec())
-                {
-                    let links = find_link_in_content(&content, file_path.clone());
-                    all_links.extend(links);
-                }
-            }
-            git2::TreeWalkResult::Ok
-        })?;
+/// Extracts the links from the files of a repository source.
+///
+/// # Arguments
+/// * `source` - The repository snapshot to read, e.g. a clone or the GitHub Trees API
+/// * `options` - Options controlling which links are extracted
+pub async fn extract_links(
+    source: &impl RepoSource,
+    options: &ExtractOptions,
+) -> Result<HashSet<LinkInfo>, SourceError> {
+    let files = source.read_files().await?;
+    Ok(find_links_in_files(files, options))
+}
+
+pub(crate) fn find_links_in_files(
+    files: Vec<SourceFile>,
+    options: &ExtractOptions,
+) -> HashSet<LinkInfo> {
+    let mut all_links = HashSet::new();
+    for file in &files {
+        all_links.extend(find_links_in_file(file, options));
     }
+    all_links
+}
 
-    Ok(all_links)
+/// Finds the links in a single file that the options allow.
+pub(crate) fn find_links_in_file(file: &SourceFile, options: &ExtractOptions) -> Vec<LinkInfo> {
+    find_link_in_content(&file.content, file.path.clone())
+        .into_iter()
+        .filter(|link| options.check_string_literals || link.context != LinkContext::StringLiteral)
+        .filter(|link| {
+            !options
+                .exclude
+                .iter()
+                .any(|pattern| pattern.is_match(&link.url))
+        })
+        .collect()
 }
 
-fn find_link_in_content(content: &str, file_path: String) -> HashSet<LinkInfo> {
+/// Finds the links in a file's content.
+///
+/// Source files are tokenized so that links in comments and string literals are tagged
+/// with their context. Links inside string literals keep their exact boundaries, since
+/// the literal already delimits them.
+pub(crate) fn find_link_in_content(content: &str, file_path: String) -> HashSet<LinkInfo> {
     let domain_regex = Regex::new(REGEX_DOMAIN).unwrap();
     let ip_address_regex = Regex::new(REGEX_IP_ADDRESS).unwrap();
+    let syntax = source_syntax(&file_path);
+    let mut in_block_comment = false;
     let mut result = HashSet::new();
 
     for (line_num, line) in content.lines().enumerate() {
-        for mat in domain_regex.find_iter(line) {
-            if ip_address_regex.is_match(mat.as_str()) {
-                continue;
-            }
+        let segments = match syntax {
+            Some(syntax) => tokenize_line(line, syntax, &mut in_block_comment),
+            None => vec![Segment {
+                context: LinkContext::from_file_path(&file_path),
+                offset: 0,
+                text: line,
+            }],
+        };
 
-            let url = mat
-                .as_str()
-                .trim_end_matches(&[')', '>', '.', ',', ';'][..])
-                .to_string();
+        for segment in segments {
+            for mat in domain_regex.find_iter(segment.text) {
+                if ip_address_regex.is_match(mat.as_str()) {
+                    continue;
+                }
 
-            result.insert(LinkInfo {
-                url,
-                file_path: file_path.clone(),
-                line_number: line_num + 1,
-            });
+                let url = match segment.context {
+                    LinkContext::StringLiteral => mat.as_str(),
+                    _ => mat
+                        .as_str()
+                        .trim_end_matches(&[')', '>', '.', ',', ';'][..]),
+                };
+
+                let column = segment.offset + mat.start();
+                result.insert(LinkInfo {
+                    url: url.to_string(),
+                    file_path: file_path.clone(),
+                    line_number: line_num + 1,
+                    column,
+                    char_column: line[..column].chars().count(),
+                    context: segment.context,
+                });
+            }
         }
     }
     result
@@ -107,8 +273,8 @@ mod tests {
         let file_path = "test.txt".to_string();
         let links = find_link_in_content(content, file_path);
 
-        // Should have exactly 3 unique URLs
-        assert_eq!(links.len(), 3, "Expected 3 unique URLs");
+        // Every occurrence is kept, so that each of them can be fixed
+        assert_eq!(links.len(), 5, "Expected 5 occurrences");
 
         // Verify each URL exists
         let urls: Vec<String> = links.iter().map(|link| link.url.clone()).collect();
@@ -126,6 +292,104 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_find_link_in_content_column() {
+        let content = "see https://example.com/a and (https://example.org/b)";
+
+        let links = find_link_in_content(content, "test.md".to_string());
+        let mut columns: Vec<(String, usize)> = links
+            .into_iter()
+            .map(|link| (link.url, link.column))
+            .collect();
+        columns.sort();
+
+        assert_eq!(
+            columns,
+            vec![
+                ("https://example.com/a".to_string(), 4),
+                ("https://example.org/b".to_string(), 31),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_link_in_source_file() {
+        let content = r#"
+        // Docs: https://example.com/docs.
+        const WIKI: &str = "https://en.wikipedia.org/wiki/Rust_(programming_language)";
+        "#;
+
+        let links = find_link_in_content(content, "src/lib.rs".to_string());
+        let mut links: Vec<(String, LinkContext)> = links
+            .into_iter()
+            .map(|link| (link.url, link.context))
+            .collect();
+        links.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(
+            links,
+            vec![
+                (
+                    "https://en.wikipedia.org/wiki/Rust_(programming_language)".to_string(),
+                    LinkContext::StringLiteral
+                ),
+                ("https://example.com/docs".to_string(), LinkContext::Comment),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_links_in_file_with_exclude() {
+        let file = SourceFile {
+            path: "README.md".to_string(),
+            content: "https://example.com/a https://localhost.test/b https://example.org/c"
+                .to_string(),
+            lossy: false,
+        };
+        let options = ExtractOptions {
+            exclude: vec![
+                Regex::new(r"^https://localhost\.").unwrap(),
+                Regex::new(r"example\.org").unwrap(),
+            ],
+            ..ExtractOptions::default()
+        };
+
+        let links = find_links_in_file(&file, &options);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].url, "https://example.com/a");
+    }
+
+    #[test]
+    fn test_link_context_by_file_type() {
+        let content = "https://example.com";
+        let context_of = |file_path: &str| {
+            find_link_in_content(content, file_path.to_string())
+                .into_iter()
+                .next()
+                .unwrap()
+                .context
+        };
+
+        assert_eq!(context_of("docs/README.md"), LinkContext::Markdown);
+        assert_eq!(context_of("Cargo.toml"), LinkContext::Config);
+        assert_eq!(context_of("LICENSE"), LinkContext::Text);
+    }
+
+    #[test]
+    fn test_fix_policy() {
+        let policy = FixPolicy::default();
+        assert!(policy.allows_fix(LinkContext::Markdown));
+        assert!(policy.allows_fix(LinkContext::Comment));
+        assert!(!policy.allows_fix(LinkContext::StringLiteral));
+
+        let policy = FixPolicy {
+            report_only: vec!["config".parse().unwrap(), "comment".parse().unwrap()],
+        };
+        assert!(policy.allows_fix(LinkContext::StringLiteral));
+        assert!(!policy.allows_fix(LinkContext::Config));
+        assert!(!policy.allows_fix(LinkContext::Comment));
+    }
+
     #[test]
     fn test_skip_ip_addresses() {
         let content = r#"
@@ -151,31 +415,40 @@ mod tests {
             url: "https://example.com".to_string(),
             file_path: "file1.txt".to_string(),
             line_number: 1,
+            column: 0,
+            char_column: 0,
+            context: LinkContext::Text,
         };
 
         let link2 = LinkInfo {
             url: "https://example.com".to_string(),
             file_path: "file2.txt".to_string(),
             line_number: 2,
+            column: 0,
+            char_column: 0,
+            context: LinkContext::Text,
         };
 
         links.insert(link1);
         links.insert(link2);
 
-        // Should only have one entry because URLs are the same
-        assert_eq!(links.len(), 1, "Expected only one unique URL entry");
+        // Should have two entries because the occurrences are in different places
+        assert_eq!(links.len(), 2, "Expected two occurrences");
 
-        // Different URL
+        // Same occurrence found again
         let link3 = LinkInfo {
-            url: "https://example.org".to_string(),
+            url: "https://example.com".to_string(),
             file_path: "file1.txt".to_string(),
             line_number: 1,
+            column: 0,
+            char_column: 0,
+            context: LinkContext::Text,
         };
 
         links.insert(link3);
 
-        // Should now have two entries because URLs are different
-        assert_eq!(links.len(), 2, "Expected two unique URL entries");
+        // Should still have two entries because the occurrence is already known
+        assert_eq!(links.len(), 2, "Expected two occurrences");
     }
 
     #[test]
@@ -188,7 +461,7 @@ mod tests {
             None,
         );
         let repo_manager = RepoManager::from(&github_url).unwrap();
-        let result = extract_links_from_repo(&repo_manager);
+        let result = extract_links_from_repo(&repo_manager, &ExtractOptions::default());
 
         assert!(result.is_ok(), "Expected branch to be found");
     }
@@ -224,7 +497,7 @@ mod tests {
             None,
         );
         let repo_manager = RepoManager::from(&github_url).unwrap();
-        let result = extract_links_from_repo(&repo_manager).unwrap();
+        let result = extract_links_from_repo(&repo_manager, &ExtractOptions::default()).unwrap();
 
         assert!(!result.is_empty(), "No links found in the repository");
 
@@ -252,7 +525,7 @@ mod tests {
             None,
         );
         let repo_manager = RepoManager::from(&github_url).unwrap();
-        let result = extract_links_from_repo(&repo_manager).unwrap();
+        let result = extract_links_from_repo(&repo_manager, &ExtractOptions::default()).unwrap();
 
         assert!(!result.is_empty(), "No links found in the repository");
 
@@ -0,0 +1,870 @@
+use crate::{GitHubUrl, LinkChecker, RepoManager, git::repo::TempDirGuard};
+use git2::{Repository, RepositoryInitOptions, Signature};
+use std::{fs, path::Path, time::SystemTime};
+use wiremock::matchers::{method, path, path_regex, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Host name that test links use; it is resolved to the mock server instead of DNS.
+const SITE_HOST: &str = "docs.example.com";
+
+/// A mock of the GitHub API surface queensac uses, together with a mock documentation
+/// site that links in fixture repositories point at.
+///
+/// Both are served by a single local wiremock server, so tests never reach GitHub or
+/// any other real host.
+pub(crate) struct MockGitHub {
+    pub server: MockServer,
+}
+
+impl MockGitHub {
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    /// The base URL to pass as `QUEENSAC_GITHUB_API_URL`.
+    pub fn api_url(&self) -> String {
+        self.server.uri()
+    }
+
+    /// The URL of a page on the mock documentation site.
+    pub fn site_url(&self, page: &str) -> String {
+        format!("http://{SITE_HOST}:{}{page}", self.server.address().port())
+    }
+
+    /// A link checker that resolves the mock documentation site to the mock server.
+    pub fn link_checker(&self) -> LinkChecker {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(5))
+            .redirect(reqwest::redirect::Policy::none())
+            .resolve(SITE_HOST, *self.server.address())
+            .build()
+            .unwrap();
+        LinkChecker::with_client(client)
+    }
+
+    /// Mounts the app installation listing and the installation access token endpoints.
+    pub async fn mount_app_installation(&self) {
+        Mock::given(method("GET"))
+            .and(path("/app/installations"))
+            .respond_with(json_response(
+                200,
+                include_str!("testdata/installations.json"),
+            ))
+            .mount(&self.server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/app/installations/1/access_tokens"))
+            .respond_with(json_response(
+                201,
+                include_str!("testdata/access_token.json"),
+            ))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Mounts the pull request endpoints of a repository without open pull requests.
+    pub async fn mount_pulls(&self, owner: &str, repo: &str) {
+        let pulls = format!("/repos/{owner}/{repo}/pulls");
+        Mock::given(method("GET"))
+            .and(path(pulls.as_str()))
+            .respond_with(json_response(200, "[]"))
+            .mount(&self.server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(pulls.as_str()))
+            .respond_with(json_response(
+                201,
+                include_str!("testdata/pull_request.json"),
+            ))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Mounts the pull request endpoints of a repository with open queensac pull requests,
+    /// each given by its number, branch, and the patch of its change to `README.md`.
+    ///
+    /// Commenting on, closing, and deleting the branch of any pull request succeeds.
+    pub async fn mount_open_pulls(&self, owner: &str, repo: &str, pulls: &[(u64, &str, &str)]) {
+        let user = user_json(owner);
+        let mut open_pulls = Vec::new();
+        for &(number, branch, patch) in pulls {
+            let mut pull: serde_json::Value =
+                serde_json::from_str(include_str!("testdata/pull_request.json")).unwrap();
+            pull["number"] = number.into();
+            pull["html_url"] = format!("https://github.com/{owner}/{repo}/pull/{number}").into();
+            pull["head"]["ref"] = branch.into();
+            pull["head"]["user"] = user.clone();
+            open_pulls.push(pull);
+
+            let files = serde_json::json!([{
+                "sha": "0000000000000000000000000000000000000000",
+                "filename": "README.md",
+                "status": "modified",
+                "additions": 1,
+                "deletions": 1,
+                "changes": 2,
+                "blob_url": null,
+                "raw_url": null,
+                "contents_url": format!("https://api.github.com/repos/{owner}/{repo}/contents/README.md"),
+                "patch": patch
+            }]);
+            Mock::given(method("GET"))
+                .and(path(format!("/repos/{owner}/{repo}/pulls/{number}/files")))
+                .respond_with(json_response(200, &files.to_string()))
+                .mount(&self.server)
+                .await;
+        }
+
+        let pulls_path = format!("/repos/{owner}/{repo}/pulls");
+        Mock::given(method("GET"))
+            .and(path(pulls_path.as_str()))
+            .respond_with(json_response(
+                200,
+                &serde_json::Value::from(open_pulls).to_string(),
+            ))
+            .mount(&self.server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(pulls_path.as_str()))
+            .respond_with(json_response(
+                201,
+                include_str!("testdata/pull_request.json"),
+            ))
+            .mount(&self.server)
+            .await;
+        let comment = serde_json::json!({
+            "id": 1,
+            "node_id": "MDEyOklzc3VlQ29tbWVudDE=",
+            "url": format!("https://api.github.com/repos/{owner}/{repo}/issues/comments/1"),
+            "html_url": format!("https://github.com/{owner}/{repo}/pull/1#issuecomment-1"),
+            "user": user,
+            "author_association": "OWNER",
+            "created_at": "2025-01-01T00:00:00Z"
+        });
+        Mock::given(method("POST"))
+            .and(path_regex(format!(
+                "^/repos/{owner}/{repo}/issues/[0-9]+/comments$"
+            )))
+            .respond_with(json_response(201, &comment.to_string()))
+            .mount(&self.server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path_regex(format!("^/repos/{owner}/{repo}/pulls/[0-9]+$")))
+            .respond_with(json_response(
+                200,
+                include_str!("testdata/pull_request.json"),
+            ))
+            .mount(&self.server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path_regex(format!(
+                "^/repos/{owner}/{repo}/git/refs/heads/"
+            )))
+            .respond_with(ResponseTemplate::new(204))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Mounts the fork endpoint of a repository, forking it to `fork_owner` with the given
+    /// clone URL.
+    ///
+    /// Like on GitHub, the fork is created asynchronously: looking it up fails `pending`
+    /// times before it exists.
+    pub async fn mount_fork(
+        &self,
+        owner: &str,
+        repo: &str,
+        fork_owner: &str,
+        clone_url: &str,
+        pending: u64,
+    ) {
+        let fork = serde_json::json!({
+            "id": 2,
+            "name": repo,
+            "full_name": format!("{fork_owner}/{repo}"),
+            "url": format!("https://api.github.com/repos/{fork_owner}/{repo}"),
+            "owner": user_json(fork_owner),
+            "clone_url": clone_url,
+            "fork": true
+        });
+        Mock::given(method("POST"))
+            .and(path(format!("/repos/{owner}/{repo}/forks")))
+            .respond_with(json_response(202, &fork.to_string()))
+            .mount(&self.server)
+            .await;
+
+        let fork_path = format!("/repos/{fork_owner}/{repo}");
+        Mock::given(method("GET"))
+            .and(path(fork_path.as_str()))
+            .respond_with(json_response(404, r#"{"message":"Not Found"}"#))
+            .up_to_n_times(pending)
+            .with_priority(1)
+            .mount(&self.server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(fork_path.as_str()))
+            .respond_with(json_response(200, &fork.to_string()))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Mounts the issue endpoints of a repository whose open issues are listed in pages of
+    /// the given issues, each given by its number and title.
+    ///
+    /// Updating an issue, creating one as number 100, and GraphQL mutations succeed.
+    pub async fn mount_issues(&self, owner: &str, repo: &str, pages: &[&[(u64, &str)]]) {
+        let issues_path = format!("/repos/{owner}/{repo}/issues");
+        for (index, page) in pages.iter().enumerate() {
+            let page_number = index + 1;
+            let items: Vec<_> = page
+                .iter()
+                .map(|&(number, title)| issue_json(owner, repo, number, title))
+                .collect();
+            let mut response = json_response(200, &serde_json::Value::from(items).to_string());
+            if page_number < pages.len() {
+                let next = format!(
+                    "{}{issues_path}?state=open&per_page=100&page={}",
+                    self.api_url(),
+                    page_number + 1
+                );
+                response =
+                    response.insert_header("link", format!("<{next}>; rel=\"next\"").as_str());
+            }
+            let mock = Mock::given(method("GET")).and(path(issues_path.as_str()));
+            let mock = if page_number == 1 {
+                mock.respond_with(response)
+            } else {
+                mock.and(query_param("page", page_number.to_string()))
+                    .respond_with(response)
+                    .with_priority(1)
+            };
+            mock.mount(&self.server).await;
+        }
+
+        Mock::given(method("PATCH"))
+            .and(path_regex(format!("^{issues_path}/[0-9]+$")))
+            .respond_with(json_response(
+                200,
+                &issue_json(owner, repo, 1, "🔗 Link health").to_string(),
+            ))
+            .mount(&self.server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(issues_path.as_str()))
+            .respond_with(json_response(
+                201,
+                &issue_json(owner, repo, 100, "🔗 Link health").to_string(),
+            ))
+            .mount(&self.server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(json_response(200, r#"{"data":{}}"#))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Mounts a page of the mock documentation site.
+    pub async fn mount_page(&self, page: &str, status: u16) {
+        Mock::given(method("GET"))
+            .and(path(page))
+            .respond_with(ResponseTemplate::new(status))
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Mounts a permanent redirect between pages of the mock documentation site.
+    pub async fn mount_redirect(&self, from: &str, to: &str) {
+        Mock::given(method("GET"))
+            .and(path(from))
+            .respond_with(
+                ResponseTemplate::new(301).insert_header("location", self.site_url(to).as_str()),
+            )
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Returns the bodies of the requests received on paths matching the given pattern.
+    pub async fn request_bodies(&self, http_method: &str, path_pattern: &str) -> Vec<String> {
+        let path_matcher = path_regex(path_pattern);
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|request| {
+                request.method.as_str() == http_method
+                    && wiremock::Match::matches(&path_matcher, request)
+            })
+            .map(|request| String::from_utf8_lossy(&request.body).into_owned())
+            .collect()
+    }
+}
+
+/// A GitHub issue object with the given number and title.
+fn issue_json(owner: &str, repo: &str, number: u64, title: &str) -> serde_json::Value {
+    let url = format!("https://api.github.com/repos/{owner}/{repo}/issues/{number}");
+    serde_json::json!({
+        "id": number,
+        "node_id": format!("I_kwDO{number}"),
+        "url": url,
+        "repository_url": format!("https://api.github.com/repos/{owner}/{repo}"),
+        "labels_url": format!("{url}/labels{{/name}}"),
+        "comments_url": format!("{url}/comments"),
+        "events_url": format!("{url}/events"),
+        "html_url": format!("https://github.com/{owner}/{repo}/issues/{number}"),
+        "number": number,
+        "state": "open",
+        "title": title,
+        "body": "",
+        "user": user_json(owner),
+        "labels": [],
+        "assignees": [],
+        "author_association": "OWNER",
+        "locked": false,
+        "comments": 0,
+        "created_at": "2025-01-01T00:00:00Z",
+        "updated_at": "2025-01-01T00:00:00Z"
+    })
+}
+
+/// A GitHub user object for the given login.
+fn user_json(login: &str) -> serde_json::Value {
+    serde_json::json!({
+        "login": login,
+        "id": 1,
+        "node_id": "MDQ6VXNlcjE=",
+        "avatar_url": "https://github.com/images/error/octocat_happy.gif",
+        "gravatar_id": "",
+        "url": "https://api.github.com/users/octocat",
+        "html_url": "https://github.com/octocat",
+        "followers_url": "https://api.github.com/users/octocat/followers",
+        "following_url": "https://api.github.com/users/octocat/following{/other_user}",
+        "gists_url": "https://api.github.com/users/octocat/gists{/gist_id}",
+        "starred_url": "https://api.github.com/users/octocat/starred{/owner}{/repo}",
+        "subscriptions_url": "https://api.github.com/users/octocat/subscriptions",
+        "organizations_url": "https://api.github.com/users/octocat/orgs",
+        "repos_url": "https://api.github.com/users/octocat/repos",
+        "events_url": "https://api.github.com/users/octocat/events{/privacy}",
+        "received_events_url": "https://api.github.com/users/octocat/received_events",
+        "type": "User",
+        "site_admin": false
+    })
+}
+
+fn json_response(status: u16, body: &str) -> ResponseTemplate {
+    ResponseTemplate::new(status)
+        .set_body_string(body)
+        .insert_header("content-type", "application/json")
+}
+
+/// A local repository with a single commit on `main`, cloned from a local bare `origin`.
+///
+/// Branches pushed by queensac land in the bare repository, where tests can inspect them.
+pub(crate) struct FixtureRepo {
+    origin: Repository,
+    _origin_guard: TempDirGuard,
+}
+
+impl FixtureRepo {
+    /// Creates the bare origin with the given files committed on `main`.
+    pub fn new(files: &[(&str, &str)]) -> Self {
+        let origin_guard = TempDirGuard::new(unique_temp_dir("origin")).unwrap();
+        let mut options = RepositoryInitOptions::new();
+        options.bare(true).initial_head("main");
+        let origin = Repository::init_opts(origin_guard.get_path(), &options).unwrap();
+
+        commit_files(&origin, files);
+
+        Self {
+            origin,
+            _origin_guard: origin_guard,
+        }
+    }
+
+    /// Returns the path of the bare origin, usable as its clone URL.
+    pub fn origin_url(&self) -> String {
+        self.origin.path().to_str().unwrap().to_string()
+    }
+
+    /// Clones the origin into a `RepoManager` for `reddevilmidzy/<repo>`.
+    pub fn clone_as(&self, repo: &str) -> RepoManager {
+        let guard = TempDirGuard::new(unique_temp_dir(repo)).unwrap();
+        let cloned =
+            Repository::clone(self.origin.path().to_str().unwrap(), guard.get_path()).unwrap();
+        let github_url = GitHubUrl::new(
+            "reddevilmidzy".to_string(),
+            repo.to_string(),
+            Some("main".to_string()),
+            None,
+        );
+
+        RepoManager::new(&github_url, cloned, guard)
+    }
+
+    /// Returns the names of the branches in the origin other than `main`.
+    pub fn pushed_branches(&self) -> Vec<String> {
+        self.origin
+            .branches(Some(git2::BranchType::Local))
+            .unwrap()
+            .filter_map(|branch| {
+                let (branch, _) = branch.ok()?;
+                branch.name().ok()?.map(str::to_string)
+            })
+            .filter(|name| name != "main")
+            .collect()
+    }
+
+    /// Reads a file as committed on a branch of the origin.
+    pub fn read_file(&self, branch: &str, file_path: &str) -> String {
+        let tree = self
+            .origin
+            .revparse_single(&format!("refs/heads/{branch}"))
+            .unwrap()
+            .peel_to_tree()
+            .unwrap();
+        let blob = tree
+            .get_path(Path::new(file_path))
+            .unwrap()
+            .to_object(&self.origin)
+            .unwrap()
+            .peel_to_blob()
+            .unwrap();
+
+        String::from_utf8(blob.content().to_vec()).unwrap()
+    }
+}
+
+/// Commits the given files as the initial commit of `main`.
+fn commit_files(repo: &Repository, files: &[(&str, &str)]) {
+    let signature = Signature::now("Redddy", "midzy0228@gmail.com").unwrap();
+    let mut builder = repo.treebuilder(None).unwrap();
+    for (file_path, content) in files {
+        let blob = repo.blob(content.as_bytes()).unwrap();
+        builder.insert(file_path, blob, 0o100644).unwrap();
+    }
+    let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+    repo.commit(
+        Some("refs/heads/main"),
+        &signature,
+        &signature,
+        "Initial commit",
+        &tree,
+        &[],
+    )
+    .unwrap();
+}
+
+fn unique_temp_dir(name: &str) -> std::path::PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let dir = std::env::temp_dir().join(format!("queensac_fixture/{name}_{nanos}"));
+    fs::create_dir_all(dir.parent().unwrap()).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExtractOptions, FileChange, GitHubAppConfig, PullRequestGenerator, check_links};
+    use serial_test::serial;
+
+    async fn scan_for_fixes(
+        repo_manager: &RepoManager,
+        link_checker: &LinkChecker,
+    ) -> Vec<FileChange> {
+        let report = check_links(repo_manager, &ExtractOptions::default(), link_checker, None)
+            .await
+            .unwrap();
+
+        report
+            .invalid_links
+            .into_iter()
+            .filter_map(|link| {
+                Some(FileChange {
+                    new_content: link.collect_link?,
+                    file_path: link.file_path,
+                    old_content: link.url,
+                    line_number: link.line_number,
+                    column: link.column,
+                })
+            })
+            .collect()
+    }
+
+    async fn app_pr_generator(
+        github: &MockGitHub,
+        repo_manager: RepoManager,
+    ) -> PullRequestGenerator {
+        // SAFETY: the test is serial, so no other thread reads the environment meanwhile.
+        unsafe { std::env::set_var("QUEENSAC_GITHUB_API_URL", github.api_url()) };
+        let app_config =
+            GitHubAppConfig::new(1, include_str!("testdata/app_private_key.pem").to_string());
+        let generator = PullRequestGenerator::builder(repo_manager, "main")
+            .github_app(app_config)
+            .link_checker(github.link_checker())
+            .build()
+            .await;
+        unsafe { std::env::remove_var("QUEENSAC_GITHUB_API_URL") };
+
+        generator.unwrap()
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_scan_fix_and_open_pull_request() {
+        let github = MockGitHub::start().await;
+        github.mount_app_installation().await;
+        github.mount_pulls("reddevilmidzy", "kingsac").await;
+        github.mount_redirect("/old-guide", "/new-guide").await;
+        github.mount_page("/new-guide", 200).await;
+        github.mount_page("/faq", 200).await;
+
+        let readme = format!(
+            "# kingsac\n\nSee the [guide]({}) and the [FAQ]({}).\n",
+            github.site_url("/old-guide"),
+            github.site_url("/faq")
+        );
+        let fixture = FixtureRepo::new(&[("README.md", &readme)]);
+        let repo_manager = fixture.clone_as("kingsac");
+
+        let fixes = scan_for_fixes(&repo_manager, &github.link_checker()).await;
+        assert_eq!(fixes.len(), 1);
+        assert_eq!(fixes[0].new_content, github.site_url("/new-guide"));
+
+        let generator = app_pr_generator(&github, repo_manager).await;
+        let pr_url = generator.create_fix_pr(fixes).await.unwrap();
+        assert_eq!(pr_url, "https://github.com/reddevilmidzy/kingsac/pull/1");
+
+        let branches = fixture.pushed_branches();
+        assert_eq!(branches.len(), 1);
+        assert!(branches[0].starts_with("queensac-"));
+        assert_eq!(
+            fixture.read_file(&branches[0], "README.md"),
+            readme.replace("/old-guide", "/new-guide")
+        );
+
+        let created = github
+            .request_bodies("POST", "^/repos/reddevilmidzy/kingsac/pulls$")
+            .await;
+        assert_eq!(created.len(), 1);
+        assert!(created[0].contains(&format!("\"head\":\"{}\"", branches[0])));
+        assert!(created[0].contains("\"base\":\"main\""));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_scan_fixes_every_occurrence() {
+        let github = MockGitHub::start().await;
+        github.mount_app_installation().await;
+        github.mount_pulls("reddevilmidzy", "kingsac").await;
+        github.mount_redirect("/old-guide", "/new-guide").await;
+        github.mount_page("/new-guide", 200).await;
+
+        let old_url = github.site_url("/old-guide");
+        let readme = format!("See the [guide]({old_url}) or [this]({old_url}).\n");
+        let guide = format!("# Guide\n\nMoved to {old_url}\n");
+        let fixture = FixtureRepo::new(&[("README.md", &readme), ("GUIDE.md", &guide)]);
+        let repo_manager = fixture.clone_as("kingsac");
+
+        let fixes = scan_for_fixes(&repo_manager, &github.link_checker()).await;
+        assert_eq!(fixes.len(), 3);
+        assert_eq!(github.request_bodies("GET", "^/old-guide$").await.len(), 1);
+
+        let generator = app_pr_generator(&github, repo_manager).await;
+        generator.create_fix_pr(fixes).await.unwrap();
+
+        let branches = fixture.pushed_branches();
+        assert_eq!(
+            fixture.read_file(&branches[0], "README.md"),
+            readme.replace("/old-guide", "/new-guide")
+        );
+        assert_eq!(
+            fixture.read_file(&branches[0], "GUIDE.md"),
+            guide.replace("/old-guide", "/new-guide")
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_fix_pr_closes_only_obsolete_pull_requests() {
+        let github = MockGitHub::start().await;
+        github.mount_app_installation().await;
+        github.mount_redirect("/old-guide", "/new-guide").await;
+        github.mount_page("/new-guide", 200).await;
+        github.mount_redirect("/old-api", "/newer-api").await;
+        github.mount_page("/newer-api", 200).await;
+
+        let patch = |old: &str, new: &str| {
+            format!(
+                "@@ -1 +1 @@\n-[link]({})\n+[link]({})",
+                github.site_url(old),
+                github.site_url(new)
+            )
+        };
+        let still_needed = patch("/old-guide", "/new-guide");
+        let fixed_by_humans = patch("/old-faq", "/new-faq");
+        let moved_again = patch("/old-api", "/new-api");
+        github
+            .mount_open_pulls(
+                "reddevilmidzy",
+                "kingsac",
+                &[
+                    (2, "queensac-guide", &still_needed),
+                    (3, "queensac-faq", &fixed_by_humans),
+                    (4, "queensac-api", &moved_again),
+                ],
+            )
+            .await;
+
+        let readme = format!(
+            "[guide]({})\n[api]({})\n",
+            github.site_url("/old-guide"),
+            github.site_url("/old-api")
+        );
+        let fixture = FixtureRepo::new(&[("README.md", &readme)]);
+        let repo_manager = fixture.clone_as("kingsac");
+
+        let fixes = scan_for_fixes(&repo_manager, &github.link_checker()).await;
+        assert_eq!(fixes.len(), 2);
+
+        let generator = app_pr_generator(&github, repo_manager).await;
+        let pr_url = generator.create_fix_pr(fixes).await.unwrap();
+
+        // The guide fix is already proposed, so the new pull request only fixes the API link
+        let branches = fixture.pushed_branches();
+        assert_eq!(branches.len(), 1);
+        assert_eq!(
+            fixture.read_file(&branches[0], "README.md"),
+            readme.replace("/old-api", "/newer-api")
+        );
+
+        let comments =
+            |number: u64| format!("^/repos/reddevilmidzy/kingsac/issues/{number}/comments$");
+        assert!(github.request_bodies("POST", &comments(2)).await.is_empty());
+        let fixed = github.request_bodies("POST", &comments(3)).await;
+        assert_eq!(fixed.len(), 1);
+        assert!(fixed[0].contains("were fixed on `main`"));
+        let moved = github.request_bodies("POST", &comments(4)).await;
+        assert_eq!(moved.len(), 1);
+        assert!(moved[0].contains("moved again"));
+        assert!(moved[0].contains(&pr_url));
+
+        assert!(
+            github
+                .request_bodies("DELETE", "/git/refs/heads/queensac-guide$")
+                .await
+                .is_empty()
+        );
+        assert_eq!(
+            github
+                .request_bodies("DELETE", "/git/refs/heads/queensac-(faq|api)$")
+                .await
+                .len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_stale_pr_is_closed_when_comment_fails() {
+        let github = MockGitHub::start().await;
+        github.mount_app_installation().await;
+        Mock::given(method("POST"))
+            .and(path_regex("/issues/[0-9]+/comments$"))
+            .respond_with(ResponseTemplate::new(500))
+            .with_priority(1)
+            .mount(&github.server)
+            .await;
+        github
+            .mount_open_pulls(
+                "reddevilmidzy",
+                "kingsac",
+                &[(2, "queensac-guide", "@@ -1 +1 @@\n-[a](x)\n+[a](y)")],
+            )
+            .await;
+
+        let fixture = FixtureRepo::new(&[("README.md", "# kingsac\n")]);
+        let generator = app_pr_generator(&github, fixture.clone_as("kingsac")).await;
+        let closed = generator
+            .close_stale_prs(None, "The links were fixed.")
+            .await
+            .unwrap();
+
+        assert_eq!(closed, 1);
+        assert_eq!(
+            github
+                .request_bodies("PATCH", "^/repos/reddevilmidzy/kingsac/pulls/2$")
+                .await
+                .len(),
+            1
+        );
+        assert_eq!(
+            github
+                .request_bodies("DELETE", "/git/refs/heads/queensac-guide$")
+                .await
+                .len(),
+            1
+        );
+    }
+
+    fn health_summary() -> crate::LinkCheckSummaryEvent {
+        crate::LinkCheckSummaryEvent {
+            total: 3,
+            valid: 2,
+            invalid: 1,
+            redirect: 0,
+            moved: 0,
+            undecodable_files: 0,
+        }
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_health_issue_is_found_on_a_later_page() {
+        let github = MockGitHub::start().await;
+        github.mount_app_installation().await;
+        github
+            .mount_issues(
+                "reddevilmidzy",
+                "kingsac",
+                &[&[(1, "Crash on startup")], &[(2, "🔗 Link health")]],
+            )
+            .await;
+
+        let fixture = FixtureRepo::new(&[("README.md", "# kingsac\n")]);
+        let generator = app_pr_generator(&github, fixture.clone_as("kingsac")).await;
+        generator
+            .update_health_issue(&health_summary(), &[])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            github
+                .request_bodies("PATCH", "^/repos/reddevilmidzy/kingsac/issues/2$")
+                .await
+                .len(),
+            1
+        );
+        assert!(
+            github
+                .request_bodies("POST", "^/repos/reddevilmidzy/kingsac/issues$")
+                .await
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_new_health_issue_is_pinned() {
+        let github = MockGitHub::start().await;
+        github.mount_app_installation().await;
+        github
+            .mount_issues("reddevilmidzy", "kingsac", &[&[(1, "Crash on startup")]])
+            .await;
+
+        let fixture = FixtureRepo::new(&[("README.md", "# kingsac\n")]);
+        let generator = app_pr_generator(&github, fixture.clone_as("kingsac")).await;
+        generator
+            .update_health_issue(&health_summary(), &[])
+            .await
+            .unwrap();
+
+        let pins = github.request_bodies("POST", "^/graphql$").await;
+        assert_eq!(pins.len(), 1);
+        let pin: serde_json::Value = serde_json::from_str(&pins[0]).unwrap();
+        assert_eq!(pin["variables"]["id"], "I_kwDO100");
+        assert!(!pin["query"].as_str().unwrap().contains("I_kwDO100"));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_fix_pr_is_not_duplicated() {
+        let github = MockGitHub::start().await;
+        github.mount_app_installation().await;
+        github.mount_redirect("/old-guide", "/new-guide").await;
+        github.mount_page("/new-guide", 200).await;
+
+        let patch = format!(
+            "@@ -1 +1 @@\n-[guide]({})\n+[guide]({})",
+            github.site_url("/old-guide"),
+            github.site_url("/new-guide")
+        );
+        github
+            .mount_open_pulls("reddevilmidzy", "kingsac", &[(2, "queensac-guide", &patch)])
+            .await;
+
+        let readme = format!("[guide]({})\n", github.site_url("/old-guide"));
+        let fixture = FixtureRepo::new(&[("README.md", &readme)]);
+        let repo_manager = fixture.clone_as("kingsac");
+
+        let fixes = scan_for_fixes(&repo_manager, &github.link_checker()).await;
+        let generator = app_pr_generator(&github, repo_manager).await;
+        let pr_url = generator.create_fix_pr(fixes).await.unwrap();
+
+        assert_eq!(pr_url, "https://github.com/reddevilmidzy/kingsac/pull/2");
+        assert!(fixture.pushed_branches().is_empty());
+        assert!(
+            github
+                .request_bodies("POST", "^/repos/reddevilmidzy/kingsac/pulls$")
+                .await
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_installation_token_is_shared_between_generators() {
+        let github = MockGitHub::start().await;
+        github.mount_app_installation().await;
+        let fixture = FixtureRepo::new(&[("README.md", "# tokensac\n")]);
+
+        app_pr_generator(&github, fixture.clone_as("tokensac")).await;
+        app_pr_generator(&github, fixture.clone_as("tokensac")).await;
+
+        let minted = github
+            .request_bodies("POST", "^/app/installations/1/access_tokens$")
+            .await;
+        assert_eq!(minted.len(), 1);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_scan_skips_broken_replacement() {
+        let github = MockGitHub::start().await;
+        github.mount_app_installation().await;
+        github.mount_pulls("reddevilmidzy", "kingsac").await;
+        github.mount_redirect("/old-guide", "/new-guide").await;
+        github.mount_page("/new-guide", 200).await;
+        github.mount_redirect("/old-api", "/gone-api").await;
+        github.mount_page("/gone-api", 404).await;
+
+        let readme = format!(
+            "[guide]({})\n[api]({})\n",
+            github.site_url("/old-guide"),
+            github.site_url("/old-api")
+        );
+        let fixture = FixtureRepo::new(&[("README.md", &readme)]);
+        let repo_manager = fixture.clone_as("kingsac");
+
+        let fixes = scan_for_fixes(&repo_manager, &github.link_checker()).await;
+        assert_eq!(fixes.len(), 2);
+
+        let generator = app_pr_generator(&github, repo_manager).await;
+        generator.create_fix_pr(fixes).await.unwrap();
+
+        let branches = fixture.pushed_branches();
+        assert_eq!(
+            fixture.read_file(&branches[0], "README.md"),
+            readme.replace("/old-guide", "/new-guide")
+        );
+        let created = github
+            .request_bodies("POST", "^/repos/reddevilmidzy/kingsac/pulls$")
+            .await;
+        assert!(created[0].contains("Skipped replacements"));
+    }
+}
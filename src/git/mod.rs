@@ -1,11 +1,30 @@
+mod bitbucket;
+mod change_request;
+mod cooldown;
 mod file_tracker;
+mod gitlab;
 mod link_extractor;
+#[cfg(test)]
+pub(crate) mod mock_github;
+mod path_rules;
 mod pr_generator;
 mod repo;
+mod repo_cache;
+mod signing;
+mod source;
+mod tokenizer;
 mod url;
 
+pub use bitbucket::*;
+pub use change_request::*;
+pub use cooldown::*;
 pub use file_tracker::*;
+pub use gitlab::*;
 pub use link_extractor::*;
+pub use path_rules::*;
 pub use pr_generator::*;
 pub use repo::*;
+pub use repo_cache::*;
+pub use signing::*;
+pub use source::*;
 pub use url::*;
@@ -0,0 +1,201 @@
+use crate::RepoManager;
+use regex::Regex;
+use tracing::error;
+
+/// File in the repository root listing paths queensac must never modify.
+const PROTECTED_PATHS_FILE: &str = ".queensacignore";
+
+/// Locations GitHub looks for a CODEOWNERS file, in order of precedence.
+const CODEOWNERS_FILES: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// A gitignore-style path pattern, as used by `.queensacignore` and CODEOWNERS files.
+///
+/// Patterns without a slash match a file or directory name at any depth, patterns
+/// containing a slash are relative to the repository root. `*` matches within a path
+/// segment, `**` matches across segments, and a pattern matching a directory also
+/// matches everything inside it.
+#[derive(Debug, Clone)]
+struct PathPattern {
+    regex: Regex,
+}
+
+impl PathPattern {
+    fn new(pattern: &str) -> Option<Self> {
+        let directory_only = pattern.ends_with('/');
+        let pattern = pattern.trim_end_matches('/');
+        let anchored = pattern.contains('/');
+        let pattern = pattern.trim_start_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+
+        let mut regex = String::from(if anchored { "^" } else { "^(.*/)?" });
+        let mut rest = pattern;
+        while let Some(c) = rest.chars().next() {
+            if let Some(after) = rest.strip_prefix("**/") {
+                regex.push_str("(.*/)?");
+                rest = after;
+                continue;
+            }
+            if let Some(after) = rest.strip_prefix("**") {
+                regex.push_str(".*");
+                rest = after;
+                continue;
+            }
+            match c {
+                '*' => regex.push_str("[^/]*"),
+                '?' => regex.push_str("[^/]"),
+                _ => regex.push_str(&regex::escape(&c.to_string())),
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+        regex.push_str(if directory_only { "/.*$" } else { "(/.*)?$" });
+
+        Regex::new(&regex).ok().map(|regex| Self { regex })
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        self.regex.is_match(path.trim_start_matches("./"))
+    }
+}
+
+/// Paths that queensac must never modify, such as changelogs or vendored documentation.
+///
+/// The patterns are read from the `.queensacignore` file in the repository root, one
+/// gitignore-style pattern per line. Empty lines and lines starting with `#` are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct ProtectedPaths {
+    patterns: Vec<PathPattern>,
+}
+
+impl ProtectedPaths {
+    /// Parses protected path patterns from the content of a `.queensacignore` file.
+    pub fn parse(content: &str) -> Self {
+        let patterns = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(PathPattern::new)
+            .collect();
+
+        Self { patterns }
+    }
+
+    /// Reads the protected paths of a cloned repository.
+    ///
+    /// Returns an empty set if the repository has no `.queensacignore` file.
+    pub fn from_repo(repo_manager: &RepoManager) -> Self {
+        let path = repo_manager.get_repo_path().join(PROTECTED_PATHS_FILE);
+        match std::fs::read_to_string(path) {
+            Ok(content) => Self::parse(&content),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Returns whether the given repository-relative path must not be modified.
+    pub fn is_protected(&self, file_path: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| pattern.matches(file_path))
+    }
+}
+
+/// The owners of the repository's files, as listed in its CODEOWNERS file.
+#[derive(Debug, Clone, Default)]
+pub struct CodeOwners {
+    rules: Vec<(PathPattern, Vec<String>)>,
+}
+
+impl CodeOwners {
+    /// Parses the content of a CODEOWNERS file.
+    pub fn parse(content: &str) -> Self {
+        let rules = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                let pattern = PathPattern::new(parts.next()?)?;
+                let owners = parts.map(str::to_string).collect();
+                Some((pattern, owners))
+            })
+            .collect();
+
+        Self { rules }
+    }
+
+    /// Reads the CODEOWNERS file of a cloned repository.
+    ///
+    /// Returns no owners if the repository has no CODEOWNERS file.
+    pub fn from_repo(repo_manager: &RepoManager) -> Self {
+        let repo_path = repo_manager.get_repo_path();
+        for file in CODEOWNERS_FILES {
+            match std::fs::read_to_string(repo_path.join(file)) {
+                Ok(content) => return Self::parse(&content),
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    error!("Failed to read {}: {}", file, e);
+                }
+                Err(_) => {}
+            }
+        }
+
+        Self::default()
+    }
+
+    /// Returns the owners of a repository-relative path.
+    ///
+    /// As on GitHub, the last matching rule takes precedence.
+    pub fn owners_of(&self, file_path: &str) -> &[String] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.matches(file_path))
+            .map(|(_, owners)| owners.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_pattern() {
+        let pattern = PathPattern::new("CHANGELOG.md").unwrap();
+        assert!(pattern.matches("CHANGELOG.md"));
+        assert!(pattern.matches("crates/core/CHANGELOG.md"));
+        assert!(!pattern.matches("CHANGELOG.md.bak"));
+
+        let pattern = PathPattern::new("/vendor/").unwrap();
+        assert!(pattern.matches("vendor/lib/README.md"));
+        assert!(!pattern.matches("src/vendor/README.md"));
+
+        let pattern = PathPattern::new("docs/**/*.md").unwrap();
+        assert!(pattern.matches("docs/intro.md"));
+        assert!(pattern.matches("docs/guide/setup.md"));
+        assert!(!pattern.matches("docs/guide/setup.rs"));
+    }
+
+    #[test]
+    fn test_protected_paths() {
+        let protected = ProtectedPaths::parse("# generated files\nCHANGELOG.md\n\nthird_party/\n");
+
+        assert!(protected.is_protected("CHANGELOG.md"));
+        assert!(protected.is_protected("third_party/docs/index.md"));
+        assert!(!protected.is_protected("README.md"));
+    }
+
+    #[test]
+    fn test_code_owners_last_match_wins() {
+        let owners = CodeOwners::parse(
+            "* @reddevilmidzy\n/docs/ @reddevilmidzy/docs-team user@example.com\n",
+        );
+
+        assert_eq!(owners.owners_of("src/main.rs"), ["@reddevilmidzy"]);
+        assert_eq!(
+            owners.owners_of("docs/intro.md"),
+            ["@reddevilmidzy/docs-team", "user@example.com"]
+        );
+        assert!(CodeOwners::default().owners_of("README.md").is_empty());
+    }
+}
@@ -1,7 +1,21 @@
-use crate::RepoManager;
-
-use octocrab::{Octocrab, models::InstallationToken, params::apps::CreateInstallationAccessToken};
-use std::{path::PathBuf, time::SystemTime};
+use crate::{
+    CodeOwners, GitHubUrl, InvalidLinkInfo, LinkCheckResult, LinkCheckSummaryEvent, LinkChecker,
+    Locale, Message, ProtectedPaths, RepoManager, find_link_in_content,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use octocrab::{
+    DefaultOctocrabBuilderConfig, NoAuth, NoSvc, NotLayerReady, Octocrab, OctocrabBuilder,
+    models::{InstallationToken, issues::Issue, pulls::PullRequest},
+    params,
+    params::apps::CreateInstallationAccessToken,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::{LazyLock, Mutex},
+    time::{Duration, SystemTime},
+};
 use thiserror::Error;
 use tracing::{error, info};
 use url::Url;
@@ -13,10 +27,16 @@ pub enum PrError {
     Git(#[from] git2::Error),
     #[error("GitHub API error: {0}")]
     GitHub(String),
+    #[error("GitLab API error: {0}")]
+    GitLab(String),
+    #[error("Bitbucket API error: {0}")]
+    Bitbucket(String),
     #[error("File operation failed: {0}")]
     File(String),
     #[error("Configuration error: {0}")]
     Config(String),
+    #[error("Invalid commit trailer `{0}`, expected `Key: value`")]
+    InvalidTrailer(String),
 }
 
 /// Represents a file change to be included in a pull request.
@@ -26,6 +46,8 @@ pub struct FileChange {
     pub old_content: String,
     pub new_content: String,
     pub line_number: usize,
+    /// The 0-based byte offset of `old_content` within the line
+    pub column: usize,
 }
 
 /// GitHub App configuration for authentication.
@@ -41,9 +63,194 @@ pub struct PullRequestGenerator {
     base_branch: String,
     octocrab: Octocrab,
     access_token: String,
+    /// The GitHub App the access token is an installation token of, used to renew it
+    app_config: Option<GitHubAppConfig>,
+    /// The owner of the fork the fix branch is pushed to, `None` when pushing to the upstream repository
+    fork_owner: Option<String>,
+    /// Whether the owners of the changed files in CODEOWNERS are requested as reviewers
+    codeowner_reviews: bool,
+    /// Trailers appended to fix commit messages, e.g. `Signed-off-by: ...`
+    commit_trailers: Vec<String>,
+    /// The checker used to verify replacement URLs before they are applied
+    link_checker: LinkChecker,
+    /// The language of pull request titles and descriptions
+    locale: Locale,
+}
+
+/// Builds a [`PullRequestGenerator`], which is the only way to configure one beyond its
+/// credentials.
+///
+/// The generator authenticates with the credentials of the environment, as
+/// [`PullRequestGenerator::from_env`] does, unless a GitHub App or a token for the
+/// fork-based workflow is given.
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example(repo_manager: queensac::RepoManager) -> Result<(), queensac::PrError> {
+/// use queensac::{Locale, PullRequestGenerator};
+///
+/// let generator = PullRequestGenerator::builder(repo_manager, "main")
+///     .locale(Locale::Ko)
+///     .sign_off(true)
+///     .trailer("Refs: #42")
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct PullRequestGeneratorBuilder {
+    repo_manager: RepoManager,
+    base_branch: String,
+    credentials: Credentials,
+    codeowner_reviews: bool,
+    sign_off: bool,
+    trailers: Vec<String>,
+    link_checker: Option<LinkChecker>,
+    locale: Locale,
+}
+
+/// How a built [`PullRequestGenerator`] authenticates with GitHub.
+enum Credentials {
+    Env,
+    App(GitHubAppConfig),
+    /// A personal access token for the fork-based workflow
+    Fork(String),
+}
+
+impl PullRequestGeneratorBuilder {
+    /// Authenticates as a GitHub App installed on the repository.
+    pub fn github_app(mut self, app_config: GitHubAppConfig) -> Self {
+        self.credentials = Credentials::App(app_config);
+        self
+    }
+
+    /// Contributes through a fork created with a personal access token, see
+    /// [`PullRequestGenerator::new_with_fork`].
+    pub fn fork_with_token(mut self, github_token: impl Into<String>) -> Self {
+        self.credentials = Credentials::Fork(github_token.into());
+        self
+    }
+
+    /// Requests the CODEOWNERS of the changed files as reviewers of fix pull requests.
+    pub fn codeowner_reviews(mut self, enabled: bool) -> Self {
+        self.codeowner_reviews = enabled;
+        self
+    }
+
+    /// Adds a `Signed-off-by` trailer for the bot to fix commits, for projects that require
+    /// a Developer Certificate of Origin sign-off.
+    pub fn sign_off(mut self, enabled: bool) -> Self {
+        self.sign_off = enabled;
+        self
+    }
+
+    /// Adds a trailer, such as `Co-authored-by: Name <email>`, to fix commits.
+    ///
+    /// The trailer must be in `Key: value` form, which is validated by [`build`](Self::build).
+    pub fn trailer(mut self, trailer: impl Into<String>) -> Self {
+        self.trailers.push(trailer.into());
+        self
+    }
+
+    /// Sets the checker used to verify replacement URLs before they are applied.
+    pub fn link_checker(mut self, link_checker: LinkChecker) -> Self {
+        self.link_checker = Some(link_checker);
+        self
+    }
+
+    /// Sets the language of pull request titles and descriptions.
+    pub fn locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Authenticates with GitHub and creates the `PullRequestGenerator`.
+    ///
+    /// Invalid trailers are reported before anything is requested from GitHub.
+    pub async fn build(self) -> Result<PullRequestGenerator, PrError> {
+        for trailer in &self.trailers {
+            validate_trailer(trailer)?;
+        }
+
+        let mut generator = match self.credentials {
+            Credentials::Env => {
+                PullRequestGenerator::from_env(self.repo_manager, self.base_branch).await?
+            }
+            Credentials::App(app_config) => {
+                PullRequestGenerator::new(self.repo_manager, app_config, self.base_branch).await?
+            }
+            Credentials::Fork(github_token) => {
+                PullRequestGenerator::new_with_fork(
+                    self.repo_manager,
+                    github_token,
+                    self.base_branch,
+                )
+                .await?
+            }
+        };
+        generator.codeowner_reviews = self.codeowner_reviews;
+        if self.sign_off {
+            generator.commit_trailers.push(sign_off_trailer());
+        }
+        generator.commit_trailers.extend(self.trailers);
+        if let Some(link_checker) = self.link_checker {
+            generator.link_checker = link_checker;
+        }
+        generator.locale = self.locale;
+        Ok(generator)
+    }
 }
 
+/// Installation tokens are renewed when they expire within this many seconds.
+const TOKEN_RENEWAL_MARGIN_SECS: i64 = 5 * 60;
+
+/// Name the fix commits are authored under.
+pub(crate) const AUTHOR_NAME: &str = "queensac[bot]";
+
+/// Email the fix commits are authored under.
+pub(crate) const AUTHOR_EMAIL: &str = "218335951+queensac[bot]@users.noreply.github.com";
+
+/// Name of the git remote that points at the fork in the fork-based workflow.
+const FORK_REMOTE: &str = "fork";
+
+/// How many times a newly created fork is looked up before giving up on it.
+const FORK_POLL_ATTEMPTS: u32 = 10;
+
+/// Delay between lookups of a newly created fork.
+const FORK_POLL_DELAY: Duration = Duration::from_secs(3);
+
+/// Prefix of the branches queensac creates for its pull requests.
+pub(crate) const BRANCH_PREFIX: &str = "queensac-";
+
+/// Title of the issue queensac keeps up to date with the repository's link health.
+const HEALTH_ISSUE_TITLE: &str = "🔗 Link health";
+
+/// Marker of the hidden comment storing broken link counts of previous runs in the health issue.
+const TREND_MARKER: &str = "<!-- queensac-trend:";
+
+/// The maximum number of runs kept in the health issue's trend.
+const MAX_TREND_POINTS: usize = 30;
+
+/// Name of the check run queensac publishes on the base branch.
+const CHECK_RUN_NAME: &str = "queensac";
+
+/// The maximum number of annotations the Checks API accepts per request.
+const MAX_ANNOTATIONS_PER_REQUEST: usize = 50;
+
 impl GitHubAppConfig {
+    /// Creates a GitHub App configuration.
+    ///
+    /// # Arguments
+    /// * `app_id` - The GitHub App ID
+    /// * `private_key` - The GitHub App private key (PEM format)
+    pub fn new(app_id: u64, private_key: String) -> Self {
+        Self {
+            app_id,
+            private_key,
+        }
+    }
+
     /// Creates a GitHub App configuration from environment variables.
     ///
     /// # Environment Variables
@@ -64,6 +271,27 @@ impl GitHubAppConfig {
 }
 
 impl PullRequestGenerator {
+    /// Returns a builder for a `PullRequestGenerator`.
+    ///
+    /// # Arguments
+    /// * `repo_manager` - The repository manager instance
+    /// * `base_branch` - The base branch for the pull request
+    pub fn builder(
+        repo_manager: RepoManager,
+        base_branch: impl Into<String>,
+    ) -> PullRequestGeneratorBuilder {
+        PullRequestGeneratorBuilder {
+            repo_manager,
+            base_branch: base_branch.into(),
+            credentials: Credentials::Env,
+            codeowner_reviews: false,
+            sign_off: false,
+            trailers: Vec::new(),
+            link_checker: None,
+            locale: Locale::default(),
+        }
+    }
+
     /// Creates a new PullRequestGenerator with GitHub App authentication.
     ///
     /// # Arguments
@@ -75,69 +303,199 @@ impl PullRequestGenerator {
         app_config: GitHubAppConfig,
         base_branch: String,
     ) -> Result<Self, PrError> {
-        let key = jsonwebtoken::EncodingKey::from_rsa_pem(app_config.private_key.as_bytes())
-            .map_err(|e| PrError::Config(format!("Failed to parse private key: {e}")))?;
+        let access_token = installation_token(&app_config, repo_manager.get_github_url()).await?;
+        Self::with_access_token(
+            repo_manager,
+            access_token,
+            base_branch,
+            Some(app_config),
+            None,
+        )
+    }
 
-        let octocrab = Octocrab::builder()
-            .app(app_config.app_id.into(), key)
+    /// Creates a new PullRequestGenerator that contributes through a fork.
+    ///
+    /// Forks the target repository with the given personal access token, adds the fork
+    /// as a remote of the cloned repository, and opens cross-repository pull requests
+    /// from it. This allows fixing repositories where the GitHub App is not installed.
+    ///
+    /// # Arguments
+    /// * `repo_manager` - The repository manager instance
+    /// * `github_token` - A personal access token allowed to fork and open pull requests
+    /// * `base_branch` - The base branch for the pull request
+    pub async fn new_with_fork(
+        repo_manager: RepoManager,
+        github_token: String,
+        base_branch: String,
+    ) -> Result<Self, PrError> {
+        Self::with_fork(repo_manager, github_token, base_branch, FORK_POLL_DELAY).await
+    }
+
+    async fn with_fork(
+        repo_manager: RepoManager,
+        github_token: String,
+        base_branch: String,
+        poll_delay: Duration,
+    ) -> Result<Self, PrError> {
+        let octocrab = octocrab_builder()?
+            .personal_token(github_token.clone())
             .build()
             .map_err(|e| PrError::Config(format!("Failed to build Octocrab instance: {e}")))?;
 
-        let installations = octocrab
-            .apps()
-            .installations()
+        let github_url = repo_manager.get_github_url();
+        let fork = octocrab
+            .repos(github_url.owner(), github_url.repo())
+            .create_fork()
             .send()
             .await
-            .map_err(|e| PrError::GitHub(format!("Failed to get installations: {e}")))?;
+            .map_err(|e| PrError::GitHub(format!("Failed to fork repository: {e}")))?;
+
+        let fork_owner = fork
+            .owner
+            .map(|owner| owner.login)
+            .ok_or_else(|| PrError::GitHub("Fork has no owner".to_string()))?;
+        let fork_url = fork
+            .clone_url
+            .ok_or_else(|| PrError::GitHub("Fork has no clone URL".to_string()))?;
+
+        // GitHub creates forks asynchronously, so the fork may not accept pushes yet.
+        let mut attempt = 1;
+        while let Err(e) = octocrab.repos(&fork_owner, &fork.name).get().await {
+            if attempt == FORK_POLL_ATTEMPTS {
+                return Err(PrError::GitHub(format!(
+                    "Fork {}/{} is not ready: {e}",
+                    fork_owner, fork.name
+                )));
+            }
+            info!(
+                "Waiting for fork {}/{} to be created",
+                fork_owner, fork.name
+            );
+            tokio::time::sleep(poll_delay).await;
+            attempt += 1;
+        }
 
-        let installation = installations
-            .into_iter()
-            .find(|inst| {
-                inst.account
-                    .login
-                    .eq_ignore_ascii_case(repo_manager.get_github_url().owner())
-            })
-            .ok_or_else(|| PrError::GitHub("No GitHub App installation found".to_string()))?;
+        repo_manager.add_remote(FORK_REMOTE, fork_url.as_str())?;
+        info!("Using fork {}/{}", fork_owner, github_url.repo());
 
-        let mut create_access_token = CreateInstallationAccessToken::default();
-        create_access_token.repositories = vec![repo_manager.get_github_url().repo().to_string()];
+        Self::with_access_token(
+            repo_manager,
+            github_token,
+            base_branch,
+            None,
+            Some(fork_owner),
+        )
+    }
+
+    /// Creates a new PullRequestGenerator using credentials from environment variables.
+    ///
+    /// The GitHub App is tried first. If it is not configured or not installed on the
+    /// target repository, falls back to the fork-based workflow when a personal access
+    /// token is available.
+    ///
+    /// # Environment Variables
+    /// * `QUEENSAC_APP_ID`, `QUEENSAC_APP_PRIVATE_KEY` - See [`GitHubAppConfig::from_env`]
+    /// * `QUEENSAC_GITHUB_TOKEN` - Personal access token used for the fork-based fallback
+    /// * `QUEENSAC_GITHUB_API_URL` - Base URL of the GitHub API, for GitHub Enterprise Server
+    pub async fn from_env(repo_manager: RepoManager, base_branch: String) -> Result<Self, PrError> {
+        let app_error = match GitHubAppConfig::from_env() {
+            Ok(app_config) => {
+                match installation_token(&app_config, repo_manager.get_github_url()).await {
+                    Ok(access_token) => {
+                        return Self::with_access_token(
+                            repo_manager,
+                            access_token,
+                            base_branch,
+                            Some(app_config),
+                            None,
+                        );
+                    }
+                    Err(e) => e,
+                }
+            }
+            Err(e) => e,
+        };
 
-        let access_token_url =
-            Url::parse(installation.access_tokens_url.as_ref().ok_or_else(|| {
-                PrError::GitHub("Missing access_token_url in installation".to_string())
-            })?)
-            .map_err(|e| PrError::GitHub(format!("Failed to parse access token URL: {e}")))?;
+        let Ok(github_token) = read_env_var("QUEENSAC_GITHUB_TOKEN") else {
+            return Err(app_error);
+        };
 
-        let access_token: InstallationToken = octocrab
-            .post(access_token_url.path(), Some(&create_access_token))
-            .await
-            .map_err(|e| {
-                PrError::GitHub(format!("Failed to create installation access token: {e}"))
-            })?;
+        info!(
+            "GitHub App unavailable ({}), falling back to fork-based workflow",
+            app_error
+        );
+        Self::new_with_fork(repo_manager, github_token, base_branch).await
+    }
 
-        let octocrab = Octocrab::builder()
-            .personal_token(access_token.token.clone())
+    fn with_access_token(
+        repo_manager: RepoManager,
+        access_token: String,
+        base_branch: String,
+        app_config: Option<GitHubAppConfig>,
+        fork_owner: Option<String>,
+    ) -> Result<Self, PrError> {
+        let octocrab = octocrab_builder()?
+            .personal_token(access_token.clone())
             .build()
             .map_err(|e| PrError::GitHub(format!("Failed to build Octocrab instance: {e}")))?;
-        let token_string = access_token.token;
 
         Ok(Self {
             repo_manager,
             base_branch,
             octocrab,
-            access_token: token_string,
+            access_token,
+            app_config,
+            fork_owner,
+            codeowner_reviews: false,
+            commit_trailers: Vec::new(),
+            link_checker: LinkChecker::default(),
+            locale: Locale::default(),
         })
     }
 
     /// Creates a pull request with link fixes.
     ///
+    /// Each replacement URL is checked before being applied. Fixes whose replacement is itself
+    /// broken are not applied and are listed in the pull request description instead.
+    ///
+    /// Fixes that an open queensac pull request already makes are left out, and if that
+    /// leaves none, no pull request is created and the open one is returned. Open queensac
+    /// pull requests that make none of the fixes are closed, with a comment saying whether
+    /// their links were fixed in the meantime or have moved again.
+    ///
     /// # Arguments
     /// * `fixes` - The list of file changes to apply
     pub async fn create_fix_pr(&self, fixes: Vec<FileChange>) -> Result<String, PrError> {
+        let (fixes, skipped) = verify_fixes(&self.link_checker, fixes).await;
+
+        let open_prs = self.open_fix_prs().await.unwrap_or_else(|e| {
+            error!("Failed to load open PRs: {}", e);
+            Vec::new()
+        });
+        let broken_urls: HashSet<String> = fixes
+            .iter()
+            .chain(&skipped)
+            .map(|fix| fix.old_content.clone())
+            .collect();
+        let (needed, obsolete): (Vec<OpenFixPr>, Vec<OpenFixPr>) = open_prs
+            .into_iter()
+            .partition(|pr| fixes.iter().chain(&skipped).any(|fix| pr.proposes(fix)));
+        let fixes: Vec<FileChange> = fixes
+            .into_iter()
+            .filter(|fix| !needed.iter().any(|pr| pr.proposes(fix)))
+            .collect();
+        if let Some(pr) = needed.first()
+            && fixes.is_empty()
+        {
+            info!("All fixes are already proposed in {}", pr.url);
+            self.close_obsolete_prs(&obsolete, &broken_urls, None).await;
+            return Ok(pr.url.clone());
+        }
+
         let branch_name = generate_branch_name();
         self.create_branch(&branch_name).await?;
 
-        let changes = self.apply_fixes(fixes).await?;
+        let changes = apply_fixes(&self.repo_manager, fixes).await?;
 
         // Check if there are any changes before proceeding with commit, push, and PR
         if changes.is_empty() {
@@ -149,106 +507,427 @@ impl PullRequestGenerator {
 
         self.push_to_remote(branch_name.as_str()).await?;
 
+        let reviewers = if self.codeowner_reviews {
+            find_reviewers(&CodeOwners::from_repo(&self.repo_manager), &changes)
+        } else {
+            Vec::new()
+        };
         let pr_url = self
-            .generate_pull_request_via_api(branch_name.as_str())
+            .generate_pull_request_via_api(branch_name.as_str(), &skipped, &reviewers)
             .await?;
 
+        self.close_obsolete_prs(&obsolete, &broken_urls, Some(&pr_url))
+            .await;
+
         info!("Successfully created PR: {}", pr_url);
         Ok(pr_url)
     }
 
-    /// Creates a new feature branch from the current branch.
-    async fn create_branch(&self, branch_name: &str) -> Result<(), PrError> {
-        self.repo_manager.create_branch(branch_name).await?;
-        self.repo_manager.checkout_branch(branch_name).await?;
+    /// Previews the changes a fix pull request would make, without committing or pushing.
+    ///
+    /// The fixes are verified and applied to the working directory of the cloned repository
+    /// exactly as [`PullRequestGenerator::create_fix_pr`] would, the resulting unified diff is
+    /// captured, and the working directory is restored afterwards. No GitHub credentials are needed.
+    ///
+    /// # Arguments
+    /// * `repo_manager` - The repository to apply the fixes to
+    /// * `fixes` - The list of file changes to preview
+    ///
+    /// # Returns
+    /// The unified diff of the changes, empty if no fix could be applied.
+    pub async fn preview(
+        repo_manager: &RepoManager,
+        fixes: Vec<FileChange>,
+    ) -> Result<String, PrError> {
+        let (fixes, _) = verify_fixes(&LinkChecker::default(), fixes).await;
+        let applied = apply_fixes(repo_manager, fixes).await;
+        let diff = applied.and_then(|_| Ok(repo_manager.diff_workdir()?));
 
-        Ok(())
+        repo_manager.discard_changes()?;
+        diff
     }
 
-    /// Applies link fixes to files in the repository.
+    /// Creates or updates the repository's link health issue with the latest results.
+    ///
+    /// A single issue titled "🔗 Link health" is maintained per repository. Each run replaces
+    /// its body with the current summary table and broken links, and appends the broken link
+    /// count to a trend kept in a hidden comment of the body. A newly created issue is pinned.
     ///
     /// # Arguments
-    /// * `fixes` - The list of file changes to apply
-    async fn apply_fixes(&self, fixes: Vec<FileChange>) -> Result<Vec<FileChange>, PrError> {
-        let mut changes = Vec::new();
+    /// * `summary` - The summary of the latest run
+    /// * `invalid_links` - The links that are not valid in the latest run
+    ///
+    /// # Returns
+    /// The URL of the health issue.
+    pub async fn update_health_issue(
+        &self,
+        summary: &LinkCheckSummaryEvent,
+        invalid_links: &[InvalidLinkInfo],
+    ) -> Result<String, PrError> {
+        let (owner, repo) = self.get_repo_owner_and_name()?;
+        let octocrab = self.client().await?;
+        let issues = octocrab.issues(owner.as_str(), repo.as_str());
+
+        let mut page = issues
+            .list()
+            .state(params::State::Open)
+            .per_page(100)
+            .send()
+            .await
+            .map_err(|e| PrError::GitHub(format!("Failed to list issues: {e}")))?;
+        let existing = loop {
+            let found = page
+                .take_items()
+                .into_iter()
+                .find(|issue| issue.pull_request.is_none() && issue.title == HEALTH_ISSUE_TITLE);
+            if found.is_some() {
+                break found;
+            }
+            match octocrab
+                .get_page::<Issue>(&page.next)
+                .await
+                .map_err(|e| PrError::GitHub(format!("Failed to list issues: {e}")))?
+            {
+                Some(next) => page = next,
+                None => break None,
+            }
+        };
+
+        let mut trend = existing
+            .as_ref()
+            .and_then(|issue| issue.body.as_deref())
+            .map(parse_trend)
+            .unwrap_or_default();
+        trend.push(summary.total - summary.valid);
+        if trend.len() > MAX_TREND_POINTS {
+            trend.drain(..trend.len() - MAX_TREND_POINTS);
+        }
+        let body = create_health_issue_body(summary, invalid_links, &trend);
+
+        let issue = match existing {
+            Some(issue) => issues
+                .update(issue.number)
+                .body(&body)
+                .send()
+                .await
+                .map_err(|e| PrError::GitHub(format!("Failed to update health issue: {e}")))?,
+            None => {
+                let issue = issues
+                    .create(HEALTH_ISSUE_TITLE)
+                    .body(body)
+                    .send()
+                    .await
+                    .map_err(|e| PrError::GitHub(format!("Failed to create health issue: {e}")))?;
+                if let Err(e) = self.pin_issue(&issue.node_id).await {
+                    error!("Failed to pin health issue: {}", e);
+                }
+                issue
+            }
+        };
+
+        info!("Updated health issue #{}", issue.number);
+        Ok(issue.html_url.to_string())
+    }
+
+    /// Publishes the latest results as a check run on the head commit of the base branch.
+    ///
+    /// Each broken link becomes a warning annotation on its file and line, so the results
+    /// are visible on GitHub even when no pull request is opened. The Checks API only
+    /// accepts a limited number of annotations per request, so the remaining ones are
+    /// added by updating the check run.
+    ///
+    /// # Arguments
+    /// * `summary` - The summary of the latest run
+    /// * `invalid_links` - The links that are not valid in the latest run
+    ///
+    /// # Returns
+    /// The URL of the check run.
+    pub async fn publish_check_run(
+        &self,
+        summary: &LinkCheckSummaryEvent,
+        invalid_links: &[InvalidLinkInfo],
+    ) -> Result<String, PrError> {
+        let (owner, repo) = self.get_repo_owner_and_name()?;
+        // The clone may be checked out on another revision, e.g. a queensac branch
+        let head_sha = self
+            .repo_manager
+            .resolve_commit(&format!("origin/{}", self.base_branch))?
+            .id()
+            .to_string();
+        let octocrab = self.client().await?;
+        let checks = octocrab.checks(owner.as_str(), repo.as_str());
+
+        let conclusion = if invalid_links.is_empty() {
+            params::checks::CheckRunConclusion::Success
+        } else {
+            params::checks::CheckRunConclusion::Neutral
+        };
+        let mut batches = invalid_links.chunks(MAX_ANNOTATIONS_PER_REQUEST);
+        let check_run = checks
+            .create_check_run(CHECK_RUN_NAME, head_sha)
+            .status(params::checks::CheckRunStatus::Completed)
+            .conclusion(conclusion)
+            .completed_at(Utc::now())
+            .output(create_check_run_output(
+                summary,
+                batches.next().unwrap_or_default(),
+            ))
+            .send()
+            .await
+            .map_err(|e| PrError::GitHub(format!("Failed to create check run: {e}")))?;
+        for batch in batches {
+            checks
+                .update_check_run(check_run.id)
+                .output(create_check_run_output(summary, batch))
+                .send()
+                .await
+                .map_err(|e| PrError::GitHub(format!("Failed to annotate check run: {e}")))?;
+        }
+
+        info!(
+            "Published check run with {} annotations",
+            invalid_links.len()
+        );
+        Ok(check_run.html_url.unwrap_or_default())
+    }
+
+    /// Pins an issue to the repository through the GraphQL API.
+    async fn pin_issue(&self, node_id: &str) -> Result<(), PrError> {
+        let body = serde_json::json!({
+            "query": "mutation($id: ID!) { pinIssue(input: {issueId: $id}) { issue { id } } }",
+            "variables": { "id": node_id },
+        });
+
+        let response = self
+            .client()
+            .await?
+            ._post("/graphql", Some(&body))
+            .await
+            .map_err(|e| PrError::GitHub(format!("Failed to pin issue: {e}")))?;
+        if !response.status().is_success() {
+            return Err(PrError::GitHub(format!(
+                "Failed to pin issue: HTTP status code: {}",
+                response.status()
+            )));
+        }
 
-        for fix in fixes {
-            let file_path = PathBuf::from(&fix.file_path);
-            let full_path = self.repo_manager.get_repo_path().join(&file_path);
+        Ok(())
+    }
 
-            if !full_path.exists() {
-                error!("File not found: {}", fix.file_path);
+    /// Closes open queensac pull requests that are no longer needed.
+    ///
+    /// Every open pull request from a queensac branch other than `current_branch` is
+    /// commented on with `reason`, closed, and has its branch deleted.
+    ///
+    /// # Arguments
+    /// * `current_branch` - The branch of the pull request to keep open, if any
+    /// * `reason` - Why the pull requests are closed, posted as a comment
+    ///
+    /// # Returns
+    /// The number of closed pull requests.
+    pub async fn close_stale_prs(
+        &self,
+        current_branch: Option<&str>,
+        reason: &str,
+    ) -> Result<usize, PrError> {
+        let octocrab = self.client().await?;
+
+        let mut closed = 0;
+        for pr in self.list_fix_prs(&octocrab).await? {
+            if Some(pr.head.ref_field.as_str()) == current_branch {
                 continue;
             }
+            self.close_pr(&octocrab, &pr, reason).await?;
+            closed += 1;
+        }
 
-            let current_content = tokio::fs::read_to_string(&full_path).await.map_err(|e| {
-                PrError::File(format!("Failed to read file {}: {}", fix.file_path, e))
-            })?;
+        Ok(closed)
+    }
 
-            let new_content = self.replace_line_content(
-                &current_content,
-                fix.line_number,
-                &fix.old_content,
-                &fix.new_content,
-            )?;
+    /// Loads the open queensac pull requests along with the links they change.
+    async fn open_fix_prs(&self) -> Result<Vec<OpenFixPr>, PrError> {
+        let (owner, repo) = self.get_repo_owner_and_name()?;
+        let octocrab = self.client().await?;
 
-            tokio::fs::write(&full_path, &new_content)
+        let mut open_prs = Vec::new();
+        for pr in self.list_fix_prs(&octocrab).await? {
+            let files = octocrab
+                .pulls(owner.as_str(), repo.as_str())
+                .list_files(pr.number)
                 .await
-                .map_err(|e| {
-                    PrError::File(format!("Failed to write file {}: {}", fix.file_path, e))
-                })?;
+                .map_err(|e| PrError::GitHub(format!("Failed to list PR files: {e}")))?;
+            let mut open_pr = OpenFixPr {
+                url: pr
+                    .html_url
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_else(|| format!("#{}", pr.number)),
+                pr,
+                removed_urls: HashSet::new(),
+                added_urls: HashSet::new(),
+            };
+            for file in files {
+                if let Some(patch) = &file.patch {
+                    let (removed, added) = patch_urls(&file.filename, patch);
+                    open_pr.removed_urls.extend(removed);
+                    open_pr.added_urls.extend(added);
+                }
+            }
+            open_prs.push(open_pr);
+        }
 
-            changes.push(FileChange {
-                file_path: fix.file_path.clone(),
-                old_content: current_content,
-                new_content,
-                line_number: fix.line_number,
-            });
+        Ok(open_prs)
+    }
 
-            info!(
-                "Applied fix to {}:{}",
-                fix.file_path.clone(),
-                fix.line_number
-            );
+    /// Closes the open queensac pull requests whose fixes are obsolete, explaining why in
+    /// a comment, and logs instead of failing on errors.
+    ///
+    /// # Arguments
+    /// * `obsolete` - Pull requests that propose none of the current fixes
+    /// * `broken_urls` - The links the current fixes replace
+    /// * `pr_url` - The pull request with the current fixes, if one was created
+    async fn close_obsolete_prs(
+        &self,
+        obsolete: &[OpenFixPr],
+        broken_urls: &HashSet<String>,
+        pr_url: Option<&str>,
+    ) {
+        if obsolete.is_empty() {
+            return;
         }
+        let octocrab = match self.client().await {
+            Ok(octocrab) => octocrab,
+            Err(e) => {
+                error!("Failed to close obsolete PRs: {}", e);
+                return;
+            }
+        };
+        for open_pr in obsolete {
+            // Links that are still broken but have a different replacement than the
+            // pull request proposes have moved again, the others were fixed on the base branch
+            let moved_again = open_pr
+                .removed_urls
+                .iter()
+                .any(|url| broken_urls.contains(url));
+            let reason = match (moved_again, pr_url) {
+                (true, Some(pr_url)) => format!(
+                    "The links this pull request fixes have moved again. {pr_url} updates them to their new location."
+                ),
+                (true, None) => "The links this pull request fixes have moved again, so its replacements are outdated.".to_string(),
+                (false, _) => format!(
+                    "The links this pull request fixes were fixed on `{}` in the meantime.",
+                    self.base_branch
+                ),
+            };
+            if let Err(e) = self.close_pr(&octocrab, &open_pr.pr, &reason).await {
+                error!("Failed to close obsolete PR {}: {}", open_pr.url, e);
+            }
+        }
+    }
+
+    /// Lists the open pull requests from queensac branches.
+    async fn list_fix_prs(&self, octocrab: &Octocrab) -> Result<Vec<PullRequest>, PrError> {
+        let (owner, repo) = self.get_repo_owner_and_name()?;
+        let head_owner = self.fork_owner.as_deref().unwrap_or(owner.as_str());
+
+        let prs = octocrab
+            .pulls(owner.as_str(), repo.as_str())
+            .list()
+            .state(params::State::Open)
+            .per_page(100)
+            .send()
+            .await
+            .map_err(|e| PrError::GitHub(format!("Failed to list PRs: {e}")))?;
 
-        Ok(changes)
+        Ok(prs
+            .into_iter()
+            .filter(|pr| {
+                pr.head.ref_field.starts_with(BRANCH_PREFIX)
+                    && pr
+                        .head
+                        .user
+                        .as_ref()
+                        .is_some_and(|user| user.login.eq_ignore_ascii_case(head_owner))
+            })
+            .collect())
     }
 
-    /// Replaces content in a specific line of a file.
+    /// Comments on a pull request with the reason it is closed, closes it, and deletes
+    /// its branch.
     ///
-    /// # Arguments
-    /// * `content` - The file content
-    /// * `line_number` - The line number to replace (1-based)
-    /// * `old_url` - The old URL to replace
-    /// * `new_url` - The new URL to insert
-    fn replace_line_content(
+    /// A failed comment is only logged, so that the pull request is still closed.
+    async fn close_pr(
         &self,
-        content: &str,
-        line_number: usize,
-        old_url: &str,
-        new_url: &str,
-    ) -> Result<String, PrError> {
-        let lines: Vec<&str> = content.lines().collect();
+        octocrab: &Octocrab,
+        pr: &PullRequest,
+        reason: &str,
+    ) -> Result<(), PrError> {
+        let (owner, repo) = self.get_repo_owner_and_name()?;
+        let head_owner = self.fork_owner.as_deref().unwrap_or(owner.as_str());
+        let branch = pr.head.ref_field.as_str();
 
-        if line_number == 0 || line_number > lines.len() {
-            return Err(PrError::File(format!("Invalid line number: {line_number}")));
+        if let Err(e) = octocrab
+            .issues(owner.as_str(), repo.as_str())
+            .create_comment(pr.number, reason)
+            .await
+        {
+            error!("Failed to comment on PR #{}: {}", pr.number, e);
         }
 
-        let line_index = line_number - 1;
-        let old_line = lines[line_index];
+        octocrab
+            .pulls(owner.as_str(), repo.as_str())
+            .update(pr.number)
+            .state(params::pulls::State::Closed)
+            .send()
+            .await
+            .map_err(|e| PrError::GitHub(format!("Failed to close PR: {e}")))?;
+
+        let head_repo = pr
+            .head
+            .repo
+            .as_ref()
+            .map(|head_repo| head_repo.name.as_str())
+            .unwrap_or(repo.as_str());
+        octocrab
+            .repos(head_owner, head_repo)
+            .delete_ref(&params::repos::Reference::Branch(branch.to_string()))
+            .await
+            .map_err(|e| PrError::GitHub(format!("Failed to delete branch: {e}")))?;
 
-        if !old_line.contains(old_url) {
-            return Err(PrError::File(format!(
-                "Old URL '{old_url}' not found in line {line_number}: {old_line}"
-            )));
+        info!("Closed stale PR #{} and deleted {}", pr.number, branch);
+        Ok(())
+    }
+
+    /// Returns a valid access token, renewing the GitHub App installation token if it is
+    /// about to expire.
+    async fn token(&self) -> Result<String, PrError> {
+        match &self.app_config {
+            Some(app_config) => {
+                installation_token(app_config, self.repo_manager.get_github_url()).await
+            }
+            None => Ok(self.access_token.clone()),
         }
+    }
 
-        let new_line = old_line.replace(old_url, new_url);
-        let mut new_lines = lines.clone();
-        new_lines[line_index] = &new_line;
+    /// Returns a GitHub client authenticated with a valid access token.
+    async fn client(&self) -> Result<Octocrab, PrError> {
+        let access_token = self.token().await?;
+        if access_token == self.access_token {
+            return Ok(self.octocrab.clone());
+        }
 
-        Ok(new_lines.join("\n"))
+        octocrab_builder()?
+            .personal_token(access_token)
+            .build()
+            .map_err(|e| PrError::GitHub(format!("Failed to build Octocrab instance: {e}")))
+    }
+
+    /// Creates a new feature branch from the current branch.
+    async fn create_branch(&self, branch_name: &str) -> Result<(), PrError> {
+        self.repo_manager.create_branch(branch_name).await?;
+        self.repo_manager.checkout_branch(branch_name).await?;
+
+        Ok(())
     }
 
     /// Commits all file changes to the repository.
@@ -265,13 +944,10 @@ impl PullRequestGenerator {
         for change in changes {
             self.repo_manager.add_file(&change.file_path).await?;
         }
-        let author_name = "queensac[bot]";
-        let author_email = "218335951+queensac[bot]@users.noreply.github.com";
-
         let commit_message = self.create_commit_message(changes);
 
         self.repo_manager
-            .commit(&commit_message, author_name, author_email)
+            .commit(&commit_message, AUTHOR_NAME, AUTHOR_EMAIL)
             .await?;
 
         info!("Successfully committed changes");
@@ -283,25 +959,18 @@ impl PullRequestGenerator {
     /// # Arguments
     /// * `changes` - The list of file changes
     fn create_commit_message(&self, changes: &[FileChange]) -> String {
-        let mut message = String::from("fix: Update broken links\n\n");
-
-        for change in changes {
-            message.push_str(&format!(
-                "- Update link in {}:{}\n",
-                change.file_path, change.line_number
-            ));
-        }
-
-        message.push_str(
-            "\nThis PR was automatically generated to fix broken links in the repository.",
-        );
-        message
+        fix_commit_message(changes, &self.commit_trailers)
     }
 
-    /// Pushes the feature branch to the remote repository.
+    /// Pushes the feature branch to the remote repository, or to the fork if one is used.
     async fn push_to_remote(&self, branch_name: &str) -> Result<(), PrError> {
+        let remote_name = match self.fork_owner {
+            Some(_) => FORK_REMOTE,
+            None => "origin",
+        };
+        let access_token = self.token().await?;
         self.repo_manager
-            .push("origin", branch_name, &self.access_token)
+            .push(remote_name, branch_name, &access_token)
             .await?;
 
         info!("Successfully pushed branch to remote");
@@ -309,26 +978,43 @@ impl PullRequestGenerator {
     }
 
     /// Generates a pull request via the GitHub API.
+    ///
+    /// # Arguments
+    /// * `branch_name` - The branch containing the fixes
+    /// * `skipped` - Fixes that were not applied because their replacement could not be verified
+    /// * `reviewers` - CODEOWNERS entries (`@user` or `@org/team`) to request reviews from
     pub async fn generate_pull_request_via_api(
         &self,
         branch_name: &str,
+        skipped: &[FileChange],
+        reviewers: &[String],
     ) -> Result<String, PrError> {
         let (owner, repo) = self.get_repo_owner_and_name()?;
+        let head = match &self.fork_owner {
+            Some(fork_owner) => format!("{fork_owner}:{branch_name}"),
+            None => branch_name.to_string(),
+        };
 
         let pr = self
-            .octocrab
+            .client()
+            .await?
             .pulls(owner.as_str(), repo.as_str())
             .create(
-                "fix: Update broken links",
-                branch_name,
+                self.locale.text(Message::FixTitle),
+                head,
                 self.base_branch.as_str(),
             )
-            .body(self.create_pr_description())
+            .body(self.create_pr_description(skipped))
             .send()
             .await
             .map_err(|e| PrError::GitHub(format!("Failed to create PR: {e}")))?;
 
         info!("Successfully created PR #{}", pr.number);
+        if !reviewers.is_empty()
+            && let Err(e) = self.request_reviews(pr.number, reviewers).await
+        {
+            error!("Failed to request reviews from code owners: {}", e);
+        }
         match pr.html_url {
             Some(url) => Ok(url.to_string()),
             None => Err(PrError::GitHub(
@@ -337,6 +1023,44 @@ impl PullRequestGenerator {
         }
     }
 
+    /// Requests reviews on a pull request from users and teams.
+    ///
+    /// # Arguments
+    /// * `pr_number` - The number of the pull request
+    /// * `reviewers` - CODEOWNERS entries, `@user` for users and `@org/team` for teams
+    async fn request_reviews(&self, pr_number: u64, reviewers: &[String]) -> Result<(), PrError> {
+        let (owner, repo) = self.get_repo_owner_and_name()?;
+        let (teams, users): (Vec<&str>, Vec<&str>) = reviewers
+            .iter()
+            .map(|reviewer| reviewer.trim_start_matches('@'))
+            .partition(|reviewer| reviewer.contains('/'));
+        let teams: Vec<&str> = teams
+            .into_iter()
+            .filter_map(|team| team.split_once('/').map(|(_, slug)| slug))
+            .collect();
+        let body =
+            std::collections::HashMap::from([("reviewers", users), ("team_reviewers", teams)]);
+
+        let response = self
+            .client()
+            .await?
+            ._post(
+                format!("/repos/{owner}/{repo}/pulls/{pr_number}/requested_reviewers"),
+                Some(&body),
+            )
+            .await
+            .map_err(|e| PrError::GitHub(format!("Failed to request reviews: {e}")))?;
+        if !response.status().is_success() {
+            return Err(PrError::GitHub(format!(
+                "Failed to request reviews: HTTP status code: {}",
+                response.status()
+            )));
+        }
+
+        info!("Requested reviews from {}", reviewers.join(", "));
+        Ok(())
+    }
+
     /// Gets the owner and repository name from the repository path.
     fn get_repo_owner_and_name(&self) -> Result<(String, String), PrError> {
         let github_url = self.repo_manager.get_github_url();
@@ -347,32 +1071,580 @@ impl PullRequestGenerator {
     }
 
     /// Creates a description for the pull request.
-    fn create_pr_description(&self) -> String {
-        "## 🔗 Link Fixes
+    ///
+    /// # Arguments
+    /// * `skipped` - Fixes that were not applied because their replacement could not be verified
+    fn create_pr_description(&self, skipped: &[FileChange]) -> String {
+        fix_description(self.locale, Message::PullRequest, "PR", skipped)
+    }
+}
+
+/// An installation access token and the time it expires at.
+#[derive(Debug, Clone)]
+struct CachedToken {
+    token: String,
+    expires_at: DateTime<Utc>,
+}
+
+impl CachedToken {
+    /// Returns whether the token stays valid long enough to be used for a whole push.
+    fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        now + TimeDelta::seconds(TOKEN_RENEWAL_MARGIN_SECS) < self.expires_at
+    }
+}
+
+/// Installation access tokens shared by all generators in the process, keyed by app and repository.
+static INSTALLATION_TOKENS: LazyLock<Mutex<HashMap<String, CachedToken>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Returns an installation access token for the repository, minting a new one only if
+/// there is no cached token or the cached one is about to expire.
+async fn installation_token(
+    app_config: &GitHubAppConfig,
+    github_url: &GitHubUrl,
+) -> Result<String, PrError> {
+    let key = format!(
+        "{}/{}/{}",
+        app_config.app_id,
+        github_url.owner(),
+        github_url.repo()
+    );
+    if let Some(cached) = INSTALLATION_TOKENS.lock().unwrap().get(&key)
+        && cached.is_fresh(Utc::now())
+    {
+        return Ok(cached.token.clone());
+    }
+
+    let cached = create_installation_token(app_config, github_url).await?;
+    info!("Minted installation token for {}", key);
+    INSTALLATION_TOKENS
+        .lock()
+        .unwrap()
+        .insert(key, cached.clone());
+    Ok(cached.token)
+}
+
+/// Creates a GitHub App installation access token scoped to the given repository.
+async fn create_installation_token(
+    app_config: &GitHubAppConfig,
+    github_url: &GitHubUrl,
+) -> Result<CachedToken, PrError> {
+    let key = jsonwebtoken::EncodingKey::from_rsa_pem(app_config.private_key.as_bytes())
+        .map_err(|e| PrError::Config(format!("Failed to parse private key: {e}")))?;
+
+    let octocrab = octocrab_builder()?
+        .app(app_config.app_id.into(), key)
+        .build()
+        .map_err(|e| PrError::Config(format!("Failed to build Octocrab instance: {e}")))?;
+
+    let installations = octocrab
+        .apps()
+        .installations()
+        .send()
+        .await
+        .map_err(|e| PrError::GitHub(format!("Failed to get installations: {e}")))?;
+
+    let installation = installations
+        .into_iter()
+        .find(|inst| inst.account.login.eq_ignore_ascii_case(github_url.owner()))
+        .ok_or_else(|| PrError::GitHub("No GitHub App installation found".to_string()))?;
+
+    let mut create_access_token = CreateInstallationAccessToken::default();
+    create_access_token.repositories = vec![github_url.repo().to_string()];
+
+    let access_token_url =
+        Url::parse(installation.access_tokens_url.as_ref().ok_or_else(|| {
+            PrError::GitHub("Missing access_token_url in installation".to_string())
+        })?)
+        .map_err(|e| PrError::GitHub(format!("Failed to parse access token URL: {e}")))?;
+
+    let access_token: InstallationToken = octocrab
+        .post(access_token_url.path(), Some(&create_access_token))
+        .await
+        .map_err(|e| PrError::GitHub(format!("Failed to create installation access token: {e}")))?;
+
+    // Installation tokens are valid for one hour
+    let expires_at = access_token
+        .expires_at
+        .as_deref()
+        .and_then(|expires_at| DateTime::parse_from_rfc3339(expires_at).ok())
+        .map(|expires_at| expires_at.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc::now() + TimeDelta::hours(1));
+
+    Ok(CachedToken {
+        token: access_token.token,
+        expires_at,
+    })
+}
+
+/// Creates an Octocrab builder for the GitHub API.
+///
+/// The API at `QUEENSAC_GITHUB_API_URL` is used instead of `https://api.github.com` if set,
+/// e.g. `https://github.example.com/api/v3` for GitHub Enterprise Server.
+fn octocrab_builder()
+-> Result<OctocrabBuilder<NoSvc, DefaultOctocrabBuilderConfig, NoAuth, NotLayerReady>, PrError> {
+    let builder = Octocrab::builder();
+    match std::env::var("QUEENSAC_GITHUB_API_URL") {
+        Ok(api_url) => builder
+            .base_uri(api_url)
+            .map_err(|e| PrError::Config(format!("Invalid QUEENSAC_GITHUB_API_URL: {e}"))),
+        Err(_) => Ok(builder),
+    }
+}
+
+/// An open queensac pull request and the links it changes.
+struct OpenFixPr {
+    pr: PullRequest,
+    /// The URL of the pull request on GitHub
+    url: String,
+    /// The links on the lines the pull request removes
+    removed_urls: HashSet<String>,
+    /// The links on the lines the pull request adds
+    added_urls: HashSet<String>,
+}
+
+impl OpenFixPr {
+    /// Returns whether the pull request makes the given fix.
+    fn proposes(&self, fix: &FileChange) -> bool {
+        self.removed_urls.contains(&fix.old_content) && self.added_urls.contains(&fix.new_content)
+    }
+}
+
+/// Collects the links on the removed and added lines of a unified diff.
+///
+/// # Arguments
+/// * `file_path` - The path of the changed file, which decides how its links are found
+/// * `patch` - The diff of the file, as returned by the GitHub API
+///
+/// # Returns
+/// The links on the removed lines and the links on the added lines.
+fn patch_urls(file_path: &str, patch: &str) -> (HashSet<String>, HashSet<String>) {
+    let mut removed = String::new();
+    let mut added = String::new();
+    for line in patch.lines() {
+        if let Some(content) = line.strip_prefix('-') {
+            removed.push_str(content);
+            removed.push('\n');
+        } else if let Some(content) = line.strip_prefix('+') {
+            added.push_str(content);
+            added.push('\n');
+        }
+    }
+
+    let urls = |content: &str| {
+        find_link_in_content(content, file_path.to_string())
+            .into_iter()
+            .map(|link| link.url)
+            .collect()
+    };
+    (urls(&removed), urls(&added))
+}
+
+/// Splits fixes into those whose replacement URL is valid and those whose replacement
+/// is itself broken, so that a dead link is never swapped for another dead link.
+pub(crate) async fn verify_fixes(
+    link_checker: &LinkChecker,
+    fixes: Vec<FileChange>,
+) -> (Vec<FileChange>, Vec<FileChange>) {
+    let mut verified = Vec::new();
+    let mut skipped = Vec::new();
+    // Every occurrence of a link gets its own fix, but each replacement is checked once
+    let mut results: HashMap<String, LinkCheckResult> = HashMap::new();
+
+    for fix in fixes {
+        let result = match results.get(&fix.new_content) {
+            Some(result) => result.clone(),
+            None => {
+                let result = link_checker.check_link(&fix.new_content).await;
+                results.insert(fix.new_content.clone(), result.clone());
+                result
+            }
+        };
+        match result {
+            LinkCheckResult::Valid => verified.push(fix),
+            result => {
+                error!(
+                    "Skipping fix for {}:{}, replacement {} is not valid: {:?}",
+                    fix.file_path, fix.line_number, fix.new_content, result
+                );
+                skipped.push(fix);
+            }
+        }
+    }
+
+    (verified, skipped)
+}
 
-This pull request was automatically generated to fix broken links in the repository.
+/// Applies link fixes to files in the repository's working directory.
+///
+/// # Arguments
+/// * `repo_manager` - The repository to apply the fixes to
+/// * `fixes` - The list of file changes to apply
+pub(crate) async fn apply_fixes(
+    repo_manager: &RepoManager,
+    mut fixes: Vec<FileChange>,
+) -> Result<Vec<FileChange>, PrError> {
+    let mut changes = Vec::new();
+    let protected_paths = ProtectedPaths::from_repo(repo_manager);
+    let submodule_paths = repo_manager.submodule_paths();
+
+    // Apply fixes from right to left so that earlier offsets on the same line stay valid
+    fixes.sort_by_key(|fix| std::cmp::Reverse(fix.column));
+
+    for fix in fixes {
+        if protected_paths.is_protected(&fix.file_path) {
+            info!(
+                "Skipping fix for {}:{}, the path is protected",
+                fix.file_path, fix.line_number
+            );
+            continue;
+        }
+        if submodule_paths
+            .iter()
+            .any(|submodule| fix.file_path.starts_with(&format!("{submodule}/")))
+        {
+            info!(
+                "Skipping fix for {}:{}, the file belongs to a submodule",
+                fix.file_path, fix.line_number
+            );
+            continue;
+        }
 
-### What was changed?
-- Updated broken links to their correct destinations
-- All changes were automatically detected and fixed
+        let file_path = PathBuf::from(&fix.file_path);
+        let full_path = repo_manager.get_repo_path().join(&file_path);
 
-### How to review?
-1. Check that the new links are correct and accessible
-2. Verify that the changes don't break any existing functionality
-3. Ensure the commit messages are descriptive
+        if !full_path.exists() {
+            error!("File not found: {}", fix.file_path);
+            continue;
+        }
 
----
-*This PR was generated by the [queens.ac](https://github.com/reddevilmidzy/queensac)*"
-            .to_string()
+        let current_bytes = tokio::fs::read(&full_path)
+            .await
+            .map_err(|e| PrError::File(format!("Failed to read file {}: {}", fix.file_path, e)))?;
+        let Ok(current_content) = String::from_utf8(current_bytes) else {
+            info!(
+                "Skipping fix for {}:{}, the file is not valid UTF-8",
+                fix.file_path, fix.line_number
+            );
+            continue;
+        };
+
+        let new_content = replace_line_content(
+            &current_content,
+            fix.line_number,
+            fix.column,
+            &fix.old_content,
+            &fix.new_content,
+        )?;
+
+        tokio::fs::write(&full_path, &new_content)
+            .await
+            .map_err(|e| PrError::File(format!("Failed to write file {}: {}", fix.file_path, e)))?;
+
+        changes.push(FileChange {
+            file_path: fix.file_path.clone(),
+            old_content: current_content,
+            new_content,
+            line_number: fix.line_number,
+            column: fix.column,
+        });
+
+        info!(
+            "Applied fix to {}:{}",
+            fix.file_path.clone(),
+            fix.line_number
+        );
     }
+
+    Ok(changes)
 }
 
-fn generate_branch_name() -> String {
+/// Collects the code owners of the changed files that can be requested as reviewers.
+///
+/// Owners listed by email address are skipped, as GitHub only accepts users and teams.
+fn find_reviewers(code_owners: &CodeOwners, changes: &[FileChange]) -> Vec<String> {
+    let mut reviewers: Vec<String> = Vec::new();
+    for change in changes {
+        for owner in code_owners.owners_of(&change.file_path) {
+            if owner.starts_with('@') && !reviewers.contains(owner) {
+                reviewers.push(owner.clone());
+            }
+        }
+    }
+
+    reviewers
+}
+
+/// Replaces the URL at a specific position of a file.
+///
+/// Only the occurrence starting at `column` is replaced, so other occurrences of the
+/// same URL and longer URLs containing it are left untouched.
+///
+/// # Arguments
+/// * `content` - The file content
+/// * `line_number` - The line number to replace (1-based)
+/// * `column` - The byte offset of the old URL within the line (0-based)
+/// * `old_url` - The old URL to replace
+/// * `new_url` - The new URL to insert
+fn replace_line_content(
+    content: &str,
+    line_number: usize,
+    column: usize,
+    old_url: &str,
+    new_url: &str,
+) -> Result<String, PrError> {
+    // Keep the line endings so that the rest of the file is left byte-for-byte intact
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+
+    if line_number == 0 || line_number > lines.len() {
+        return Err(PrError::File(format!("Invalid line number: {line_number}")));
+    }
+
+    let line_index = line_number - 1;
+    let old_line = lines[line_index];
+
+    if old_line.get(column..column + old_url.len()) != Some(old_url) {
+        return Err(PrError::File(format!(
+            "Old URL '{old_url}' not found at column {column} in line {line_number}: {}",
+            old_line.trim_end()
+        )));
+    }
+
+    let new_line = format!(
+        "{}{}{}",
+        &old_line[..column],
+        new_url,
+        &old_line[column + old_url.len()..]
+    );
+    let mut new_lines = lines.clone();
+    new_lines[line_index] = &new_line;
+
+    Ok(new_lines.concat())
+}
+
+/// Creates the message of a commit with link fixes.
+///
+/// # Arguments
+/// * `changes` - The applied fixes
+/// * `trailers` - Trailers appended to the message, in `Key: value` form
+pub(crate) fn fix_commit_message(changes: &[FileChange], trailers: &[String]) -> String {
+    let mut message = String::from("fix: Update broken links\n\n");
+
+    for change in changes {
+        message.push_str(&format!(
+            "- Update link in {}:{}\n",
+            change.file_path, change.line_number
+        ));
+    }
+
+    message
+        .push_str("\nThis PR was automatically generated to fix broken links in the repository.");
+    if !trailers.is_empty() {
+        message.push_str("\n\n");
+        message.push_str(&trailers.join("\n"));
+    }
+    message
+}
+
+/// Creates the description of a change request with link fixes.
+///
+/// # Arguments
+/// * `locale` - The language of the description
+/// * `noun` - What the change request is called on the hosting service, e.g. `Message::PullRequest`
+/// * `abbreviation` - The abbreviation of `noun`, e.g. "PR"
+/// * `skipped` - Fixes that were not applied because their replacement could not be verified
+pub(crate) fn fix_description(
+    locale: Locale,
+    noun: Message,
+    abbreviation: &str,
+    skipped: &[FileChange],
+) -> String {
+    let mut description = format!(
+        "{}\n\n{}\n\n{}\n\n{}\n",
+        locale.text(Message::FixHeading),
+        locale.format(Message::FixIntro, &[("noun", locale.text(noun))]),
+        locale.text(Message::FixChanges),
+        locale.text(Message::FixReview),
+    );
+
+    if !skipped.is_empty() {
+        description.push_str(&format!(
+            "\n{}\n{}\n",
+            locale.text(Message::SkippedHeading),
+            locale.text(Message::SkippedIntro)
+        ));
+        for fix in skipped {
+            description.push_str(&format!(
+                "- {}:{} `{}` → `{}`\n",
+                fix.file_path, fix.line_number, fix.old_content, fix.new_content
+            ));
+        }
+    }
+
+    description.push_str(&format!(
+        "\n---\n{}",
+        locale.format(Message::GeneratedBy, &[("abbreviation", abbreviation)])
+    ));
+    description
+}
+
+/// The `Signed-off-by` trailer for the bot, for projects that require a Developer
+/// Certificate of Origin sign-off.
+fn sign_off_trailer() -> String {
+    format!("Signed-off-by: {AUTHOR_NAME} <{AUTHOR_EMAIL}>")
+}
+
+/// Checks that a commit trailer is in `Key: value` form.
+fn validate_trailer(trailer: &str) -> Result<(), PrError> {
+    let valid = trailer.split_once(": ").is_some_and(|(key, value)| {
+        !key.is_empty()
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && !value.trim().is_empty()
+    });
+    if !valid {
+        return Err(PrError::InvalidTrailer(trailer.to_string()));
+    }
+    Ok(())
+}
+
+/// Creates the body of the link health issue.
+///
+/// # Arguments
+/// * `summary` - The summary of the latest run
+/// * `invalid_links` - The links that are not valid in the latest run
+/// * `trend` - Broken link counts of previous runs, oldest first, including the latest run
+fn create_health_issue_body(
+    summary: &LinkCheckSummaryEvent,
+    invalid_links: &[InvalidLinkInfo],
+    trend: &[usize],
+) -> String {
+    let mut body = format!(
+        "## 🔗 Link health
+
+| Total | Valid | Invalid | Redirect | Moved |
+|---|---|---|---|---|
+| {} | {} | {} | {} | {} |
+
+**Trend** (broken links over the last {} runs): {}
+",
+        summary.total,
+        summary.valid,
+        summary.invalid,
+        summary.redirect,
+        summary.moved,
+        trend.len(),
+        sparkline(trend)
+    );
+    if summary.undecodable_files > 0 {
+        body.push_str(&format!(
+            "\n{} files are not valid UTF-8 and were decoded lossily.\n",
+            summary.undecodable_files
+        ));
+    }
+
+    if !invalid_links.is_empty() {
+        body.push_str("\n### Broken links\n");
+        for link in invalid_links {
+            body.push_str(&format!(
+                "- `{}` in {}:{}\n",
+                link.url, link.file_path, link.line_number
+            ));
+        }
+    }
+
+    let trend_data: Vec<String> = trend.iter().map(|count| count.to_string()).collect();
+    body.push_str(&format!(
+        "\n---\n*Updated by [queens.ac](https://github.com/reddevilmidzy/queensac) on {}*\n{} {} -->",
+        chrono::Utc::now().format("%Y-%m-%d %H:%M UTC"),
+        TREND_MARKER,
+        trend_data.join(",")
+    ));
+    body
+}
+
+/// Creates the output of the check run, annotating the given broken links.
+///
+/// # Arguments
+/// * `summary` - The summary of the latest run
+/// * `invalid_links` - The broken links to annotate in this request
+fn create_check_run_output(
+    summary: &LinkCheckSummaryEvent,
+    invalid_links: &[InvalidLinkInfo],
+) -> params::checks::CheckRunOutput {
+    let broken = summary.total - summary.valid;
+    let title = if broken == 0 {
+        "All links are valid".to_string()
+    } else {
+        format!("{broken} broken links")
+    };
+    let summary = format!(
+        "| Total | Valid | Invalid | Redirect | Moved |
+|---|---|---|---|---|
+| {} | {} | {} | {} | {} |",
+        summary.total, summary.valid, summary.invalid, summary.redirect, summary.moved
+    );
+    let annotations = invalid_links
+        .iter()
+        .map(|link| {
+            let line = u32::try_from(link.line_number).unwrap_or(u32::MAX);
+            let message = match &link.collect_link {
+                Some(replacement) => {
+                    format!("`{}` can be replaced with `{}`", link.url, replacement)
+                }
+                None => format!("`{}` is not reachable", link.url),
+            };
+            params::checks::CheckRunOutputAnnotation {
+                path: link.file_path.clone(),
+                start_line: line,
+                end_line: line,
+                start_column: None,
+                end_column: None,
+                annotation_level: params::checks::CheckRunOutputAnnotationLevel::Warning,
+                message,
+                title: Some("Broken link".to_string()),
+                raw_details: None,
+            }
+        })
+        .collect();
+
+    params::checks::CheckRunOutput {
+        title,
+        summary,
+        text: None,
+        annotations,
+        images: Vec::new(),
+    }
+}
+
+/// Reads the broken link counts of previous runs from a health issue body.
+fn parse_trend(body: &str) -> Vec<usize> {
+    body.split_once(TREND_MARKER)
+        .and_then(|(_, rest)| rest.split_once("-->"))
+        .map(|(data, _)| {
+            data.split(',')
+                .filter_map(|count| count.trim().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Renders counts as a sparkline of block characters, scaled to the largest count.
+fn sparkline(values: &[usize]) -> String {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+
+    values
+        .iter()
+        .map(|value| LEVELS[value * (LEVELS.len() - 1) / max])
+        .collect()
+}
+
+pub(crate) fn generate_branch_name() -> String {
     let now = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .map(|d| d.as_millis())
         .unwrap_or(0);
-    format!("queensac-{}", now)
+    format!("{BRANCH_PREFIX}{now}")
 }
 
 fn read_env_var(var_name: &str) -> Result<String, PrError> {
@@ -383,7 +1655,6 @@ fn read_env_var(var_name: &str) -> Result<String, PrError> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::GitHubUrl;
     use wiremock::matchers::{method, path};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
@@ -422,23 +1693,155 @@ mod tests {
                 base_branch,
                 octocrab,
                 access_token,
+                app_config: None,
+                fork_owner: None,
+                codeowner_reviews: false,
+                commit_trailers: Vec::new(),
+                link_checker: LinkChecker::default(),
+                locale: Locale::default(),
             }
         }
     }
 
-    #[tokio::test]
-    async fn test_replace_line_content() {
-        let generator = PullRequestGenerator::new_for_test();
-
+    #[test]
+    fn test_replace_line_content() {
         let content = "Line 1\nLine 2 with https://old-url.com\nLine 3";
-        let new_content = generator
-            .replace_line_content(content, 2, "https://old-url.com", "https://new-url.com")
-            .unwrap();
+        let new_content =
+            replace_line_content(content, 2, 12, "https://old-url.com", "https://new-url.com")
+                .unwrap();
 
         assert!(new_content.contains("https://new-url.com"));
         assert!(!new_content.contains("https://old-url.com"));
     }
 
+    #[test]
+    fn test_replace_line_content_keeps_line_endings() {
+        let content = "line 1\r\nhttps://old-url.com\r\nline 3\n";
+        let result =
+            replace_line_content(content, 2, 0, "https://old-url.com", "https://new-url.com")
+                .unwrap();
+        assert_eq!(result, "line 1\r\nhttps://new-url.com\r\nline 3\n");
+    }
+
+    #[test]
+    fn test_replace_line_content_adjacent_urls() {
+        let content = "[a](https://old-url.com) [b](https://old-url.com)";
+        let new_content =
+            replace_line_content(content, 1, 29, "https://old-url.com", "https://new-url.com")
+                .unwrap();
+        assert_eq!(
+            new_content,
+            "[a](https://old-url.com) [b](https://new-url.com)"
+        );
+
+        // Fixes applied from right to left keep the offsets of the earlier ones valid
+        let new_content = replace_line_content(
+            &new_content,
+            1,
+            4,
+            "https://old-url.com",
+            "https://new-url.com",
+        )
+        .unwrap();
+        assert_eq!(
+            new_content,
+            "[a](https://new-url.com) [b](https://new-url.com)"
+        );
+    }
+
+    #[test]
+    fn test_replace_line_content_nested_url() {
+        let content = "https://old-url.com/docs/intro https://old-url.com/docs";
+        let new_content = replace_line_content(
+            content,
+            1,
+            31,
+            "https://old-url.com/docs",
+            "https://new-url.com/docs",
+        )
+        .unwrap();
+
+        assert_eq!(
+            new_content,
+            "https://old-url.com/docs/intro https://new-url.com/docs"
+        );
+    }
+
+    #[test]
+    fn test_replace_line_content_wrong_column() {
+        let content = "Line with https://old-url.com";
+        let result =
+            replace_line_content(content, 1, 0, "https://old-url.com", "https://new-url.com");
+
+        assert!(matches!(result, Err(PrError::File(_))));
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_fix_pr_through_fork_waits_for_the_fork() {
+        use crate::git::mock_github::{FixtureRepo, MockGitHub};
+
+        let github = MockGitHub::start().await;
+        let readme = format!(
+            "# kingsac\n\nSee the [guide]({}).\n",
+            github.site_url("/old")
+        );
+        let upstream = FixtureRepo::new(&[("README.md", &readme)]);
+        let fork = FixtureRepo::new(&[("README.md", &readme)]);
+        github
+            .mount_fork(
+                "reddevilmidzy",
+                "kingsac",
+                "forker",
+                &format!("file://{}", fork.origin_url()),
+                2,
+            )
+            .await;
+        github.mount_pulls("reddevilmidzy", "kingsac").await;
+        github.mount_page("/new", 200).await;
+
+        // SAFETY: the test is serial, so no other thread reads the environment meanwhile.
+        unsafe { std::env::set_var("QUEENSAC_GITHUB_API_URL", github.api_url()) };
+        let generator = PullRequestGenerator::with_fork(
+            upstream.clone_as("kingsac"),
+            "token".to_string(),
+            "main".to_string(),
+            Duration::from_millis(10),
+        )
+        .await;
+        unsafe { std::env::remove_var("QUEENSAC_GITHUB_API_URL") };
+        let mut generator = generator.unwrap();
+        generator.link_checker = github.link_checker();
+
+        let fix = FileChange {
+            file_path: "README.md".to_string(),
+            old_content: github.site_url("/old"),
+            new_content: github.site_url("/new"),
+            line_number: 3,
+            column: 16,
+        };
+        generator.create_fix_pr(vec![fix]).await.unwrap();
+
+        let lookups = github
+            .request_bodies("GET", "^/repos/forker/kingsac$")
+            .await;
+        assert_eq!(lookups.len(), 3);
+
+        assert!(upstream.pushed_branches().is_empty());
+        let branches = fork.pushed_branches();
+        assert_eq!(branches.len(), 1);
+        assert_eq!(
+            fork.read_file(&branches[0], "README.md"),
+            readme.replace("/old", "/new")
+        );
+
+        let created = github
+            .request_bodies("POST", "^/repos/reddevilmidzy/kingsac/pulls$")
+            .await;
+        assert_eq!(created.len(), 1);
+        assert!(created[0].contains(&format!("\"head\":\"forker:{}\"", branches[0])));
+    }
+
     #[tokio::test]
     async fn test_create_commit_message() {
         let generator = PullRequestGenerator::new_for_test();
@@ -449,12 +1852,14 @@ mod tests {
                 old_content: "old".to_string(),
                 new_content: "new".to_string(),
                 line_number: 3,
+                column: 0,
             },
             FileChange {
                 file_path: "readme.md".to_string(),
                 old_content: "old".to_string(),
                 new_content: "new".to_string(),
                 line_number: 10,
+                column: 0,
             },
         ];
 
@@ -465,15 +1870,208 @@ mod tests {
         assert!(message.contains("readme.md:10"));
     }
 
+    #[tokio::test]
+    async fn test_create_commit_message_with_trailers() {
+        let mut generator = PullRequestGenerator::new_for_test();
+        generator.commit_trailers = vec![
+            sign_off_trailer(),
+            "Co-authored-by: Redddy <midzy0228@gmail.com>".to_string(),
+        ];
+        assert!(validate_trailer("Co-authored-by: Redddy <midzy0228@gmail.com>").is_ok());
+        assert!(validate_trailer("not a trailer").is_err());
+
+        let message = generator.create_commit_message(&[]);
+
+        assert!(message.ends_with(
+            "repository.\n\nSigned-off-by: queensac[bot] <218335951+queensac[bot]@users.noreply.github.com>\nCo-authored-by: Redddy <midzy0228@gmail.com>"
+        ));
+    }
+
     #[tokio::test]
     async fn test_create_pr_description() {
         let generator = PullRequestGenerator::new_for_test();
 
-        let description = generator.create_pr_description();
+        let description = generator.create_pr_description(&[]);
 
         assert!(description.contains("## 🔗 Link Fixes"));
         assert!(description.contains("This pull request was automatically generated"));
         assert!(description.contains("queens.ac"));
+        assert!(!description.contains("Skipped replacements"));
+    }
+
+    #[tokio::test]
+    async fn test_create_pr_description_in_korean() {
+        let mut generator = PullRequestGenerator::new_for_test();
+        generator.locale = Locale::Ko;
+
+        let description = generator.create_pr_description(&[]);
+
+        assert!(description.starts_with("## 🔗 링크 수정\n\n이 풀 리퀘스트는"));
+        assert!(description.ends_with(
+            "*이 PR는 [queens.ac](https://github.com/reddevilmidzy/queensac)가 생성했습니다*"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_create_pr_description_with_skipped() {
+        let generator = PullRequestGenerator::new_for_test();
+
+        let skipped = vec![FileChange {
+            file_path: "docs/guide.md".to_string(),
+            old_content: "https://old-url.com".to_string(),
+            new_content: "https://dead-url.com".to_string(),
+            line_number: 7,
+            column: 0,
+        }];
+        let description = generator.create_pr_description(&skipped);
+
+        assert!(description.contains("### Skipped replacements"));
+        assert!(description.contains("docs/guide.md:7"));
+        assert!(description.contains("https://dead-url.com"));
+        assert!(description.ends_with("(https://github.com/reddevilmidzy/queensac)*"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_fixes() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/valid"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/gone"))
+            .respond_with(ResponseTemplate::new(410))
+            .mount(&mock_server)
+            .await;
+
+        let fix = |new_content: String| FileChange {
+            file_path: "README.md".to_string(),
+            old_content: "https://old-url.com".to_string(),
+            new_content,
+            line_number: 1,
+            column: 0,
+        };
+        let fixes = vec![
+            fix(format!("{}/valid", mock_server.uri())),
+            fix(format!("{}/gone", mock_server.uri())),
+        ];
+
+        let (verified, skipped) = verify_fixes(&LinkChecker::default(), fixes).await;
+
+        assert_eq!(verified.len(), 1);
+        assert!(verified[0].new_content.ends_with("/valid"));
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].new_content.ends_with("/gone"));
+    }
+
+    #[test]
+    fn test_find_reviewers() {
+        let code_owners =
+            CodeOwners::parse("* @reddevilmidzy\n/docs/ @reddevilmidzy/docs user@example.com\n");
+        let change = |file_path: &str| FileChange {
+            file_path: file_path.to_string(),
+            old_content: String::new(),
+            new_content: String::new(),
+            line_number: 1,
+            column: 0,
+        };
+
+        let reviewers = find_reviewers(
+            &code_owners,
+            &[
+                change("docs/intro.md"),
+                change("README.md"),
+                change("docs/faq.md"),
+            ],
+        );
+        assert_eq!(reviewers, vec!["@reddevilmidzy/docs", "@reddevilmidzy"]);
+    }
+
+    #[test]
+    fn test_cached_token_is_fresh() {
+        let now = Utc::now();
+        let token = |expires_in: TimeDelta| CachedToken {
+            token: "ghs_test".to_string(),
+            expires_at: now + expires_in,
+        };
+
+        assert!(token(TimeDelta::minutes(30)).is_fresh(now));
+        assert!(!token(TimeDelta::minutes(2)).is_fresh(now));
+        assert!(!token(TimeDelta::minutes(-1)).is_fresh(now));
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[0, 0]), "▁▁");
+        assert_eq!(sparkline(&[0, 7, 14]), "▁▄█");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn test_health_issue_trend_round_trip() {
+        let summary = LinkCheckSummaryEvent {
+            total: 10,
+            valid: 7,
+            invalid: 2,
+            redirect: 1,
+            moved: 0,
+            undecodable_files: 0,
+        };
+        let invalid_links = vec![InvalidLinkInfo {
+            url: "https://old-url.com".to_string(),
+            file_path: "README.md".to_string(),
+            line_number: 3,
+            column: 0,
+            char_column: 0,
+            context: crate::LinkContext::Markdown,
+            collect_link: None,
+            result: LinkCheckResult::Invalid("HTTP status code: 404 Not Found".to_string()),
+            suggestions: Vec::new(),
+        }];
+
+        let body = create_health_issue_body(&summary, &invalid_links, &[5, 4, 3]);
+
+        assert!(body.contains("| 10 | 7 | 2 | 1 | 0 |"));
+        assert!(body.contains("`https://old-url.com` in README.md:3"));
+        assert_eq!(parse_trend(&body), vec![5, 4, 3]);
+        assert_eq!(parse_trend("no trend yet"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_check_run_output() {
+        let summary = LinkCheckSummaryEvent {
+            total: 10,
+            valid: 8,
+            invalid: 1,
+            redirect: 1,
+            moved: 0,
+            undecodable_files: 0,
+        };
+        let invalid_links = vec![InvalidLinkInfo {
+            url: "https://old-url.com".to_string(),
+            file_path: "docs/intro.md".to_string(),
+            line_number: 12,
+            column: 4,
+            char_column: 4,
+            context: crate::LinkContext::Markdown,
+            collect_link: Some("https://new-url.com".to_string()),
+            result: LinkCheckResult::Redirect("https://new-url.com".to_string()),
+            suggestions: Vec::new(),
+        }];
+
+        let output = create_check_run_output(&summary, &invalid_links);
+
+        assert_eq!(output.title, "2 broken links");
+        assert!(output.summary.contains("| 10 | 8 | 1 | 1 | 0 |"));
+        assert_eq!(output.annotations.len(), 1);
+        assert_eq!(output.annotations[0].path, "docs/intro.md");
+        assert_eq!(output.annotations[0].start_line, 12);
+        assert_eq!(output.annotations[0].end_line, 12);
+        assert_eq!(
+            output.annotations[0].message,
+            "`https://old-url.com` can be replaced with `https://new-url.com`"
+        );
     }
 
     #[test]
@@ -660,11 +2258,17 @@ mod tests {
             base_branch: generator.base_branch,
             octocrab,
             access_token: generator.access_token,
+            app_config: None,
+            fork_owner: None,
+            codeowner_reviews: false,
+            commit_trailers: Vec::new(),
+            link_checker: LinkChecker::default(),
+            locale: Locale::default(),
         };
 
         // Test the PR generation
         let result = generator_with_mock
-            .generate_pull_request_via_api("queensac-test-branch")
+            .generate_pull_request_via_api("queensac-test-branch", &[], &[])
             .await;
 
         assert!(result.is_ok());
@@ -848,11 +2452,17 @@ mod tests {
             base_branch: generator.base_branch,
             octocrab,
             access_token: generator.access_token,
+            app_config: None,
+            fork_owner: None,
+            codeowner_reviews: false,
+            commit_trailers: Vec::new(),
+            link_checker: LinkChecker::default(),
+            locale: Locale::default(),
         };
 
         // Test the PR generation
         let result = generator_with_mock
-            .generate_pull_request_via_api("queensac-test-branch")
+            .generate_pull_request_via_api("queensac-test-branch", &[], &[])
             .await;
 
         assert!(result.is_err());
@@ -900,11 +2510,17 @@ mod tests {
             base_branch: generator.base_branch,
             octocrab,
             access_token: generator.access_token,
+            app_config: None,
+            fork_owner: None,
+            codeowner_reviews: false,
+            commit_trailers: Vec::new(),
+            link_checker: LinkChecker::default(),
+            locale: Locale::default(),
         };
 
         // Test the PR generation
         let result = generator_with_mock
-            .generate_pull_request_via_api("queensac-test-branch")
+            .generate_pull_request_via_api("queensac-test-branch", &[], &[])
             .await;
 
         assert!(result.is_err());
@@ -915,6 +2531,90 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_close_stale_prs_without_open_prs() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/repos/reddevilmidzy/kingsac/pulls"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("[]")
+                    .insert_header("content-type", "application/json"),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let generator = PullRequestGenerator::new_for_test();
+        let octocrab = Octocrab::builder()
+            .base_uri(mock_server.uri())
+            .unwrap()
+            .personal_token("test_token".to_string())
+            .build()
+            .unwrap();
+        let generator_with_mock = PullRequestGenerator {
+            octocrab,
+            ..generator
+        };
+
+        let closed = generator_with_mock
+            .close_stale_prs(None, "All links are valid")
+            .await
+            .unwrap();
+
+        assert_eq!(closed, 0);
+    }
+
+    #[tokio::test]
+    async fn test_preview() {
+        use std::fs;
+
+        let generator = PullRequestGenerator::new_for_test();
+        let repo_manager = &generator.repo_manager;
+
+        let readme = repo_manager.get_repo_path().join("README.md");
+        fs::write(&readme, "# Test\nSee https://old-url.com\n").unwrap();
+        repo_manager.add_file("README.md").await.unwrap();
+        let repo = repo_manager.get_repo();
+        let signature = git2::Signature::now("Test User", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "Initial commit",
+            &tree,
+            &[],
+        )
+        .unwrap();
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/new"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+        let new_url = format!("{}/new", mock_server.uri());
+
+        let fixes = vec![FileChange {
+            file_path: "README.md".to_string(),
+            old_content: "https://old-url.com".to_string(),
+            new_content: new_url.clone(),
+            line_number: 2,
+            column: 4,
+        }];
+        let diff = PullRequestGenerator::preview(repo_manager, fixes)
+            .await
+            .unwrap();
+
+        assert!(diff.contains("--- a/README.md"));
+        assert!(diff.contains("-See https://old-url.com"));
+        assert!(diff.contains(&format!("+See {new_url}")));
+        assert!(!repo_manager.has_uncommitted_changes().unwrap());
+    }
+
     #[tokio::test]
     async fn test_create_fix_pr_with_no_changes() {
         use std::fs;
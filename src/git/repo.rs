@@ -1,11 +1,26 @@
-use crate::{GitHubUrl, file_exists_in_repo, find_last_commit_id};
+use crate::{
+    BitbucketUrl, CommitSigner, GitHubUrl, GitLabUrl, file_exists_in_repo, find_last_commit_id,
+};
 use git2::{
-    BranchType, Cred, Oid, PushOptions, RemoteCallbacks, Repository, Signature,
+    BranchType, Cred, DiffFormat, Oid, PushOptions, RemoteCallbacks, Repository, Signature,
     build::CheckoutBuilder,
 };
-use std::{env, fs, path::PathBuf, time};
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs, io,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time,
+};
 use tracing::{error, info};
 
+/// The directory under the system temporary directory that repositories are cloned into
+const CLONE_ROOT: &str = "github_repo_temp";
+
+/// The directories of the live `TempDirGuard`s, so they can be removed when the process is
+/// terminated without dropping them
+static ACTIVE_TEMP_DIRS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
 /// A guard that automatically removes a temporary directory when dropped.
 pub struct TempDirGuard {
     path: PathBuf,
@@ -19,6 +34,10 @@ impl TempDirGuard {
             fs::remove_dir_all(&path)?;
         }
         fs::create_dir_all(&path)?;
+        ACTIVE_TEMP_DIRS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(path.clone());
         Ok(Self { path })
     }
 
@@ -31,6 +50,108 @@ impl TempDirGuard {
 impl Drop for TempDirGuard {
     fn drop(&mut self) {
         let _ = fs::remove_dir_all(&self.path);
+        ACTIVE_TEMP_DIRS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|path| path != &self.path);
+    }
+}
+
+/// Removes the directories of all live `TempDirGuard`s.
+///
+/// Guards are not dropped when the process exits, so this should be called before exiting
+/// on a signal.
+pub fn remove_active_temp_dirs() {
+    let paths = std::mem::take(
+        &mut *ACTIVE_TEMP_DIRS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner),
+    );
+    for path in paths {
+        let _ = fs::remove_dir_all(path);
+    }
+}
+
+/// Removes the clones left behind by queensac processes that were killed before they could
+/// clean up, that is clones last modified more than `max_age` ago.
+///
+/// # Returns
+/// The number of removed clones
+pub fn sweep_stale_clones(max_age: time::Duration) -> io::Result<usize> {
+    sweep_stale_dirs(&env::temp_dir().join(CLONE_ROOT), max_age)
+}
+
+/// Removes the `<owner>/<clone>` directories under `root` last modified more than `max_age`
+/// ago, other than those of live guards.
+fn sweep_stale_dirs(root: &Path, max_age: time::Duration) -> io::Result<usize> {
+    let owner_dirs = match fs::read_dir(root) {
+        Ok(owner_dirs) => owner_dirs,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let active_dirs = ACTIVE_TEMP_DIRS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+
+    let mut removed = 0;
+    for owner_dir in owner_dirs {
+        let owner_dir = owner_dir?.path();
+        if !owner_dir.is_dir() {
+            continue;
+        }
+        for clone_dir in fs::read_dir(&owner_dir)? {
+            let clone_dir = clone_dir?;
+            let age = clone_dir
+                .metadata()?
+                .modified()?
+                .elapsed()
+                .unwrap_or_default();
+            if age < max_age || active_dirs.contains(&clone_dir.path()) {
+                continue;
+            }
+            fs::remove_dir_all(clone_dir.path())?;
+            removed += 1;
+        }
+        // Only succeeds once the owner directory is empty
+        let _ = fs::remove_dir(&owner_dir);
+    }
+    Ok(removed)
+}
+
+/// Controls how much of a repository is fetched when cloning.
+///
+/// Extracting links only needs the files of the checked out commit, so clones are
+/// shallow by default. Tracking moved files walks the history and needs a full clone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CloneOptions {
+    /// The number of commits to fetch, or `None` for the full history
+    pub depth: Option<u32>,
+    /// Whether to fetch only the branch named in the URL rather than all branches
+    pub single_branch: bool,
+}
+
+impl CloneOptions {
+    /// Fetches only the latest commit of the checked out branch.
+    pub fn shallow() -> Self {
+        Self {
+            depth: Some(1),
+            single_branch: true,
+        }
+    }
+
+    /// Fetches the full history of all branches.
+    pub fn full() -> Self {
+        Self {
+            depth: None,
+            single_branch: false,
+        }
+    }
+}
+
+impl Default for CloneOptions {
+    fn default() -> Self {
+        Self::shallow()
     }
 }
 
@@ -38,7 +159,11 @@ impl Drop for TempDirGuard {
 pub struct RepoManager {
     url: GitHubUrl,
     repo: Repository,
-    _temp_dir_guard: TempDirGuard,
+    signer: Option<CommitSigner>,
+    /// Whether the files of submodules are read along with the repository's own files
+    recurse_submodules: bool,
+    /// Removes the clone when dropped, `None` for a local checkout that must be kept
+    _temp_dir_guard: Option<TempDirGuard>,
 }
 
 impl RepoManager {
@@ -47,11 +172,53 @@ impl RepoManager {
         Self {
             url: url.clone(),
             repo,
-            _temp_dir_guard,
+            signer: None,
+            recurse_submodules: false,
+            _temp_dir_guard: Some(_temp_dir_guard),
         }
     }
 
-    /// Clones a Git repository from a GitHub URL.
+    /// Opens an existing local checkout, such as the working directory of a CI job.
+    ///
+    /// The checkout is used as is, including uncommitted changes, and is not removed when
+    /// the `RepoManager` is dropped. If its `origin` remote is a GitHub repository, that
+    /// repository is returned by [`RepoManager::get_github_url`].
+    ///
+    /// # Arguments
+    /// * `path` - A directory inside the checkout
+    pub fn open_local(path: &Path) -> Result<Self, git2::Error> {
+        let repo = Repository::discover(path)?;
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| git2::Error::from_str("Repository has no working directory"))?;
+        let url = repo
+            .find_remote("origin")
+            .ok()
+            .and_then(|remote| remote.url().and_then(|url| GitHubUrl::parse(url).ok()))
+            .unwrap_or_else(|| {
+                let name = workdir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                GitHubUrl::new("local".to_string(), name, None, None)
+            });
+
+        Ok(Self {
+            url,
+            repo,
+            signer: None,
+            recurse_submodules: false,
+            _temp_dir_guard: None,
+        })
+    }
+
+    /// Returns whether the repository is a local checkout opened with
+    /// [`RepoManager::open_local`] rather than a temporary clone.
+    pub fn is_local(&self) -> bool {
+        self._temp_dir_guard.is_none()
+    }
+
+    /// Clones a Git repository from a GitHub URL with the default, shallow [`CloneOptions`].
     ///
     /// # Arguments
     /// * `url` - The GitHub URL of the repository to clone
@@ -59,8 +226,81 @@ impl RepoManager {
     /// # Returns
     /// A `RepoManager` instance that will automatically clean up the cloned repository when dropped.
     pub fn from(url: &GitHubUrl) -> Result<Self, git2::Error> {
+        Self::from_with_options(url, CloneOptions::default())
+    }
+
+    /// Clones a Git repository from a GitHub URL.
+    ///
+    /// # Arguments
+    /// * `url` - The GitHub URL of the repository to clone
+    /// * `options` - How much of the repository to fetch
+    ///
+    /// # Returns
+    /// A `RepoManager` instance that will automatically clean up the cloned repository when dropped.
+    pub fn from_with_options(url: &GitHubUrl, options: CloneOptions) -> Result<Self, git2::Error> {
+        Self::clone_repository(&url.clone_url(), url, options)
+    }
+
+    /// Clones a Git repository from a GitLab URL.
+    ///
+    /// The GitLab namespace and project are used as the owner and repository name
+    /// returned by [`RepoManager::get_github_url`].
+    ///
+    /// # Arguments
+    /// * `url` - The GitLab URL of the repository to clone
+    pub fn from_gitlab(url: &GitLabUrl) -> Result<Self, git2::Error> {
+        Self::clone_repository(
+            &url.clone_url(),
+            &gitlab_identity(url),
+            CloneOptions::default(),
+        )
+    }
+
+    /// Clones a Git repository from a Bitbucket Cloud URL.
+    ///
+    /// The Bitbucket workspace and repository slug are used as the owner and repository
+    /// name returned by [`RepoManager::get_github_url`].
+    ///
+    /// # Arguments
+    /// * `url` - The Bitbucket URL of the repository to clone
+    pub fn from_bitbucket(url: &BitbucketUrl) -> Result<Self, git2::Error> {
+        Self::clone_repository(
+            &url.clone_url(),
+            &bitbucket_identity(url),
+            CloneOptions::default(),
+        )
+    }
+
+    /// Clones the working copy of a repository from a local bare mirror of it.
+    ///
+    /// The `origin` remote of the clone is pointed back at the repository itself, so that
+    /// branches are pushed there rather than to the mirror.
+    ///
+    /// # Arguments
+    /// * `mirror` - The path of the bare mirror
+    /// * `clone_url` - The URL of the mirrored repository
+    /// * `url` - The repository, as returned by [`RepoManager::get_github_url`]
+    pub(crate) fn clone_from_mirror(
+        mirror: &Path,
+        clone_url: &str,
+        url: &GitHubUrl,
+    ) -> Result<Self, git2::Error> {
+        let mirror_url = mirror
+            .to_str()
+            .ok_or_else(|| git2::Error::from_str("Mirror path is not valid UTF-8"))?;
+        // Local clones cannot be shallow, and copying the history is cheap anyway
+        let repo_manager = Self::clone_repository(mirror_url, url, CloneOptions::full())?;
+        repo_manager.repo.remote_set_url("origin", clone_url)?;
+        Ok(repo_manager)
+    }
+
+    fn clone_repository(
+        clone_url: &str,
+        url: &GitHubUrl,
+        options: CloneOptions,
+    ) -> Result<Self, git2::Error> {
         let temp_dir = env::temp_dir().join(format!(
-            "github_repo_temp/{}/{}_{}",
+            "{CLONE_ROOT}/{}/{}_{}",
             url.owner(),
             url.repo(),
             time::SystemTime::now()
@@ -75,16 +315,31 @@ impl RepoManager {
 
         let mut builder = git2::build::RepoBuilder::new();
 
+        if let Some(depth) = options.depth {
+            let mut fetch_options = git2::FetchOptions::new();
+            fetch_options.depth(i32::try_from(depth).unwrap_or(i32::MAX));
+            builder.fetch_options(fetch_options);
+        }
+
         if let Some(branch_name) = url.branch() {
             builder.branch(branch_name);
+            if options.single_branch {
+                let refspec =
+                    format!("+refs/heads/{branch_name}:refs/remotes/origin/{branch_name}");
+                builder.remote_create(move |repo, name, url| {
+                    repo.remote_with_fetch(name, url, &refspec)
+                });
+            }
         }
 
-        let repo = builder.clone(url.clone_url().as_str(), &temp_dir)?;
+        let repo = builder.clone(clone_url, &temp_dir)?;
 
         Ok(Self {
             url: url.clone(),
             repo,
-            _temp_dir_guard,
+            signer: None,
+            recurse_submodules: false,
+            _temp_dir_guard: Some(_temp_dir_guard),
         })
     }
 
@@ -92,7 +347,7 @@ impl RepoManager {
     ///
     /// # Returns
     /// * `Ok(Some(String))` - The current location of the file if found
-    /// * `Ok(None)` - If the file was not found
+    /// * `Ok(None)` - If the file was not found, or its renames lead back to an earlier path
     /// * `Err(git2::Error)` - If there was an error accessing the repository
     pub fn find_current_location(
         &self,
@@ -104,8 +359,14 @@ impl RepoManager {
 
         let repo = self.get_repo();
         let mut current_path = file_path.to_string();
+        let mut visited = HashSet::new();
 
         loop {
+            if !visited.insert(current_path.clone()) {
+                error!("Renames of {} lead back to {}", file_path, current_path);
+                return Ok(None);
+            }
+
             if file_exists_in_repo(repo, &current_path)? {
                 return Ok(Some(current_path));
             }
@@ -134,6 +395,85 @@ impl RepoManager {
         }
     }
 
+    /// Makes reading the repository's files also read the files of its submodules, each
+    /// from its own clone at the commit recorded in the repository.
+    pub fn enable_submodule_recursion(&mut self) {
+        self.recurse_submodules = true;
+    }
+
+    /// Returns whether the files of submodules are read along with the repository's own files.
+    pub fn recurses_submodules(&self) -> bool {
+        self.recurse_submodules
+    }
+
+    /// Returns the paths of the repository's submodules.
+    pub fn submodule_paths(&self) -> Vec<String> {
+        self.repo
+            .submodules()
+            .map(|submodules| {
+                submodules
+                    .iter()
+                    .filter_map(|submodule| submodule.path().to_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Clones a submodule of the repository at the commit recorded for it in the
+    /// checked out commit.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the submodule in the repository
+    pub fn clone_submodule(&self, path: &str) -> Result<Self, git2::Error> {
+        let submodule = self.repo.find_submodule(path)?;
+        let commit_id = submodule
+            .head_id()
+            .ok_or_else(|| git2::Error::from_str(&format!("Submodule {path} has no commit")))?;
+        let submodule_url = submodule
+            .url()
+            .ok_or_else(|| git2::Error::from_str(&format!("Submodule {path} has no URL")))?;
+        let clone_url = if submodule_url.starts_with("../") || submodule_url.starts_with("./") {
+            let origin = self.repo.find_remote("origin")?;
+            let origin_url = origin
+                .url()
+                .ok_or_else(|| git2::Error::from_str("Remote origin has no URL"))?;
+            url::Url::parse(&format!("{}/", origin_url.trim_end_matches('/')))
+                .and_then(|base| base.join(submodule_url))
+                .map_err(|e| git2::Error::from_str(&format!("Invalid submodule URL: {e}")))?
+                .to_string()
+        } else {
+            submodule_url.to_string()
+        };
+
+        let identity = GitHubUrl::new(
+            self.url.owner().to_string(),
+            format!("{}_{}", self.url.repo(), path.replace('/', "_")),
+            None,
+            None,
+        );
+        let manager = Self::clone_repository(&clone_url, &identity, CloneOptions::shallow())?;
+        if manager.repo.find_commit(commit_id).is_err() {
+            let mut fetch_options = git2::FetchOptions::new();
+            fetch_options.depth(1);
+            manager.repo.find_remote("origin")?.fetch(
+                &[commit_id.to_string()],
+                Some(&mut fetch_options),
+                None,
+            )?;
+        }
+        manager.repo.set_head_detached(commit_id)?;
+        manager
+            .repo
+            .checkout_head(Some(CheckoutBuilder::new().force()))?;
+
+        Ok(manager)
+    }
+
+    /// Sets the signer used to sign commits created by [`RepoManager::commit`].
+    pub fn set_commit_signer(&mut self, signer: CommitSigner) {
+        self.signer = Some(signer);
+    }
+
     /// Returns a reference to the managed Git repository.
     pub fn get_repo(&self) -> &Repository {
         &self.repo
@@ -221,28 +561,73 @@ impl RepoManager {
         let tree_id = index.write_tree()?;
         let tree = self.repo.find_tree(tree_id)?;
 
-        let head = self.repo.head()?;
+        let mut head = self.repo.head()?;
         let parent_commit = self.repo.find_commit(head.target().unwrap())?;
 
-        let commit_id = self.repo.commit(
-            Some(head.name().unwrap()),
-            &signature,
-            &signature,
-            message,
-            &tree,
-            &[&parent_commit],
-        )?;
+        let commit_id = match &self.signer {
+            Some(signer) => {
+                let buffer = self.repo.commit_create_buffer(
+                    &signature,
+                    &signature,
+                    message,
+                    &tree,
+                    &[&parent_commit],
+                )?;
+                let content = buffer
+                    .as_str()
+                    .ok_or_else(|| git2::Error::from_str("Commit content is not valid UTF-8"))?;
+                let commit_signature = signer.sign(content)?;
+
+                let commit_id = self.repo.commit_signed(content, &commit_signature, None)?;
+                head.set_target(commit_id, message)?;
+                commit_id
+            }
+            None => self.repo.commit(
+                Some(head.name().unwrap()),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &[&parent_commit],
+            )?,
+        };
 
         info!("Successfully created commit: {}", commit_id);
         Ok(commit_id)
     }
 
+    /// Adds a remote with the given name and URL to the repository
+    pub fn add_remote(&self, remote_name: &str, url: &str) -> Result<(), git2::Error> {
+        info!("Adding remote {}: {}", remote_name, url);
+
+        self.repo.remote(remote_name, url)?;
+        Ok(())
+    }
+
     /// Pushes the current branch to the remote repository
     pub async fn push(
         &self,
         remote_name: &str,
         branch_name: &str,
         github_token: &str,
+    ) -> Result<(), git2::Error> {
+        self.push_with_credentials(remote_name, branch_name, "x-access-token", github_token)
+            .await
+    }
+
+    /// Pushes a branch to a remote, authenticating over HTTPS with a username and password.
+    ///
+    /// # Arguments
+    /// * `remote_name` - The name of the remote to push to
+    /// * `branch_name` - The name of the local branch to push
+    /// * `username` - The username, e.g. the account name for Bitbucket app passwords
+    /// * `password` - The password or token
+    pub async fn push_with_credentials(
+        &self,
+        remote_name: &str,
+        branch_name: &str,
+        username: &str,
+        password: &str,
     ) -> Result<(), git2::Error> {
         info!("Pushing branch {} to remote {}", branch_name, remote_name);
 
@@ -250,8 +635,7 @@ impl RepoManager {
 
         // Set up authentication callbacks
         let mut callbacks = RemoteCallbacks::new();
-        callbacks
-            .credentials(move |_, _, _| Cred::userpass_plaintext("x-access-token", github_token));
+        callbacks.credentials(move |_, _, _| Cred::userpass_plaintext(username, password));
 
         // Create push options with authentication
         let mut push_options = PushOptions::new();
@@ -271,6 +655,37 @@ impl RepoManager {
         Ok(())
     }
 
+    /// Returns the default branch of the `origin` remote, e.g. `main`, `master` or `develop`.
+    ///
+    /// Reads `refs/remotes/origin/HEAD`. If the clone has no such reference, the branch
+    /// checked out by a clone of the default branch is used, and otherwise the remote is
+    /// asked for its default branch.
+    pub fn default_branch(&self) -> Result<String, git2::Error> {
+        const REMOTE_PREFIX: &str = "refs/remotes/origin/";
+
+        if let Ok(reference) = self.repo.find_reference("refs/remotes/origin/HEAD")
+            && let Some(target) = reference.symbolic_target()
+            && let Some(branch) = target.strip_prefix(REMOTE_PREFIX)
+        {
+            return Ok(branch.to_string());
+        }
+
+        if self.url.branch().is_none() && !self.is_local() {
+            return self.get_current_branch();
+        }
+
+        let mut remote = self.repo.find_remote("origin")?;
+        remote.connect(git2::Direction::Fetch)?;
+        let default_branch = remote.default_branch()?;
+        let default_branch = default_branch
+            .as_str()
+            .ok_or_else(|| git2::Error::from_str("Default branch is not valid UTF-8"))?;
+        Ok(default_branch
+            .strip_prefix("refs/heads/")
+            .unwrap_or(default_branch)
+            .to_string())
+    }
+
     /// Gets the current branch name
     pub fn get_current_branch(&self) -> Result<String, git2::Error> {
         let head = self.repo.head()?;
@@ -281,6 +696,157 @@ impl RepoManager {
         Ok(branch_name.to_string())
     }
 
+    /// Returns the ID of the commit checked out in the repository.
+    pub fn head_commit_id(&self) -> Result<String, git2::Error> {
+        Ok(self.repo.head()?.peel_to_commit()?.id().to_string())
+    }
+
+    /// Returns the paths of the files added, modified, deleted or renamed between a commit
+    /// and the checked out commit.
+    ///
+    /// The commit is fetched from `origin` if a shallow clone does not contain it.
+    ///
+    /// # Arguments
+    /// * `commit_id` - The ID of the commit to compare against
+    pub fn changed_paths_since(&self, commit_id: &str) -> Result<Vec<String>, git2::Error> {
+        let oid = Oid::from_str(commit_id)?;
+        if self.repo.find_commit(oid).is_err() {
+            let mut fetch_options = git2::FetchOptions::new();
+            fetch_options.depth(1);
+            self.repo
+                .find_remote("origin")?
+                .fetch(&[commit_id], Some(&mut fetch_options), None)?;
+        }
+
+        let old_tree = self.repo.find_commit(oid)?.tree()?;
+        let new_tree = self.repo.head()?.peel_to_tree()?;
+        let diff = self
+            .repo
+            .diff_tree_to_tree(Some(&old_tree), Some(&new_tree), None)?;
+
+        let mut paths = Vec::new();
+        for delta in diff.deltas() {
+            for file in [delta.old_file(), delta.new_file()] {
+                if let Some(path) = file.path().and_then(|path| path.to_str())
+                    && !paths.iter().any(|p| p == path)
+                {
+                    paths.push(path.to_string());
+                }
+            }
+        }
+        Ok(paths)
+    }
+
+    /// Returns the lines added or modified between two revisions, by the path of their file,
+    /// like the `+` lines of `git diff base...head`.
+    ///
+    /// The head is compared against the merge base of both revisions, so that changes made
+    /// on the base branch in the meantime are left out. Revisions missing from a shallow
+    /// clone are fetched from `origin`, and so is their full history if the merge base is
+    /// beyond the shallow history.
+    ///
+    /// # Errors
+    /// Fails if the revisions have no merge base, rather than comparing them directly,
+    /// which would count the changes made on the base branch as changes of the head.
+    ///
+    /// # Arguments
+    /// * `base` - The revision the changes were made on, e.g. `main`
+    /// * `head` - The revision with the changes, e.g. `HEAD` or `refs/pull/42/head`
+    ///
+    /// # Returns
+    /// The 1-based numbers of the added or modified lines of each changed file.
+    pub fn changed_lines(
+        &self,
+        base: &str,
+        head: &str,
+    ) -> Result<HashMap<String, HashSet<usize>>, git2::Error> {
+        let base = self.resolve_commit(base)?;
+        let head = self.resolve_commit(head)?;
+        let mut merge_base = self.repo.merge_base(base.id(), head.id());
+        if merge_base.is_err() && self.repo.is_shallow() {
+            info!(
+                "Fetching the history of {} and {} to find their merge base",
+                base.id(),
+                head.id()
+            );
+            let mut fetch_options = git2::FetchOptions::new();
+            // libgit2 unshallows the history when the maximum depth is requested
+            fetch_options.depth(i32::MAX);
+            merge_base = self
+                .repo
+                .find_remote("origin")
+                .and_then(|mut remote| {
+                    remote.fetch(
+                        &[base.id().to_string(), head.id().to_string()],
+                        Some(&mut fetch_options),
+                        None,
+                    )
+                })
+                .and_then(|_| self.repo.merge_base(base.id(), head.id()));
+        }
+        let base = match merge_base {
+            Ok(merge_base) => self.repo.find_commit(merge_base)?,
+            Err(e) => {
+                return Err(git2::Error::from_str(&format!(
+                    "No merge base of {} and {} could be found: {}",
+                    base.id(),
+                    head.id(),
+                    e.message()
+                )));
+            }
+        };
+
+        let mut options = git2::DiffOptions::new();
+        options.context_lines(0);
+        let mut diff = self.repo.diff_tree_to_tree(
+            Some(&base.tree()?),
+            Some(&head.tree()?),
+            Some(&mut options),
+        )?;
+        // Renamed files would otherwise count as entirely added
+        diff.find_similar(None)?;
+
+        let mut lines: HashMap<String, HashSet<usize>> = HashMap::new();
+        diff.foreach(
+            &mut |_, _| true,
+            None,
+            None,
+            Some(&mut |delta, _, line| {
+                if line.origin() == '+'
+                    && let Some(line_number) = line.new_lineno()
+                    && let Some(path) = delta.new_file().path().and_then(|path| path.to_str())
+                {
+                    lines
+                        .entry(path.to_string())
+                        .or_default()
+                        .insert(line_number as usize);
+                }
+                true
+            }),
+        )?;
+        Ok(lines)
+    }
+
+    /// Finds the commit of a revision, fetching it from `origin` if the clone does not
+    /// contain it.
+    ///
+    /// # Arguments
+    /// * `revision` - A commit ID, branch, or ref such as `refs/pull/42/head`
+    pub(crate) fn resolve_commit(&self, revision: &str) -> Result<git2::Commit<'_>, git2::Error> {
+        if let Ok(commit) = self
+            .repo
+            .revparse_single(revision)
+            .and_then(|object| object.peel_to_commit())
+        {
+            return Ok(commit);
+        }
+        let refspec = revision.strip_prefix("origin/").unwrap_or(revision);
+        self.repo
+            .find_remote("origin")?
+            .fetch(&[refspec], None, None)?;
+        self.repo.revparse_single("FETCH_HEAD")?.peel_to_commit()
+    }
+
     /// Checks if there are any uncommitted changes
     pub fn has_uncommitted_changes(&self) -> Result<bool, git2::Error> {
         let statuses = self.repo.statuses(Some(
@@ -308,6 +874,30 @@ impl RepoManager {
         Ok(false)
     }
 
+    /// Returns the unified diff between the index and the working directory
+    pub fn diff_workdir(&self) -> Result<String, git2::Error> {
+        let diff = self.repo.diff_index_to_workdir(None, None)?;
+
+        let mut patch = String::new();
+        diff.print(DiffFormat::Patch, |_, _, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                patch.push(line.origin());
+            }
+            patch.push_str(&String::from_utf8_lossy(line.content()));
+            true
+        })?;
+
+        Ok(patch)
+    }
+
+    /// Discards all changes in the working directory and staging area
+    pub fn discard_changes(&self) -> Result<(), git2::Error> {
+        let mut checkout_builder = CheckoutBuilder::new();
+        checkout_builder.force();
+
+        self.repo.checkout_head(Some(&mut checkout_builder))
+    }
+
     /// Gets the repository path
     pub fn get_repo_path(&self) -> PathBuf {
         self.repo.path().parent().unwrap().to_path_buf()
@@ -319,9 +909,73 @@ impl RepoManager {
     }
 }
 
+/// Finds the branch a missing branch was renamed to, by listing the branches of a remote
+/// repository like `git ls-remote`.
+///
+/// Branches are usually renamed to become the default branch, e.g. `master` to `main`,
+/// so the default branch of the remote is taken as the new name.
+///
+/// # Arguments
+/// * `clone_url` - The URL of the remote repository
+/// * `branch` - The branch a link points at
+///
+/// # Returns
+/// * `Ok(Some(String))` - The default branch, if `branch` does not exist anymore
+/// * `Ok(None)` - If `branch` still exists, is a tag or a commit ID, or the remote has no
+///   default branch
+/// * `Err(git2::Error)` - If the remote could not be listed
+pub fn find_renamed_branch(clone_url: &str, branch: &str) -> Result<Option<String>, git2::Error> {
+    let is_commit_id =
+        (7..=40).contains(&branch.len()) && branch.chars().all(|c| c.is_ascii_hexdigit());
+    if is_commit_id {
+        return Ok(None);
+    }
+
+    let mut remote = git2::Remote::create_detached(clone_url)?;
+    remote.connect(git2::Direction::Fetch)?;
+
+    let refs = [
+        format!("refs/heads/{branch}"),
+        format!("refs/tags/{branch}"),
+    ];
+    if remote
+        .list()?
+        .iter()
+        .any(|head| refs.iter().any(|name| head.name() == name))
+    {
+        return Ok(None);
+    }
+    let default_branch = remote.default_branch()?;
+    Ok(default_branch
+        .as_str()
+        .and_then(|name| name.strip_prefix("refs/heads/"))
+        .map(str::to_string))
+}
+
+/// Returns the GitLab namespace and project as the owner and repository of a `GitHubUrl`.
+pub(crate) fn gitlab_identity(url: &GitLabUrl) -> GitHubUrl {
+    GitHubUrl::new(
+        url.namespace().to_string(),
+        url.project().to_string(),
+        url.branch().map(str::to_string),
+        None,
+    )
+}
+
+/// Returns the Bitbucket workspace and repository as the owner and repository of a `GitHubUrl`.
+pub(crate) fn bitbucket_identity(url: &BitbucketUrl) -> GitHubUrl {
+    GitHubUrl::new(
+        url.workspace().to_string(),
+        url.repo().to_string(),
+        url.branch().map(str::to_string),
+        None,
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::git::mock_github::FixtureRepo;
     use serial_test::serial;
     use std::fs;
 
@@ -405,7 +1059,7 @@ mod tests {
         )
         .unwrap();
 
-        let repo_manager = RepoManager::from(&url).unwrap();
+        let repo_manager = RepoManager::from_with_options(&url, CloneOptions::full()).unwrap();
 
         assert_eq!(
             repo_manager.find_current_location(&url).unwrap(),
@@ -418,11 +1072,11 @@ mod tests {
         let url =
             GitHubUrl::parse("https://github.com/reddevilmidzy/kingsac/tree/main/foo/intrinsics")
                 .unwrap();
-        let repo_manager = RepoManager::from(&url).unwrap();
+        let repo_manager = RepoManager::from_with_options(&url, CloneOptions::full()).unwrap();
 
         assert_eq!(
             repo_manager.find_current_location(&url).unwrap(),
-            Some("foo/".to_string())
+            Some("foo".to_string())
         )
     }
 
@@ -544,4 +1198,103 @@ mod tests {
         // Should not have uncommitted changes
         assert!(!repo_manager.has_uncommitted_changes().unwrap());
     }
+
+    #[test]
+    fn test_find_renamed_branch() {
+        let fixture = FixtureRepo::new(&[("README.md", "# kingsac\n")]);
+
+        assert_eq!(
+            find_renamed_branch(&fixture.origin_url(), "master").unwrap(),
+            Some("main".to_string())
+        );
+        assert_eq!(
+            find_renamed_branch(&fixture.origin_url(), "main").unwrap(),
+            None
+        );
+        assert_eq!(
+            find_renamed_branch(&fixture.origin_url(), "0123abc").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sweep_stale_dirs_keeps_live_clones() {
+        let root = TempDirGuard::new(env::temp_dir().join(format!(
+            "queensac_sweep_test_{}",
+            chrono::Utc::now().timestamp_nanos_opt().unwrap()
+        )))
+        .unwrap();
+        let stale = root.get_path().join("reddevilmidzy/kingsac_1");
+        fs::create_dir_all(stale.join(".git")).unwrap();
+        let live = TempDirGuard::new(root.get_path().join("reddevilmidzy/queensac_2")).unwrap();
+
+        assert_eq!(
+            sweep_stale_dirs(root.get_path(), time::Duration::from_secs(3600)).unwrap(),
+            0
+        );
+        assert_eq!(
+            sweep_stale_dirs(root.get_path(), time::Duration::ZERO).unwrap(),
+            1
+        );
+        assert!(!stale.exists());
+        assert!(live.get_path().exists());
+
+        drop(live);
+        assert_eq!(
+            sweep_stale_dirs(root.get_path(), time::Duration::ZERO).unwrap(),
+            0
+        );
+        assert!(!root.get_path().join("reddevilmidzy").exists());
+    }
+
+    #[test]
+    fn test_default_branch() {
+        let fixture = FixtureRepo::new(&[("README.md", "# kingsac\n")]);
+        let repo_manager = fixture.clone_as("defaultsac");
+        assert_eq!(repo_manager.default_branch().unwrap(), "main");
+
+        // Without refs/remotes/origin/HEAD the remote is asked
+        if let Ok(mut reference) = repo_manager
+            .get_repo()
+            .find_reference("refs/remotes/origin/HEAD")
+        {
+            reference.delete().unwrap();
+        }
+        assert_eq!(repo_manager.default_branch().unwrap(), "main");
+    }
+
+    #[test]
+    fn test_changed_lines_without_merge_base_in_shallow_clone() {
+        let fixture = FixtureRepo::new(&[("README.md", "# kingsac\n")]);
+        let repo_manager = fixture.clone_as("shallowsac");
+        let repo = repo_manager.get_repo();
+        let root = repo.head().unwrap().peel_to_commit().unwrap();
+        let signature = Signature::now("queensac", "queensac@example.com").unwrap();
+        let commit = |file: &str, content: &str| {
+            let mut builder = repo.treebuilder(Some(&root.tree().unwrap())).unwrap();
+            let blob = repo.blob(content.as_bytes()).unwrap();
+            builder.insert(file, blob, 0o100644).unwrap();
+            let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+            repo.commit(None, &signature, &signature, "Change", &tree, &[&root])
+                .unwrap()
+        };
+        let base = commit("FAQ.md", "Changed on the base branch\n");
+        let head = commit("README.md", "# kingsac\n\nChanged on the head branch\n");
+
+        let lines = repo_manager
+            .changed_lines(&base.to_string(), &head.to_string())
+            .unwrap();
+        assert_eq!(lines.keys().collect::<Vec<_>>(), vec!["README.md"]);
+
+        // Cut the history at both commits, as a shallow clone of depth 1 does. The local
+        // origin cannot deepen it, so the merge base stays missing.
+        fs::write(repo.path().join("shallow"), format!("{base}\n{head}\n")).unwrap();
+        let shallow = RepoManager::open_local(&repo_manager.get_repo_path()).unwrap();
+        assert!(shallow.get_repo().is_shallow());
+
+        let error = shallow
+            .changed_lines(&base.to_string(), &head.to_string())
+            .unwrap_err();
+        assert!(error.message().starts_with("No merge base of"));
+    }
 }
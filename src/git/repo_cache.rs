@@ -0,0 +1,232 @@
+use crate::{BitbucketUrl, GitHubUrl, GitLabUrl, RepoManager, bitbucket_identity, gitlab_identity};
+use git2::{FetchOptions, FetchPrune, Repository};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tracing::{error, info};
+use url::Url;
+
+/// The file in each mirror whose modification time records when the mirror was last used
+const LAST_USED_FILE: &str = "queensac_last_used";
+
+/// Mirrors all branches of the repository as local branches of the bare mirror
+const MIRROR_REFSPEC: &str = "+refs/heads/*:refs/heads/*";
+
+/// Keeps bare mirrors of checked repositories on disk, so that checking a repository
+/// again only fetches its new commits instead of cloning it from scratch.
+///
+/// Each check still works on its own temporary clone, made from the mirror. When the
+/// mirrors take up more than the disk budget, the least recently used ones are removed.
+#[derive(Debug, Clone)]
+pub struct RepoCache {
+    root: PathBuf,
+    max_bytes: u64,
+}
+
+impl RepoCache {
+    /// Creates a repository cache.
+    ///
+    /// # Arguments
+    /// * `root` - The directory the mirrors are kept in
+    /// * `max_bytes` - The disk budget of all mirrors together
+    pub fn new(root: PathBuf, max_bytes: u64) -> Self {
+        Self { root, max_bytes }
+    }
+
+    /// Returns the default cache directory, `queensac/repos` in the user's cache directory.
+    ///
+    /// Uses `$XDG_CACHE_HOME`, then `$HOME/.cache`, then the temporary directory.
+    pub fn default_root() -> PathBuf {
+        let cache_dir = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+            .unwrap_or_else(std::env::temp_dir);
+
+        cache_dir.join("queensac").join("repos")
+    }
+
+    /// Clones a GitHub repository through its cached mirror.
+    ///
+    /// # Returns
+    /// A `RepoManager` instance that will automatically clean up the clone when dropped.
+    pub fn clone_repository(&self, url: &GitHubUrl) -> Result<RepoManager, git2::Error> {
+        self.checkout(&url.clone_url(), url)
+    }
+
+    /// Clones a GitLab repository through its cached mirror, like [`RepoManager::from_gitlab`].
+    pub fn clone_gitlab(&self, url: &GitLabUrl) -> Result<RepoManager, git2::Error> {
+        self.checkout(&url.clone_url(), &gitlab_identity(url))
+    }
+
+    /// Clones a Bitbucket repository through its cached mirror, like
+    /// [`RepoManager::from_bitbucket`].
+    pub fn clone_bitbucket(&self, url: &BitbucketUrl) -> Result<RepoManager, git2::Error> {
+        self.checkout(&url.clone_url(), &bitbucket_identity(url))
+    }
+
+    fn checkout(&self, clone_url: &str, url: &GitHubUrl) -> Result<RepoManager, git2::Error> {
+        let mirror = self.update_mirror(clone_url)?;
+        let repo_manager = RepoManager::clone_from_mirror(&mirror, clone_url, url)?;
+
+        if let Err(e) = self.evict(&mirror) {
+            error!("Failed to evict cached repositories: {}", e);
+        }
+        Ok(repo_manager)
+    }
+
+    /// Returns the mirror directory of a repository, e.g. `github.com/owner/repo.git`.
+    fn mirror_path(&self, clone_url: &str) -> PathBuf {
+        let relative_path = match Url::parse(clone_url) {
+            Ok(url) if url.host_str().is_some() => format!(
+                "{}/{}",
+                url.host_str().unwrap_or_default(),
+                url.path().trim_matches('/').trim_end_matches(".git")
+            ),
+            _ => clone_url
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect(),
+        };
+        self.root.join(format!("{relative_path}.git"))
+    }
+
+    /// Creates or fetches the mirror of a repository and marks it as used.
+    fn update_mirror(&self, clone_url: &str) -> Result<PathBuf, git2::Error> {
+        let path = self.mirror_path(clone_url);
+        let repo = match Repository::open_bare(&path) {
+            Ok(repo) => repo,
+            Err(_) => {
+                info!("Creating cached mirror of {}", clone_url);
+                if path.exists() {
+                    fs::remove_dir_all(&path).map_err(|e| {
+                        git2::Error::from_str(&format!("Failed to remove broken mirror: {e}"))
+                    })?;
+                }
+                let repo = Repository::init_bare(&path)?;
+                repo.remote_with_fetch("origin", clone_url, MIRROR_REFSPEC)?;
+                repo
+            }
+        };
+
+        let mut remote = repo.find_remote("origin")?;
+        let mut fetch_options = FetchOptions::new();
+        fetch_options.prune(FetchPrune::On);
+        remote.fetch(&[MIRROR_REFSPEC], Some(&mut fetch_options), None)?;
+        // The advertised refs stay available after the fetch, so HEAD can follow the
+        // default branch without connecting again
+        if let Ok(default_branch) = remote.default_branch()
+            && let Some(default_branch) = default_branch.as_str()
+        {
+            repo.set_head(default_branch)?;
+        }
+
+        fs::write(path.join(LAST_USED_FILE), "")
+            .map_err(|e| git2::Error::from_str(&format!("Failed to mark mirror as used: {e}")))?;
+        Ok(path)
+    }
+
+    /// Removes the least recently used mirrors, other than `keep`, until the mirrors fit
+    /// in the disk budget.
+    fn evict(&self, keep: &Path) -> io::Result<()> {
+        let mut mirrors = Vec::new();
+        find_mirrors(&self.root, &mut mirrors)?;
+        mirrors.sort_by_key(|(_, last_used, _)| *last_used);
+
+        let mut total_bytes: u64 = mirrors.iter().map(|(_, _, size)| size).sum();
+        for (path, _, size) in mirrors {
+            if total_bytes <= self.max_bytes {
+                break;
+            }
+            if path == keep {
+                continue;
+            }
+            info!("Evicting cached mirror {}", path.display());
+            fs::remove_dir_all(&path)?;
+            total_bytes -= size;
+        }
+        Ok(())
+    }
+}
+
+/// Collects the mirrors under a directory with the time they were last used and their size.
+fn find_mirrors(dir: &Path, mirrors: &mut Vec<(PathBuf, SystemTime, u64)>) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    for entry in entries {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let path = entry.path();
+        match fs::metadata(path.join(LAST_USED_FILE)) {
+            Ok(metadata) => {
+                let size = dir_size(&path)?;
+                mirrors.push((path, metadata.modified()?, size));
+            }
+            Err(_) => find_mirrors(&path, mirrors)?,
+        }
+    }
+    Ok(())
+}
+
+fn dir_size(dir: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else if file_type.is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RepoSource, git::mock_github::FixtureRepo, git::repo::TempDirGuard};
+
+    #[tokio::test]
+    async fn test_repo_cache_reuses_mirrors_and_evicts_least_recently_used() {
+        let guard = TempDirGuard::new(std::env::temp_dir().join(format!(
+            "queensac_repo_cache_test_{}",
+            chrono::Utc::now().timestamp_nanos_opt().unwrap()
+        )))
+        .unwrap();
+        let cache = RepoCache::new(guard.get_path().clone(), 0);
+        let docs = FixtureRepo::new(&[("README.md", "https://example.com/docs\n")]);
+        let blog = FixtureRepo::new(&[("README.md", "https://example.com/blog\n")]);
+        let url = GitHubUrl::new(
+            "reddevilmidzy".to_string(),
+            "docs".to_string(),
+            Some("main".to_string()),
+            None,
+        );
+
+        let repo_manager = cache.checkout(&docs.origin_url(), &url).unwrap();
+        let files = repo_manager.read_files().await.unwrap();
+        assert_eq!(files[0].content, "https://example.com/docs\n");
+        assert_eq!(
+            repo_manager.get_repo().find_remote("origin").unwrap().url(),
+            Some(docs.origin_url().as_str())
+        );
+        let docs_mirror = cache.mirror_path(&docs.origin_url());
+        assert!(docs_mirror.join(LAST_USED_FILE).exists());
+
+        // A second checkout fetches into the existing mirror
+        let repo_manager = cache.checkout(&docs.origin_url(), &url).unwrap();
+        assert_eq!(repo_manager.read_files().await.unwrap(), files);
+
+        // The budget only fits one mirror, so the least recently used one is evicted
+        cache.checkout(&blog.origin_url(), &url).unwrap();
+        assert!(!docs_mirror.exists());
+        assert!(cache.mirror_path(&blog.origin_url()).exists());
+    }
+}
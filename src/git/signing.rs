@@ -0,0 +1,110 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Signs commit contents created by queensac so that they satisfy
+/// "require signed commits" branch protection rules.
+///
+/// The public key matching the configured private key must be registered on the
+/// GitHub account the commits are attributed to, otherwise GitHub marks them as unverified.
+#[derive(Debug, Clone)]
+pub enum CommitSigner {
+    /// Signs with `gpg` using the given key ID
+    Gpg { key_id: String },
+    /// Signs with `ssh-keygen -Y sign` using the private key at the given path
+    Ssh { key_path: String },
+}
+
+impl CommitSigner {
+    /// Creates a signer for the given format.
+    ///
+    /// # Arguments
+    /// * `format` - Either `gpg` or `ssh`
+    /// * `key` - The GPG key ID, or the path to the SSH private key
+    pub fn new(format: &str, key: String) -> Result<Self, git2::Error> {
+        match format {
+            "gpg" => Ok(Self::Gpg { key_id: key }),
+            "ssh" => Ok(Self::Ssh { key_path: key }),
+            other => Err(git2::Error::from_str(&format!(
+                "Unsupported signing format: {other}"
+            ))),
+        }
+    }
+
+    /// Creates a signer from environment variables, if signing is configured.
+    ///
+    /// # Environment Variables
+    /// * `QUEENSAC_SIGNING_KEY` - The GPG key ID or SSH private key path
+    /// * `QUEENSAC_SIGNING_FORMAT` - `gpg` (default) or `ssh`
+    pub fn from_env() -> Result<Option<Self>, git2::Error> {
+        let Ok(key) = std::env::var("QUEENSAC_SIGNING_KEY") else {
+            return Ok(None);
+        };
+        let format = std::env::var("QUEENSAC_SIGNING_FORMAT").unwrap_or("gpg".to_string());
+
+        Self::new(&format, key).map(Some)
+    }
+
+    /// Produces an armored signature for the given commit content.
+    pub fn sign(&self, content: &str) -> Result<String, git2::Error> {
+        let mut command = match self {
+            Self::Gpg { key_id } => {
+                let mut command = Command::new("gpg");
+                command.args(["--status-fd=2", "-bsau", key_id]);
+                command
+            }
+            Self::Ssh { key_path } => {
+                let mut command = Command::new("ssh-keygen");
+                command.args(["-Y", "sign", "-n", "git", "-f", key_path]);
+                command
+            }
+        };
+
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| git2::Error::from_str(&format!("Failed to run signing program: {e}")))?;
+
+        child
+            .stdin
+            .take()
+            .ok_or_else(|| git2::Error::from_str("Failed to open signing program stdin"))?
+            .write_all(content.as_bytes())
+            .map_err(|e| git2::Error::from_str(&format!("Failed to write commit content: {e}")))?;
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| git2::Error::from_str(&format!("Failed to sign commit: {e}")))?;
+
+        if !output.status.success() {
+            return Err(git2::Error::from_str(&format!(
+                "Failed to sign commit: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )));
+        }
+
+        String::from_utf8(output.stdout)
+            .map_err(|e| git2::Error::from_str(&format!("Invalid signature output: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_signer() {
+        assert!(matches!(
+            CommitSigner::new("gpg", "ABCDEF".to_string()),
+            Ok(CommitSigner::Gpg { .. })
+        ));
+        assert!(matches!(
+            CommitSigner::new("ssh", "~/.ssh/id_ed25519".to_string()),
+            Ok(CommitSigner::Ssh { .. })
+        ));
+        assert!(CommitSigner::new("x509", "key".to_string()).is_err());
+    }
+}
@@ -0,0 +1,535 @@
+use crate::{GitHubUrl, RepoManager};
+use serde::Deserialize;
+use thiserror::Error;
+use tokio::task::JoinSet;
+use tracing::error;
+
+/// The first line of git-lfs pointer files, which are skipped since they are not the
+/// actual file content.
+const LFS_POINTER_PREFIX: &str = "version https://git-lfs.github.com/spec/";
+
+/// The number of leading bytes searched for a NUL byte to tell binary files from text.
+const BINARY_CHECK_LEN: usize = 8000;
+
+/// The maximum number of files downloaded at the same time by [`GitHubTreeSource`].
+const MAX_CONCURRENT_DOWNLOADS: usize = 16;
+
+/// Represents errors that can occur while reading the files of a repository.
+#[derive(Debug, Error)]
+pub enum SourceError {
+    #[error("Git operation failed: {0}")]
+    Git(#[from] git2::Error),
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("GitHub API error: {0}")]
+    GitHub(String),
+}
+
+/// A text file of a repository snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFile {
+    /// The path of the file relative to the repository root
+    pub path: String,
+    pub content: String,
+    /// Whether the file is not valid UTF-8, so that its content was decoded lossily with
+    /// the invalid bytes replaced
+    pub lossy: bool,
+}
+
+impl SourceFile {
+    /// Decodes the content of a text file, or returns `None` for binary files and
+    /// git-lfs pointers.
+    ///
+    /// Text in other encodings, such as Latin-1, is decoded lossily so that its links
+    /// are still found.
+    pub(crate) fn decode(path: String, bytes: &[u8]) -> Option<Self> {
+        // Like git, consider a file binary if it has a NUL byte near the start
+        if bytes.iter().take(BINARY_CHECK_LEN).any(|byte| *byte == 0) {
+            return None;
+        }
+        let (content, lossy) = match std::str::from_utf8(bytes) {
+            Ok(content) => (content.to_string(), false),
+            Err(_) => (String::from_utf8_lossy(bytes).into_owned(), true),
+        };
+        (!is_lfs_pointer(&content)).then_some(Self {
+            path,
+            content,
+            lossy,
+        })
+    }
+}
+
+/// Provides the files of a repository snapshot that links are extracted from.
+///
+/// Link extraction only needs file contents, so a source does not have to be a git
+/// clone. Binary files, git-lfs pointers and submodules are left out, unless submodule
+/// recursion is enabled on a [`RepoManager`].
+pub trait RepoSource {
+    /// Reads the text files of the snapshot.
+    fn read_files(&self) -> impl Future<Output = Result<Vec<SourceFile>, SourceError>>;
+}
+
+impl RepoSource for RepoManager {
+    async fn read_files(&self) -> Result<Vec<SourceFile>, SourceError> {
+        if self.is_local() {
+            Ok(read_workdir_files(self.get_repo())?)
+        } else {
+            Ok(read_clone_files(self)?)
+        }
+    }
+}
+
+/// Reads the files of a clone, and those of its submodules if enabled.
+fn read_clone_files(repo_manager: &RepoManager) -> Result<Vec<SourceFile>, git2::Error> {
+    let mut files = read_head_files(repo_manager.get_repo())?;
+    if !repo_manager.recurses_submodules() {
+        return Ok(files);
+    }
+
+    for path in repo_manager.submodule_paths() {
+        let mut submodule = match repo_manager.clone_submodule(&path) {
+            Ok(submodule) => submodule,
+            Err(e) => {
+                error!("Failed to clone submodule {}: {}", path, e);
+                continue;
+            }
+        };
+        submodule.enable_submodule_recursion();
+        files.extend(
+            read_clone_files(&submodule)?
+                .into_iter()
+                .map(|file| SourceFile {
+                    path: format!("{path}/{}", file.path),
+                    ..file
+                }),
+        );
+    }
+    Ok(files)
+}
+
+/// Returns whether a file is a git-lfs pointer rather than the file it stands for.
+fn is_lfs_pointer(content: &str) -> bool {
+    content.starts_with(LFS_POINTER_PREFIX)
+}
+
+/// Reads the text files of the commit checked out in a repository.
+pub(crate) fn read_head_files(repo: &git2::Repository) -> Result<Vec<SourceFile>, git2::Error> {
+    match repo.head().and_then(|head| head.peel_to_tree()) {
+        Ok(tree) => read_tree_files(repo, &tree),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+/// Reads the text files of a tree, such as the tree of a commit.
+pub(crate) fn read_tree_files(
+    repo: &git2::Repository,
+    tree: &git2::Tree,
+) -> Result<Vec<SourceFile>, git2::Error> {
+    let mut files = Vec::new();
+    tree.walk(git2::TreeWalkMode::PreOrder, |dir, entry| {
+        if let Some(name) = entry.name() {
+            let path = if dir.is_empty() {
+                name.to_string()
+            } else {
+                format!("{dir}/{name}").replace("//", "/")
+            };
+
+            if entry.kind() == Some(git2::ObjectType::Blob)
+                && let Ok(blob) = entry.to_object(repo)
+                && let Ok(blob) = blob.peel_to_blob()
+                && let Some(file) = SourceFile::decode(path, blob.content())
+            {
+                files.push(file);
+            }
+        }
+        git2::TreeWalkResult::Ok
+    })?;
+
+    Ok(files)
+}
+
+/// Reads the text files of a working directory as they are on disk.
+///
+/// Tracked files include their uncommitted changes, and untracked files are included
+/// unless they are ignored.
+pub(crate) fn read_workdir_files(repo: &git2::Repository) -> Result<Vec<SourceFile>, git2::Error> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| git2::Error::from_str("Repository has no working directory"))?;
+
+    let mut paths: Vec<String> = repo
+        .index()?
+        .iter()
+        .filter_map(|entry| String::from_utf8(entry.path).ok())
+        .collect();
+    let statuses = repo.statuses(Some(
+        git2::StatusOptions::new()
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false),
+    ))?;
+    paths.extend(
+        statuses
+            .iter()
+            .filter(|entry| entry.status().is_wt_new())
+            .filter_map(|entry| entry.path().map(str::to_string)),
+    );
+    paths.sort();
+    paths.dedup();
+
+    Ok(paths
+        .into_iter()
+        .filter_map(|path| {
+            let bytes = std::fs::read(workdir.join(&path)).ok()?;
+            SourceFile::decode(path, &bytes)
+        })
+        .collect())
+}
+
+/// Reads a GitHub repository through the Git Trees API without cloning it.
+///
+/// The file list comes from a single recursive tree request, and the file contents are
+/// downloaded from `raw.githubusercontent.com`, which does not count against the API
+/// rate limit.
+#[derive(Debug, Clone)]
+pub struct GitHubTreeSource {
+    url: GitHubUrl,
+    client: reqwest::Client,
+    api_url: String,
+    raw_url: String,
+    access_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Repository {
+    default_branch: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Commit {
+    sha: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tree {
+    tree: Vec<TreeEntry>,
+    truncated: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct TreeEntry {
+    path: String,
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+impl GitHubTreeSource {
+    /// Creates a source for the branch of a GitHub URL, or the default branch if the URL
+    /// has none.
+    ///
+    /// # Environment Variables
+    /// * `QUEENSAC_GITHUB_TOKEN` - Optional token used for the API requests
+    /// * `QUEENSAC_GITHUB_API_URL` - Base URL of the GitHub API, defaults to `https://api.github.com`
+    pub fn new(url: GitHubUrl) -> Result<Self, SourceError> {
+        let client = reqwest::Client::builder()
+            .user_agent("queensac")
+            .timeout(std::time::Duration::from_secs(30))
+            .build()?;
+        let api_url = std::env::var("QUEENSAC_GITHUB_API_URL")
+            .map(|api_url| api_url.trim_end_matches('/').to_string())
+            .unwrap_or_else(|_| "https://api.github.com".to_string());
+
+        Ok(Self {
+            url,
+            client,
+            api_url,
+            raw_url: "https://raw.githubusercontent.com".to_string(),
+            access_token: std::env::var("QUEENSAC_GITHUB_TOKEN").ok(),
+        })
+    }
+
+    /// Sets the base URLs of the GitHub API and of the raw file host.
+    pub fn set_endpoints(&mut self, api_url: &str, raw_url: &str) {
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self.raw_url = raw_url.trim_end_matches('/').to_string();
+    }
+
+    /// Resolves the branch to read to the SHA of its head commit.
+    async fn head_sha(&self) -> Result<String, SourceError> {
+        let branch = match self.url.branch() {
+            Some(branch) => branch.to_string(),
+            None => {
+                self.get::<Repository>(&format!("/repos/{}/{}", self.url.owner(), self.url.repo()))
+                    .await?
+                    .default_branch
+            }
+        };
+        let commit: Commit = self
+            .get(&format!(
+                "/repos/{}/{}/commits/{}",
+                self.url.owner(),
+                self.url.repo(),
+                branch
+            ))
+            .await?;
+        Ok(commit.sha)
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, SourceError> {
+        let mut request = self
+            .client
+            .get(format!("{}{}", self.api_url, path))
+            .header("Accept", "application/vnd.github+json");
+        if let Some(token) = &self.access_token {
+            request = request.bearer_auth(token);
+        }
+
+        Ok(request.send().await?.error_for_status()?.json().await?)
+    }
+
+    /// Returns the raw URL of a file at a commit.
+    fn raw_file_url(&self, sha: &str, path: &str) -> Result<url::Url, SourceError> {
+        let mut url = url::Url::parse(&self.raw_url)
+            .map_err(|e| SourceError::GitHub(format!("Invalid raw file URL: {e}")))?;
+        url.path_segments_mut()
+            .map_err(|_| SourceError::GitHub("Invalid raw file URL".to_string()))?
+            .pop_if_empty()
+            .extend([self.url.owner(), self.url.repo(), sha])
+            .extend(path.split('/'));
+        Ok(url)
+    }
+}
+
+impl RepoSource for GitHubTreeSource {
+    async fn read_files(&self) -> Result<Vec<SourceFile>, SourceError> {
+        let sha = self.head_sha().await?;
+        let tree: Tree = self
+            .get(&format!(
+                "/repos/{}/{}/git/trees/{}?recursive=1",
+                self.url.owner(),
+                self.url.repo(),
+                sha
+            ))
+            .await?;
+        if tree.truncated {
+            return Err(SourceError::GitHub(
+                "The repository tree is too large for the Git Trees API".to_string(),
+            ));
+        }
+
+        let mut files = Vec::new();
+        let mut downloads = JoinSet::new();
+        for entry in tree.tree.into_iter().filter(|entry| entry.kind == "blob") {
+            if downloads.len() >= MAX_CONCURRENT_DOWNLOADS
+                && let Some(file) = downloads.join_next().await
+            {
+                files.extend(file.map_err(|e| SourceError::GitHub(e.to_string()))??);
+            }
+
+            let url = self.raw_file_url(&sha, &entry.path)?;
+            let client = self.client.clone();
+            downloads.spawn(async move {
+                let bytes = client
+                    .get(url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .bytes()
+                    .await?;
+                Ok::<_, SourceError>(SourceFile::decode(entry.path, &bytes))
+            });
+        }
+        while let Some(file) = downloads.join_next().await {
+            files.extend(file.map_err(|e| SourceError::GitHub(e.to_string()))??);
+        }
+
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::repo::TempDirGuard;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_github_tree_source() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/reddevilmidzy/kingsac"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "default_branch": "main"
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/reddevilmidzy/kingsac/commits/main"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({ "sha": "abc123" })),
+            )
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/reddevilmidzy/kingsac/git/trees/abc123"))
+            .and(query_param("recursive", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "sha": "abc123",
+                "truncated": false,
+                "tree": [
+                    { "path": "README.md", "type": "blob" },
+                    { "path": "docs", "type": "tree" },
+                    { "path": "docs/my guide.md", "type": "blob" },
+                    { "path": "logo.png", "type": "blob" },
+                ]
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/raw/reddevilmidzy/kingsac/abc123/README.md"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("# kingsac\n"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/raw/reddevilmidzy/kingsac/abc123/docs/my%20guide.md"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("https://example.com\n"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/raw/reddevilmidzy/kingsac/abc123/logo.png"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_bytes(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec()),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let mut source = GitHubTreeSource::new(GitHubUrl::new(
+            "reddevilmidzy".to_string(),
+            "kingsac".to_string(),
+            None,
+            None,
+        ))
+        .unwrap();
+        source.set_endpoints(&mock_server.uri(), &format!("{}/raw", mock_server.uri()));
+
+        let files = source.read_files().await.unwrap();
+
+        assert_eq!(
+            files,
+            vec![
+                SourceFile {
+                    path: "README.md".to_string(),
+                    content: "# kingsac\n".to_string(),
+                    lossy: false,
+                },
+                SourceFile {
+                    path: "docs/my guide.md".to_string(),
+                    content: "https://example.com\n".to_string(),
+                    lossy: false,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_local_checkout_includes_uncommitted_changes() {
+        let guard = TempDirGuard::new(std::env::temp_dir().join(format!(
+            "queensac_local_test_{}",
+            chrono::Utc::now().timestamp_nanos_opt().unwrap()
+        )))
+        .unwrap();
+        let dir = guard.get_path();
+        let repo = git2::Repository::init(dir).unwrap();
+        repo.remote("origin", "git@github.com:reddevilmidzy/kingsac.git")
+            .unwrap();
+        std::fs::write(dir.join("README.md"), "# kingsac\n").unwrap();
+        std::fs::write(dir.join(".gitignore"), "target/\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(std::path::Path::new("README.md")).unwrap();
+        index.add_path(std::path::Path::new(".gitignore")).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("queensac", "queensac@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+            .unwrap();
+
+        std::fs::write(dir.join("README.md"), "https://example.com\n").unwrap();
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("docs/new.md"), "https://example.org\n").unwrap();
+        std::fs::create_dir_all(dir.join("target")).unwrap();
+        std::fs::write(dir.join("target/build.log"), "https://example.net\n").unwrap();
+
+        let repo_manager = RepoManager::open_local(&dir.join("docs")).unwrap();
+        assert!(repo_manager.is_local());
+        assert_eq!(repo_manager.get_github_url().owner(), "reddevilmidzy");
+        assert_eq!(repo_manager.get_github_url().repo(), "kingsac");
+
+        let paths: Vec<String> = repo_manager
+            .read_files()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|file| format!("{}: {}", file.path, file.content.trim()))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                ".gitignore: target/",
+                "README.md: https://example.com",
+                "docs/new.md: https://example.org",
+            ]
+        );
+
+        drop(repo_manager);
+        assert!(dir.join("README.md").exists());
+    }
+
+    #[test]
+    fn test_read_head_files_skips_binaries_submodules_and_lfs_pointers() {
+        let guard = TempDirGuard::new(std::env::temp_dir().join(format!(
+            "queensac_lfs_test_{}",
+            chrono::Utc::now().timestamp_nanos_opt().unwrap()
+        )))
+        .unwrap();
+        let repo = git2::Repository::init(guard.get_path()).unwrap();
+        let readme = repo.blob(b"https://example.com\n").unwrap();
+        let pointer = repo
+            .blob(b"version https://git-lfs.github.com/spec/v1\noid sha256:4d7a\nsize 12345\n")
+            .unwrap();
+        let submodule_commit =
+            git2::Oid::from_str("0123456789abcdef0123456789abcdef01234567").unwrap();
+        let mut tree = repo.treebuilder(None).unwrap();
+        tree.insert("README.md", readme, 0o100644).unwrap();
+        tree.insert("logo.md", pointer, 0o100644).unwrap();
+        let image = repo.blob(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        tree.insert("logo.png", image, 0o100644).unwrap();
+        let latin1 = repo.blob(b"Caf\xe9: https://example.org\n").unwrap();
+        tree.insert("notes.txt", latin1, 0o100644).unwrap();
+        tree.insert("vendor", submodule_commit, 0o160000).unwrap();
+        let tree = repo.find_tree(tree.write().unwrap()).unwrap();
+        let signature = git2::Signature::now("queensac", "queensac@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "Initial", &tree, &[])
+            .unwrap();
+
+        let files = read_head_files(&repo).unwrap();
+
+        assert_eq!(
+            files,
+            vec![
+                SourceFile {
+                    path: "README.md".to_string(),
+                    content: "https://example.com\n".to_string(),
+                    lossy: false,
+                },
+                SourceFile {
+                    path: "notes.txt".to_string(),
+                    content: "Caf\u{FFFD}: https://example.org\n".to_string(),
+                    lossy: true,
+                },
+            ]
+        );
+    }
+}
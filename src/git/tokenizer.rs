@@ -0,0 +1,348 @@
+use crate::LinkContext;
+
+/// Comment and string literal syntax of a family of source languages.
+pub(crate) struct SourceSyntax {
+    line_comments: &'static [&'static str],
+    block_comment: Option<(&'static str, &'static str)>,
+    quotes: &'static [char],
+    /// Whether `'` only quotes single characters, as in Rust's `'"'`
+    char_literals: bool,
+}
+
+const C_LIKE: SourceSyntax = SourceSyntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+    char_literals: false,
+};
+
+/// Rust uses `'` for lifetimes, so it is only treated as the quote of char literals.
+const RUST: SourceSyntax = SourceSyntax {
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"'],
+    char_literals: true,
+};
+
+const HASH: SourceSyntax = SourceSyntax {
+    line_comments: &["#"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    char_literals: false,
+};
+
+const DASH: SourceSyntax = SourceSyntax {
+    line_comments: &["--"],
+    block_comment: None,
+    quotes: &['"', '\''],
+    char_literals: false,
+};
+
+/// Returns the syntax of a source file based on its extension, or `None` for
+/// files that are not source code (e.g. Markdown, plain text).
+pub(crate) fn source_syntax(file_path: &str) -> Option<&'static SourceSyntax> {
+    let extension = file_path.rsplit_once('.')?.1;
+    match extension {
+        "rs" => Some(&RUST),
+        "c" | "h" | "cc" | "cpp" | "hpp" | "cs" | "java" | "kt" | "kts" | "scala" | "go" | "js"
+        | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "swift" | "dart" | "php" => Some(&C_LIKE),
+        "py" | "rb" | "sh" | "bash" | "zsh" | "pl" | "r" => Some(&HASH),
+        "lua" | "sql" | "hs" => Some(&DASH),
+        _ => None,
+    }
+}
+
+/// A slice of a line that belongs to a single lexical context.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Segment<'a> {
+    pub context: LinkContext,
+    /// The byte offset of `text` within the line
+    pub offset: usize,
+    pub text: &'a str,
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    Code,
+    BlockComment,
+    String(char),
+}
+
+/// Splits a line into code, comment, and string literal segments.
+///
+/// String literal segments exclude the surrounding quotes. Strings are assumed to end at
+/// the end of the line, while block comments carry over to the next line through
+/// `in_block_comment`. Comments only start at the beginning of the line or after
+/// whitespace, so that the `//` of `https://` or the `#` of a URL fragment in code is not
+/// taken for one.
+pub(crate) fn tokenize_line<'a>(
+    line: &'a str,
+    syntax: &SourceSyntax,
+    in_block_comment: &mut bool,
+) -> Vec<Segment<'a>> {
+    let mut segments = Vec::new();
+    let mut state = if *in_block_comment {
+        State::BlockComment
+    } else {
+        State::Code
+    };
+    let mut start = 0;
+    let mut chars = line.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match state {
+            State::Code => {
+                let at_boundary = line[..i]
+                    .chars()
+                    .next_back()
+                    .is_none_or(char::is_whitespace);
+                if at_boundary
+                    && let Some(marker) = syntax
+                        .line_comments
+                        .iter()
+                        .find(|marker| line[i..].starts_with(**marker))
+                {
+                    push_segment(&mut segments, line, LinkContext::Text, start, i);
+                    push_segment(
+                        &mut segments,
+                        line,
+                        LinkContext::Comment,
+                        i + marker.len(),
+                        line.len(),
+                    );
+                    return segments;
+                }
+                if at_boundary
+                    && let Some((open, _)) = syntax.block_comment
+                    && line[i..].starts_with(open)
+                {
+                    push_segment(&mut segments, line, LinkContext::Text, start, i);
+                    chars.nth(open.len() - 2);
+                    start = i + open.len();
+                    state = State::BlockComment;
+                } else if syntax.char_literals
+                    && let Some(len) = char_literal_len(&line[i..])
+                {
+                    // Char literals stay part of the code, as they cannot hold a link.
+                    chars.nth(line[i..i + len].chars().count() - 2);
+                } else if syntax.quotes.contains(&c) {
+                    push_segment(&mut segments, line, LinkContext::Text, start, i);
+                    start = i + 1;
+                    state = State::String(c);
+                }
+            }
+            State::BlockComment => {
+                if let Some((_, close)) = syntax.block_comment
+                    && line[i..].starts_with(close)
+                {
+                    push_segment(&mut segments, line, LinkContext::Comment, start, i);
+                    chars.nth(close.len() - 2);
+                    start = i + close.len();
+                    state = State::Code;
+                }
+            }
+            State::String(quote) => {
+                if c == '\\' {
+                    chars.next();
+                } else if c == quote {
+                    push_segment(&mut segments, line, LinkContext::StringLiteral, start, i);
+                    start = i + 1;
+                    state = State::Code;
+                }
+            }
+        }
+    }
+
+    let context = match state {
+        State::Code => LinkContext::Text,
+        State::BlockComment => LinkContext::Comment,
+        State::String(_) => LinkContext::StringLiteral,
+    };
+    push_segment(&mut segments, line, context, start, line.len());
+    *in_block_comment = matches!(state, State::BlockComment);
+
+    segments
+}
+
+/// Returns the length in bytes of the char literal at the start of `rest`, such as `'"'`
+/// or `'\''`, or `None` if `rest` does not start with one, e.g. because it is a lifetime.
+fn char_literal_len(rest: &str) -> Option<usize> {
+    let body = rest.strip_prefix('\'')?;
+    let first = body.chars().next()?;
+    let after_first = &body[first.len_utf8()..];
+    let close = match first {
+        '\\' => {
+            let escaped = after_first.chars().next()?;
+            // Escapes are at most `\u{10FFFF}` long.
+            let tail = &after_first[escaped.len_utf8()..];
+            escaped.len_utf8() + tail.find('\'').filter(|&end| end <= 8)?
+        }
+        '\'' => return None,
+        _ if after_first.starts_with('\'') => 0,
+        _ => return None,
+    };
+
+    Some(1 + first.len_utf8() + close + 1)
+}
+
+fn push_segment<'a>(
+    segments: &mut Vec<Segment<'a>>,
+    line: &'a str,
+    context: LinkContext,
+    from: usize,
+    to: usize,
+) {
+    if from < to {
+        segments.push(Segment {
+            context,
+            offset: from,
+            text: &line[from..to],
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_syntax() {
+        assert!(source_syntax("src/main.rs").is_some());
+        assert!(source_syntax("scripts/build.py").is_some());
+        assert!(source_syntax("README.md").is_none());
+        assert!(source_syntax("Makefile").is_none());
+    }
+
+    #[test]
+    fn test_tokenize_line_string_and_comment() {
+        let mut in_block_comment = false;
+        let line = r#"let url = "https://example.com/api\n"; // see https://example.com/docs"#;
+        let segments = tokenize_line(line, &RUST, &mut in_block_comment);
+
+        assert_eq!(
+            segments,
+            vec![
+                Segment {
+                    context: LinkContext::Text,
+                    offset: 0,
+                    text: "let url = ",
+                },
+                Segment {
+                    context: LinkContext::StringLiteral,
+                    offset: 11,
+                    text: r"https://example.com/api\n",
+                },
+                Segment {
+                    context: LinkContext::Text,
+                    offset: 37,
+                    text: "; ",
+                },
+                Segment {
+                    context: LinkContext::Comment,
+                    offset: 41,
+                    text: " see https://example.com/docs",
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_line_block_comment_across_lines() {
+        let mut in_block_comment = false;
+        let segments = tokenize_line("int a; /* see", &C_LIKE, &mut in_block_comment);
+        assert!(in_block_comment);
+        assert_eq!(segments.last().unwrap().context, LinkContext::Comment);
+
+        let segments = tokenize_line(
+            "https://example.com */ int b;",
+            &C_LIKE,
+            &mut in_block_comment,
+        );
+        assert!(!in_block_comment);
+        assert_eq!(
+            segments[0],
+            Segment {
+                context: LinkContext::Comment,
+                offset: 0,
+                text: "https://example.com ",
+            }
+        );
+        assert_eq!(segments[1].context, LinkContext::Text);
+    }
+
+    #[test]
+    fn test_tokenize_line_url_fragment_in_hash_code() {
+        let mut in_block_comment = false;
+        let line = "curl https://example.com/docs#install # fetch the guide";
+        let segments = tokenize_line(line, &HASH, &mut in_block_comment);
+
+        assert_eq!(
+            segments,
+            vec![
+                Segment {
+                    context: LinkContext::Text,
+                    offset: 0,
+                    text: "curl https://example.com/docs#install ",
+                },
+                Segment {
+                    context: LinkContext::Comment,
+                    offset: 39,
+                    text: " fetch the guide",
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_line_url_in_c_like_code() {
+        let mut in_block_comment = false;
+        let line = "@see https://example.com/api/*.html // docs";
+        let segments = tokenize_line(line, &C_LIKE, &mut in_block_comment);
+
+        assert!(!in_block_comment);
+        assert_eq!(
+            segments,
+            vec![
+                Segment {
+                    context: LinkContext::Text,
+                    offset: 0,
+                    text: "@see https://example.com/api/*.html ",
+                },
+                Segment {
+                    context: LinkContext::Comment,
+                    offset: 38,
+                    text: " docs",
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_line_rust_char_literals() {
+        let mut in_block_comment = false;
+        let line = r#"if c == '"' || c == '\'' { fn f<'a>() {} } // see https://example.com"#;
+        let segments = tokenize_line(line, &RUST, &mut in_block_comment);
+
+        assert_eq!(
+            segments,
+            vec![
+                Segment {
+                    context: LinkContext::Text,
+                    offset: 0,
+                    text: r#"if c == '"' || c == '\'' { fn f<'a>() {} } "#,
+                },
+                Segment {
+                    context: LinkContext::Comment,
+                    offset: 45,
+                    text: " see https://example.com",
+                },
+            ]
+        );
+
+        let line = r#"let s = "https://example.com"; let q = '\u{22}';"#;
+        let segments = tokenize_line(line, &RUST, &mut in_block_comment);
+        assert_eq!(segments[1].context, LinkContext::StringLiteral);
+        assert_eq!(segments[1].text, "https://example.com");
+        assert_eq!(segments.len(), 3);
+    }
+}
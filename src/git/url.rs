@@ -1,8 +1,32 @@
 use regex::Regex;
+use thiserror::Error;
+use url::Url;
+
+/// The host of github.com repositories
+const GITHUB_HOST: &str = "github.com";
+
+/// Errors that can occur when parsing a GitHub URL
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum GitHubUrlError {
+    #[error("invalid URL: {0}")]
+    Invalid(String),
+    #[error("unsupported URL scheme: {0}")]
+    UnsupportedScheme(String),
+    #[error("not a GitHub host: {0}")]
+    UnknownHost(String),
+    #[error("missing owner or repository name")]
+    MissingRepository,
+    #[error("missing branch name after /{0}/")]
+    MissingBranch(String),
+    #[error("unsupported repository path: /{0}")]
+    UnsupportedPath(String),
+}
 
 /// Represents a parsed GitHub URL with its components
 #[derive(Debug, Clone)]
 pub struct GitHubUrl {
+    /// The host of the GitHub instance (github.com or a GitHub Enterprise host)
+    host: String,
     /// The owner/organization name from the GitHub URL
     owner: String,
     /// The repository name from the GitHub URL
@@ -14,7 +38,7 @@ pub struct GitHubUrl {
 }
 
 impl GitHubUrl {
-    /// Creates a new GitHubUrl struct
+    /// Creates a new GitHubUrl struct for a repository on github.com
     ///
     /// # Arguments
     /// * `owner` - The owner/organization name from the GitHub URL
@@ -29,6 +53,7 @@ impl GitHubUrl {
         file_path: Option<String>,
     ) -> Self {
         Self {
+            host: GITHUB_HOST.to_string(),
             owner,
             repo,
             branch,
@@ -38,12 +63,15 @@ impl GitHubUrl {
 
     /// Parses a GitHub URL string into a GitHubUrl struct
     ///
+    /// Besides github.com, the GitHub Enterprise hosts listed in the comma-separated
+    /// `QUEENSAC_GITHUB_ENTERPRISE_HOSTS` environment variable are accepted.
+    ///
     /// # Arguments
     /// * `url` - A GitHub URL string to parse
     ///
     /// # Returns
-    /// * `Some(GitHubUrl)` if the URL is valid and can be parsed
-    /// * `None` if the URL is invalid or cannot be parsed
+    /// * `Ok(GitHubUrl)` if the URL is valid and can be parsed
+    /// * `Err(GitHubUrlError)` describing why the URL is not a GitHub repository URL
     ///
     /// # Examples
     /// ```
@@ -53,18 +81,283 @@ impl GitHubUrl {
     /// let github_url = GitHubUrl::parse(url).unwrap();
     /// assert_eq!(github_url.owner(), "owner");
     /// assert_eq!(github_url.repo(), "repo");
+    ///
+    /// let github_url = GitHubUrl::parse("git@github.com:owner/repo.git").unwrap();
+    /// assert_eq!(github_url.repo(), "repo");
+    /// ```
+    pub fn parse(url: &str) -> Result<Self, GitHubUrlError> {
+        let enterprise_hosts =
+            std::env::var("QUEENSAC_GITHUB_ENTERPRISE_HOSTS").unwrap_or_default();
+        let enterprise_hosts: Vec<&str> = enterprise_hosts
+            .split(',')
+            .map(str::trim)
+            .filter(|host| !host.is_empty())
+            .collect();
+        Self::parse_with_hosts(url, &enterprise_hosts)
+    }
+
+    /// Parses a GitHub URL string, accepting the given GitHub Enterprise hosts besides github.com
+    ///
+    /// The following forms are supported, with an optional `.git` suffix on the repository
+    /// name; query strings and fragments are ignored:
+    /// * `https://github.com/owner/repo[/tree|blob/branch[/path]]`
+    /// * `ssh://git@github.com/owner/repo`
+    /// * `git@github.com:owner/repo`
+    ///
+    /// # Arguments
+    /// * `url` - A GitHub URL string to parse
+    /// * `enterprise_hosts` - Host names of GitHub Enterprise instances, e.g. `github.example.com`
+    pub fn parse_with_hosts(url: &str, enterprise_hosts: &[&str]) -> Result<Self, GitHubUrlError> {
+        let url = url.trim();
+        let parsed = match scp_like_to_ssh(url) {
+            Some(ssh_url) => Url::parse(&ssh_url),
+            None => Url::parse(url),
+        }
+        .map_err(|e| GitHubUrlError::Invalid(format!("{url} ({e})")))?;
+
+        match parsed.scheme() {
+            "http" | "https" | "ssh" => {}
+            scheme => return Err(GitHubUrlError::UnsupportedScheme(scheme.to_string())),
+        }
+
+        let host = parsed
+            .host_str()
+            .ok_or_else(|| GitHubUrlError::Invalid(url.to_string()))?
+            .to_ascii_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+        let host = if host == GITHUB_HOST {
+            GITHUB_HOST.to_string()
+        } else if enterprise_hosts
+            .iter()
+            .any(|enterprise_host| enterprise_host.eq_ignore_ascii_case(host))
+        {
+            host.to_string()
+        } else {
+            return Err(GitHubUrlError::UnknownHost(host.to_string()));
+        };
+
+        let segments: Vec<&str> = parsed
+            .path_segments()
+            .map(|segments| segments.collect())
+            .unwrap_or_default();
+        let (owner, repo, rest) = match segments.as_slice() {
+            [owner, repo, rest @ ..] => (*owner, repo.strip_suffix(".git").unwrap_or(repo), rest),
+            _ => return Err(GitHubUrlError::MissingRepository),
+        };
+        if owner.is_empty() || repo.is_empty() {
+            return Err(GitHubUrlError::MissingRepository);
+        }
+
+        let (branch, file_path) = match rest {
+            [] | [""] => (None, None),
+            [kind @ ("tree" | "blob"), rest @ ..] => match rest {
+                [] | [""] => return Err(GitHubUrlError::MissingBranch(kind.to_string())),
+                [branch, path @ ..] => {
+                    let file_path = path.join("/");
+                    (
+                        Some(branch.to_string()),
+                        (!file_path.is_empty()).then_some(file_path),
+                    )
+                }
+            },
+            _ => return Err(GitHubUrlError::UnsupportedPath(rest.join("/"))),
+        };
+
+        Ok(Self {
+            host,
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            branch,
+            file_path,
+        })
+    }
+
+    /// Returns the host of the GitHub instance, e.g. `github.com`
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Returns the owner/organization name from the GitHub URL
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    /// Returns the repository name from the GitHub URL
+    pub fn repo(&self) -> &str {
+        &self.repo
+    }
+
+    /// Returns the branch name if specified in the URL
+    pub fn branch(&self) -> Option<&str> {
+        self.branch.as_deref()
+    }
+
+    /// Returns the file path within the repository if specified in the URL
+    pub fn file_path(&self) -> Option<&str> {
+        self.file_path.as_deref()
+    }
+
+    /// Returns the clone URL for the GitHub repository
+    pub fn clone_url(&self) -> String {
+        format!("https://{}/{}/{}", self.host, self.owner, self.repo)
+    }
+}
+
+/// Converts an scp-like SSH address such as `git@github.com:owner/repo.git` into an
+/// `ssh://` URL, or returns `None` if the address is not in that form.
+fn scp_like_to_ssh(url: &str) -> Option<String> {
+    if url.contains("://") {
+        return None;
+    }
+    let (user_host, path) = url.split_once(':')?;
+    if user_host.contains('/') || !user_host.contains('@') {
+        return None;
+    }
+    Some(format!(
+        "ssh://{user_host}/{}",
+        path.trim_start_matches('/')
+    ))
+}
+
+/// Represents a parsed GitLab URL with its components
+#[derive(Debug, Clone)]
+pub struct GitLabUrl {
+    /// The host of the GitLab instance (e.g. gitlab.com)
+    host: String,
+    /// The group path, including subgroups (e.g. group/subgroup)
+    namespace: String,
+    /// The project name
+    project: String,
+    /// The branch name if specified in the URL (e.g. master, main)
+    branch: Option<String>,
+    /// The file path within the repository if specified in the URL
+    file_path: Option<String>,
+}
+
+impl GitLabUrl {
+    /// Parses a GitLab URL string into a GitLabUrl struct
+    ///
+    /// Self-managed instances are recognized when their host contains `gitlab`.
+    ///
+    /// # Arguments
+    /// * `url` - A GitLab URL string to parse
+    ///
+    /// # Returns
+    /// * `Some(GitLabUrl)` if the URL is valid and can be parsed
+    /// * `None` if the URL is invalid or cannot be parsed
+    ///
+    /// # Examples
+    /// ```
+    /// use queensac::GitLabUrl;
+    ///
+    /// let url = "https://gitlab.com/group/subgroup/project/-/blob/main/README.md";
+    /// let gitlab_url = GitLabUrl::parse(url).unwrap();
+    /// assert_eq!(gitlab_url.namespace(), "group/subgroup");
+    /// assert_eq!(gitlab_url.project(), "project");
     /// ```
     pub fn parse(url: &str) -> Option<Self> {
-        let re = Regex::new(r"^https?://(?:www\.)?github\.com/([^/]+)/([^/]+)(?:/(?:tree|blob)/([^/]+)(?:/(.+))?)?$").ok()?;
+        let re = Regex::new(r"^https?://([^/]*gitlab[^/]*)/(.+?)/([^/]+?)(?:\.git)?(?:/-/(?:tree|blob)/([^/]+)(?:/(.+))?)?/?$").ok()?;
 
         re.captures(url).and_then(|caps| {
-            let owner = caps.get(1)?.as_str().to_string();
+            let host = caps.get(1)?.as_str().to_string();
+            let namespace = caps.get(2)?.as_str().to_string();
+            let project = caps.get(3)?.as_str().to_string();
+            let branch = caps.get(4).map(|m| m.as_str().to_string());
+            let file_path = caps.get(5).map(|m| m.as_str().to_string());
+
+            if namespace.split('/').any(|segment| segment == "-") {
+                return None;
+            }
+
+            Some(Self {
+                host,
+                namespace,
+                project,
+                branch,
+                file_path,
+            })
+        })
+    }
+
+    /// Returns the host of the GitLab instance
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Returns the group path, including subgroups
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Returns the project name
+    pub fn project(&self) -> &str {
+        &self.project
+    }
+
+    /// Returns the branch name if specified in the URL
+    pub fn branch(&self) -> Option<&str> {
+        self.branch.as_deref()
+    }
+
+    /// Returns the file path within the repository if specified in the URL
+    pub fn file_path(&self) -> Option<&str> {
+        self.file_path.as_deref()
+    }
+
+    /// Returns the full path of the project, as used by the GitLab API (e.g. group/subgroup/project)
+    pub fn project_path(&self) -> String {
+        format!("{}/{}", self.namespace, self.project)
+    }
+
+    /// Returns the clone URL for the GitLab project
+    pub fn clone_url(&self) -> String {
+        format!("https://{}/{}.git", self.host, self.project_path())
+    }
+}
+
+/// Represents a parsed Bitbucket Cloud URL with its components
+#[derive(Debug, Clone)]
+pub struct BitbucketUrl {
+    /// The workspace the repository belongs to
+    workspace: String,
+    /// The repository slug
+    repo: String,
+    /// The branch name if specified in the URL (e.g. master, main)
+    branch: Option<String>,
+    /// The file path within the repository if specified in the URL
+    file_path: Option<String>,
+}
+
+impl BitbucketUrl {
+    /// Parses a Bitbucket Cloud URL string into a BitbucketUrl struct
+    ///
+    /// # Arguments
+    /// * `url` - A Bitbucket URL string to parse
+    ///
+    /// # Returns
+    /// * `Some(BitbucketUrl)` if the URL is valid and can be parsed
+    /// * `None` if the URL is invalid or cannot be parsed
+    ///
+    /// # Examples
+    /// ```
+    /// use queensac::BitbucketUrl;
+    ///
+    /// let url = "https://bitbucket.org/workspace/repo/src/main/README.md";
+    /// let bitbucket_url = BitbucketUrl::parse(url).unwrap();
+    /// assert_eq!(bitbucket_url.workspace(), "workspace");
+    /// assert_eq!(bitbucket_url.repo(), "repo");
+    /// ```
+    pub fn parse(url: &str) -> Option<Self> {
+        let re = Regex::new(r"^https?://(?:[^@/]+@)?bitbucket\.org/([^/]+)/([^/]+?)(?:\.git)?(?:/src/([^/]+)(?:/(.+?))?)?/?$").ok()?;
+
+        re.captures(url).and_then(|caps| {
+            let workspace = caps.get(1)?.as_str().to_string();
             let repo = caps.get(2)?.as_str().to_string();
             let branch = caps.get(3).map(|m| m.as_str().to_string());
             let file_path = caps.get(4).map(|m| m.as_str().to_string());
 
             Some(Self {
-                owner,
+                workspace,
                 repo,
                 branch,
                 file_path,
@@ -72,12 +365,12 @@ impl GitHubUrl {
         })
     }
 
-    /// Returns the owner/organization name from the GitHub URL
-    pub fn owner(&self) -> &str {
-        &self.owner
+    /// Returns the workspace the repository belongs to
+    pub fn workspace(&self) -> &str {
+        &self.workspace
     }
 
-    /// Returns the repository name from the GitHub URL
+    /// Returns the repository slug
     pub fn repo(&self) -> &str {
         &self.repo
     }
@@ -92,9 +385,9 @@ impl GitHubUrl {
         self.file_path.as_deref()
     }
 
-    /// Returns the clone URL for the GitHub repository
+    /// Returns the clone URL for the Bitbucket repository
     pub fn clone_url(&self) -> String {
-        format!("https://github.com/{}/{}", self.owner, self.repo)
+        format!("https://bitbucket.org/{}/{}.git", self.workspace, self.repo)
     }
 }
 
@@ -117,7 +410,10 @@ mod tests {
     #[test]
     fn test_github_url_parse_invalid() {
         let url = "https://redddy.com/owner/repo";
-        assert!(GitHubUrl::parse(url).is_none());
+        assert_eq!(
+            GitHubUrl::parse(url).unwrap_err(),
+            GitHubUrlError::UnknownHost("redddy.com".to_string())
+        );
     }
 
     #[test]
@@ -144,6 +440,122 @@ mod tests {
     #[test]
     fn test_no_branch() {
         let url = "https://github.com/owner/repo/blob";
-        assert!(GitHubUrl::parse(url).is_none());
+        assert_eq!(
+            GitHubUrl::parse(url).unwrap_err(),
+            GitHubUrlError::MissingBranch("blob".to_string())
+        );
+    }
+
+    #[test]
+    fn test_github_url_parse_git_suffix_and_ssh() {
+        for url in [
+            "https://github.com/owner/repo.git",
+            "https://github.com/owner/repo/",
+            "https://github.com/owner/repo?tab=readme-ov-file#usage",
+            "git@github.com:owner/repo.git",
+            "ssh://git@github.com/owner/repo.git",
+        ] {
+            let github_url = GitHubUrl::parse(url).unwrap();
+            assert_eq!(github_url.owner(), "owner", "{url}");
+            assert_eq!(github_url.repo(), "repo", "{url}");
+            assert_eq!(github_url.branch(), None, "{url}");
+            assert_eq!(github_url.clone_url(), "https://github.com/owner/repo");
+        }
+    }
+
+    #[test]
+    fn test_github_url_parse_enterprise_host() {
+        let url = "https://GitHub.Example.com/owner/repo/blob/main/README.md?plain=1";
+        assert_eq!(
+            GitHubUrl::parse_with_hosts(url, &[]).unwrap_err(),
+            GitHubUrlError::UnknownHost("github.example.com".to_string())
+        );
+
+        let github_url = GitHubUrl::parse_with_hosts(url, &["github.example.com"]).unwrap();
+        assert_eq!(github_url.host(), "github.example.com");
+        assert_eq!(github_url.branch(), Some("main"));
+        assert_eq!(github_url.file_path(), Some("README.md"));
+        assert_eq!(
+            github_url.clone_url(),
+            "https://github.example.com/owner/repo"
+        );
+    }
+
+    #[test]
+    fn test_github_url_parse_errors() {
+        assert_eq!(
+            GitHubUrl::parse("https://github.com/owner").unwrap_err(),
+            GitHubUrlError::MissingRepository
+        );
+        assert_eq!(
+            GitHubUrl::parse("https://github.com/owner/repo/issues/1").unwrap_err(),
+            GitHubUrlError::UnsupportedPath("issues/1".to_string())
+        );
+        assert_eq!(
+            GitHubUrl::parse("ftp://github.com/owner/repo").unwrap_err(),
+            GitHubUrlError::UnsupportedScheme("ftp".to_string())
+        );
+        assert!(matches!(
+            GitHubUrl::parse("owner/repo"),
+            Err(GitHubUrlError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_gitlab_url_parse() {
+        let gitlab_url =
+            GitLabUrl::parse("https://gitlab.com/group/subgroup/project/-/tree/develop/docs")
+                .unwrap();
+
+        assert_eq!(gitlab_url.host(), "gitlab.com");
+        assert_eq!(gitlab_url.namespace(), "group/subgroup");
+        assert_eq!(gitlab_url.project(), "project");
+        assert_eq!(gitlab_url.branch(), Some("develop"));
+        assert_eq!(gitlab_url.file_path(), Some("docs"));
+        assert_eq!(
+            gitlab_url.clone_url(),
+            "https://gitlab.com/group/subgroup/project.git"
+        );
+    }
+
+    #[test]
+    fn test_gitlab_url_parse_self_managed() {
+        let gitlab_url = GitLabUrl::parse("https://gitlab.example.com/team/docs.git").unwrap();
+
+        assert_eq!(gitlab_url.host(), "gitlab.example.com");
+        assert_eq!(gitlab_url.project_path(), "team/docs");
+        assert_eq!(gitlab_url.branch(), None);
+    }
+
+    #[test]
+    fn test_gitlab_url_parse_invalid() {
+        assert!(GitLabUrl::parse("https://github.com/owner/repo").is_none());
+        assert!(GitLabUrl::parse("https://gitlab.com/project").is_none());
+    }
+
+    #[test]
+    fn test_bitbucket_url_parse() {
+        let bitbucket_url =
+            BitbucketUrl::parse("https://bitbucket.org/workspace/repo/src/develop/docs/intro.md")
+                .unwrap();
+
+        assert_eq!(bitbucket_url.workspace(), "workspace");
+        assert_eq!(bitbucket_url.repo(), "repo");
+        assert_eq!(bitbucket_url.branch(), Some("develop"));
+        assert_eq!(bitbucket_url.file_path(), Some("docs/intro.md"));
+        assert_eq!(
+            bitbucket_url.clone_url(),
+            "https://bitbucket.org/workspace/repo.git"
+        );
+    }
+
+    #[test]
+    fn test_bitbucket_url_parse_clone_url() {
+        let bitbucket_url =
+            BitbucketUrl::parse("https://redddy@bitbucket.org/workspace/repo.git").unwrap();
+
+        assert_eq!(bitbucket_url.repo(), "repo");
+        assert_eq!(bitbucket_url.branch(), None);
+        assert!(BitbucketUrl::parse("https://bitbucket.org/workspace").is_none());
     }
 }
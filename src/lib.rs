@@ -1,5 +1,11 @@
+mod config;
+mod error;
 mod git;
 mod link_checker;
+mod locale;
 
+pub use config::*;
+pub use error::*;
 pub use git::*;
 pub use link_checker::*;
+pub use locale::*;
@@ -0,0 +1,209 @@
+use crate::{InvalidLinkInfo, LinkCheckReport, LinkCheckResult};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, io, path::Path};
+
+/// Links that are known to fail and are left out of the results of later runs, so that
+/// only new breakage fails a run.
+///
+/// A baseline is kept in a JSON file, usually `.queensac-baseline.json` in the checked
+/// repository, and applies to every checked repository. Links are acknowledged by URL and
+/// status, so an acknowledged redirect that stops resolving is reported again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    links: BTreeSet<BaselineLink>,
+}
+
+/// A link acknowledged by a baseline.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct BaselineLink {
+    url: String,
+    /// The status the link failed with, e.g. `invalid` or `redirect`
+    status: String,
+}
+
+impl Baseline {
+    /// Creates a baseline acknowledging every link that is not valid in the given results.
+    ///
+    /// # Arguments
+    /// * `reports` - The checked repositories, e.g. `github.com/owner/repo`, and their results
+    pub fn from_reports(reports: &[(String, LinkCheckReport)]) -> Self {
+        let links = reports
+            .iter()
+            .flat_map(|(_, report)| &report.invalid_links)
+            .map(BaselineLink::from)
+            .collect();
+        Self { links }
+    }
+
+    /// Reads a baseline file.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Writes the baseline to a file, with one link per line so that changes to it are
+    /// easy to review.
+    pub fn store(&self, path: &Path) -> io::Result<()> {
+        let mut content = serde_json::to_string_pretty(self)?;
+        content.push('\n');
+        std::fs::write(path, content)
+    }
+
+    /// Returns the number of acknowledged links.
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    /// Returns whether no links are acknowledged.
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+
+    /// Removes the acknowledged links from the results of a check, leaving them out of
+    /// its counts as well.
+    ///
+    /// # Returns
+    /// The number of links that were removed.
+    pub fn apply(&self, report: &mut LinkCheckReport) -> usize {
+        let summary = &mut report.summary;
+        let before = report.invalid_links.len();
+        // The counts have each URL once, however many times it occurs
+        let mut removed = BTreeSet::new();
+        report.invalid_links.retain(|link| {
+            let link_key = BaselineLink::from(link);
+            if !self.links.contains(&link_key) {
+                return true;
+            }
+            if !removed.insert(link_key) {
+                return false;
+            }
+            summary.total = summary.total.saturating_sub(1);
+            let count = match link.result {
+                LinkCheckResult::Valid => return false,
+                LinkCheckResult::Invalid(_) => &mut summary.invalid,
+                LinkCheckResult::Redirect(_) => &mut summary.redirect,
+                LinkCheckResult::GitHubFileMoved(_)
+                | LinkCheckResult::GitHubBranchRenamed(_)
+                | LinkCheckResult::GitHubCaseMismatch(_)
+                | LinkCheckResult::SitemapMatch(_) => &mut summary.moved,
+            };
+            *count = count.saturating_sub(1);
+            false
+        });
+        before - report.invalid_links.len()
+    }
+}
+
+impl From<&InvalidLinkInfo> for BaselineLink {
+    fn from(link: &InvalidLinkInfo) -> Self {
+        Self {
+            url: link.url.clone(),
+            status: link.result.status().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LinkCheckSummaryEvent, LinkContext};
+
+    fn link(url: &str, result: LinkCheckResult) -> InvalidLinkInfo {
+        InvalidLinkInfo {
+            url: url.to_string(),
+            file_path: "README.md".to_string(),
+            line_number: 1,
+            column: 0,
+            char_column: 0,
+            context: LinkContext::Markdown,
+            collect_link: None,
+            result,
+            suggestions: Vec::new(),
+        }
+    }
+
+    fn report(invalid_links: Vec<InvalidLinkInfo>) -> LinkCheckReport {
+        LinkCheckReport {
+            summary: LinkCheckSummaryEvent {
+                total: invalid_links.len() + 1,
+                valid: 1,
+                invalid: invalid_links
+                    .iter()
+                    .filter(|link| matches!(link.result, LinkCheckResult::Invalid(_)))
+                    .count(),
+                redirect: invalid_links
+                    .iter()
+                    .filter(|link| matches!(link.result, LinkCheckResult::Redirect(_)))
+                    .count(),
+                moved: 0,
+                undecodable_files: 0,
+            },
+            invalid_links,
+            valid_links: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_apply_baseline() {
+        let gone = || LinkCheckResult::Invalid("HTTP status code: 404".to_string());
+        let baseline = Baseline::from_reports(&[(
+            "github.com/owner/repo".to_string(),
+            report(vec![
+                link("https://example.com/gone", gone()),
+                link(
+                    "https://example.com/old",
+                    LinkCheckResult::Redirect("https://example.com/new".to_string()),
+                ),
+            ]),
+        )]);
+        assert_eq!(baseline.len(), 2);
+
+        let mut current = report(vec![
+            link("https://example.com/gone", gone()),
+            link("https://example.com/gone", gone()),
+            link("https://example.com/old", gone()),
+            link("https://example.com/new-breakage", gone()),
+        ]);
+        current.summary.total -= 1;
+        current.summary.invalid -= 1;
+        assert_eq!(baseline.apply(&mut current), 2);
+
+        let urls: Vec<&str> = current
+            .invalid_links
+            .iter()
+            .map(|link| link.url.as_str())
+            .collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://example.com/old",
+                "https://example.com/new-breakage"
+            ]
+        );
+        assert_eq!(current.summary.total, 3);
+        assert_eq!(current.summary.invalid, 2);
+    }
+
+    #[test]
+    fn test_store_and_load_baseline() {
+        let path = std::env::temp_dir().join(format!(
+            "queensac_baseline_test_{}.json",
+            std::process::id()
+        ));
+        let baseline = Baseline::from_reports(&[(
+            "github.com/owner/repo".to_string(),
+            report(vec![link(
+                "https://example.com/gone",
+                LinkCheckResult::Invalid("HTTP status code: 404".to_string()),
+            )]),
+        )]);
+        baseline.store(&path).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("\"url\": \"https://example.com/gone\""));
+        assert!(content.contains("\"status\": \"invalid\""));
+        let loaded = Baseline::load(&path).unwrap();
+        assert_eq!(loaded.len(), 1);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,8 +1,173 @@
-use crate::{GitHubUrl, RepoManager};
+use crate::{
+    CloneOptions, GitHubUrl, PathMatch, RepoManager, find_path_in_repo, find_renamed_branch,
+    link_checker::sitemap::SitemapCache,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::error;
 use url::Url;
 
+/// The default time to wait for a response to each request
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+const DEFAULT_CONCURRENCY: usize = 8;
+const DEFAULT_RETRIES: u32 = 2;
+const DEFAULT_USER_AGENT: &str = concat!("queensac/", env!("CARGO_PKG_VERSION"));
+
 pub struct LinkChecker {
     client: reqwest::Client,
+    sitemaps: Option<SitemapCache>,
+    results: Option<Mutex<HashMap<String, LinkCheckResult>>>,
+    concurrency: usize,
+    retries: u32,
+    host_limit: Option<HostLimit>,
+}
+
+/// Builds a [`LinkChecker`] with options other than the defaults.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use queensac::LinkChecker;
+///
+/// let checker = LinkChecker::builder()
+///     .timeout(Duration::from_secs(10))
+///     .user_agent("docs-bot/1.0")
+///     .concurrency(16)
+///     .build()
+///     .expect("failed to build LinkChecker");
+/// ```
+#[derive(Debug, Clone)]
+pub struct LinkCheckerBuilder {
+    timeout: Duration,
+    user_agent: String,
+    concurrency: usize,
+    retries: u32,
+    per_host_limit: Option<usize>,
+    sitemap_lookup: bool,
+    result_cache: bool,
+}
+
+impl Default for LinkCheckerBuilder {
+    fn default() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            concurrency: DEFAULT_CONCURRENCY,
+            retries: DEFAULT_RETRIES,
+            per_host_limit: None,
+            sitemap_lookup: false,
+            result_cache: false,
+        }
+    }
+}
+
+impl LinkCheckerBuilder {
+    /// Sets how long to wait for a response to each request, 5 seconds by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the `User-Agent` header of the requests, `queensac/<version>` by default.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Sets how many links are checked at the same time, 8 by default.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets how many times a request that failed without a response is retried, 2 by default.
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Limits the number of requests in flight to each host, so that checking many links
+    /// to one site does not get the checker rate limited.
+    pub fn per_host_limit(mut self, limit: usize) -> Self {
+        self.per_host_limit = Some(limit);
+        self
+    }
+
+    /// Enables looking up 404 pages in their host's `sitemap.xml`.
+    ///
+    /// When enabled, a 404 on a non-GitHub URL is resolved to the page in the sitemap with the
+    /// same slug, covering documentation sites that were restructured. As the match is only
+    /// by slug, it is reported as a suggestion and never applied as a fix. Each sitemap is
+    /// fetched once and cached for the lifetime of the checker.
+    pub fn sitemap_lookup(mut self, enabled: bool) -> Self {
+        self.sitemap_lookup = enabled;
+        self
+    }
+
+    /// Enables remembering the result of each checked URL.
+    ///
+    /// When enabled, a URL is requested only once for the lifetime of the checker, so that
+    /// links shared by several repositories checked in one run are not requested again.
+    pub fn result_cache(mut self, enabled: bool) -> Self {
+        self.result_cache = enabled;
+        self
+    }
+
+    /// Creates the `LinkChecker`.
+    ///
+    /// # Returns
+    ///
+    /// The checker, or `Err(reqwest::Error)` if building its HTTP client fails.
+    pub fn build(self) -> Result<LinkChecker, reqwest::Error> {
+        let client = reqwest::Client::builder()
+            .timeout(self.timeout)
+            .user_agent(self.user_agent)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+
+        Ok(LinkChecker {
+            client,
+            sitemaps: self.sitemap_lookup.then(SitemapCache::default),
+            results: self.result_cache.then(|| Mutex::new(HashMap::new())),
+            concurrency: self.concurrency,
+            retries: self.retries,
+            host_limit: self.per_host_limit.map(|limit| HostLimit {
+                permits: limit.max(1),
+                hosts: Mutex::new(HashMap::new()),
+            }),
+        })
+    }
+}
+
+/// Limits the number of requests in flight to each host.
+#[derive(Debug)]
+struct HostLimit {
+    permits: usize,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl HostLimit {
+    /// Waits until a request to the host of the URL may be sent.
+    ///
+    /// # Returns
+    /// The permit to hold while the request is sent, or `None` if the URL has no host.
+    async fn acquire(&self, url: &str) -> Option<OwnedSemaphorePermit> {
+        let host = Url::parse(url).ok()?.host_str()?.to_string();
+        let semaphore = self
+            .hosts
+            .lock()
+            .unwrap()
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.permits)))
+            .clone();
+        semaphore.acquire_owned().await.ok()
+    }
 }
 
 impl LinkChecker {
@@ -20,12 +185,38 @@ impl LinkChecker {
     /// let checker = LinkChecker::new().expect("failed to build LinkChecker");
     /// ```
     pub fn new() -> Result<Self, reqwest::Error> {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(5))
-            .redirect(reqwest::redirect::Policy::none())
-            .build()?;
+        Self::builder().build()
+    }
 
-        Ok(LinkChecker { client })
+    /// Returns a builder for a `LinkChecker`, starting from the defaults of [`LinkChecker::new`].
+    pub fn builder() -> LinkCheckerBuilder {
+        LinkCheckerBuilder::default()
+    }
+
+    /// Creates a `LinkChecker` that sends its requests through the given client.
+    #[cfg(test)]
+    pub(crate) fn with_client(client: reqwest::Client) -> Self {
+        LinkChecker {
+            client,
+            sitemaps: None,
+            results: None,
+            concurrency: DEFAULT_CONCURRENCY,
+            retries: DEFAULT_RETRIES,
+            host_limit: None,
+        }
+    }
+
+    /// Returns how many links are checked at the same time.
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Forgets the remembered results, so that every URL is requested again, e.g. before
+    /// a later scan of the same repositories.
+    pub fn clear_result_cache(&self) {
+        if let Some(results) = &self.results {
+            results.lock().unwrap().clear();
+        }
     }
 
     /// Checks a URL and classifies its link status.
@@ -50,6 +241,9 @@ impl LinkChecker {
     ///         LinkCheckResult::Redirect(target) => println!("redirect -> {}", target),
     ///         LinkCheckResult::Invalid(reason) => println!("invalid: {}", reason),
     ///         LinkCheckResult::GitHubFileMoved(new_path) => println!("moved: {}", new_path),
+    ///         LinkCheckResult::GitHubBranchRenamed(new_url) => println!("branch renamed: {}", new_url),
+    ///         LinkCheckResult::GitHubCaseMismatch(new_url) => println!("case mismatch: {}", new_url),
+    ///         LinkCheckResult::SitemapMatch(target) => println!("sitemap -> {}", target),
     ///     }
     /// });
     /// ```
@@ -60,9 +254,33 @@ impl LinkChecker {
     /// - `Valid` if the URL resolves successfully or only performs a trivial redirect,
     /// - `Redirect(String)` with the redirect target for nontrivial redirects,
     /// - `Invalid(String)` with a brief diagnostic message for HTTP errors, request failures, or retry exhaustion,
-    /// - `GitHubFileMoved(String)` when a GitHub 404 is resolved to a new file location discovered in the repository.
+    /// - `GitHubFileMoved(String)` when a GitHub 404 is resolved to a new file location discovered in the repository,
+    /// - `GitHubBranchRenamed(String)` when a GitHub 404 is caused by a renamed branch, with the URL on the new branch,
+    /// - `GitHubCaseMismatch(String)` when a GitHub 404 is caused by the letter case of the path, with the correctly-cased URL,
+    /// - `SitemapMatch(String)` when sitemap lookup is enabled and a 404 is resolved to a page in the host's sitemap.
     pub async fn check_link(&self, url: &str) -> LinkCheckResult {
-        let mut attempts = 3;
+        if let Some(results) = &self.results
+            && let Some(result) = results.lock().unwrap().get(url)
+        {
+            return result.clone();
+        }
+
+        let result = self.request_link(url).await;
+        if let Some(results) = &self.results {
+            results
+                .lock()
+                .unwrap()
+                .insert(url.to_string(), result.clone());
+        }
+        result
+    }
+
+    async fn request_link(&self, url: &str) -> LinkCheckResult {
+        let permit = match &self.host_limit {
+            Some(host_limit) => host_limit.acquire(url).await,
+            None => None,
+        };
+        let mut attempts = self.retries + 1;
         while attempts > 0 {
             match self.client.get(url).send().await {
                 Ok(res) => {
@@ -80,7 +298,23 @@ impl LinkChecker {
                         }
                         return LinkCheckResult::Valid;
                     } else if status.as_u16() == 404 && is_github_url(url) {
-                        return handle_github_404(url);
+                        // Finding where the file went lists branches and clones the
+                        // repository with blocking git operations, so it runs on the
+                        // blocking pool, without holding up other checks of the host
+                        drop(permit);
+                        let url = url.to_string();
+                        return tokio::task::spawn_blocking(move || handle_github_404(&url))
+                            .await
+                            .unwrap_or_else(|e| {
+                                LinkCheckResult::Invalid(format!(
+                                    "Failed to look up the moved file: {e}"
+                                ))
+                            });
+                    } else if status.as_u16() == 404
+                        && let Some(sitemaps) = &self.sitemaps
+                        && let Some(target) = sitemaps.find_match(&self.client, url).await
+                    {
+                        return LinkCheckResult::SitemapMatch(target);
                     } else {
                         return LinkCheckResult::Invalid(format!("HTTP status code: {status}"));
                     }
@@ -116,12 +350,30 @@ impl Default for LinkChecker {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum LinkCheckResult {
     Valid,
     Redirect(String),
     Invalid(String),
     GitHubFileMoved(String),
+    GitHubBranchRenamed(String),
+    GitHubCaseMismatch(String),
+    SitemapMatch(String),
+}
+
+impl LinkCheckResult {
+    /// Returns the name of the outcome, e.g. `file_moved`, as used in logs and reports.
+    pub fn status(&self) -> &'static str {
+        match self {
+            Self::Valid => "valid",
+            Self::Invalid(_) => "invalid",
+            Self::Redirect(_) => "redirect",
+            Self::GitHubFileMoved(_) => "file_moved",
+            Self::GitHubBranchRenamed(_) => "branch_renamed",
+            Self::GitHubCaseMismatch(_) => "case_mismatch",
+            Self::SitemapMatch(_) => "sitemap_match",
+        }
+    }
 }
 
 fn is_github_url(url: &str) -> bool {
@@ -137,6 +389,7 @@ fn is_github_url(url: &str) -> bool {
 /// Attempts to resolve a GitHub 404 by locating the file's current path in the repository.
 ///
 /// Parses the provided GitHub URL, clones or accesses the repository, and searches for the file's current location.
+/// If the branch in the URL does not exist anymore, the file is searched for on the branch it was renamed to.
 ///
 /// # Parameters
 ///
@@ -144,27 +397,134 @@ fn is_github_url(url: &str) -> bool {
 ///
 /// # Returns
 ///
-/// - `LinkCheckResult::GitHubFileMoved(new_path)` if the file was found at a new path inside the repository.
+/// - `LinkCheckResult::GitHubBranchRenamed(new_url)` if the branch was renamed; `new_url` is the original URL
+///   pointing at the renamed branch, and at the new path of the file if it moved too.
+/// - `LinkCheckResult::GitHubFileMoved(new_url)` if the file or directory was found at a new path inside the repository,
+///   or through a symlink; `new_url` is the original URL pointing at the new path.
+/// - `LinkCheckResult::GitHubCaseMismatch(new_url)` if the path only exists with different letter case;
+///   `new_url` is the original URL with the correctly-cased path.
 /// - `LinkCheckResult::Invalid(...)` with a descriptive message if the URL is not a valid GitHub URL, the repository could not be accessed or cloned, the file does not exist in the repository, or an error occurred while searching.
 fn handle_github_404(url: &str) -> LinkCheckResult {
     let parsed = match GitHubUrl::parse(url) {
-        Some(parsed) => parsed,
-        None => {
-            return LinkCheckResult::Invalid(format!("Invalid GitHub URL format: {url}"));
+        Ok(parsed) => parsed,
+        Err(e) => {
+            return LinkCheckResult::Invalid(format!("Invalid GitHub URL format: {url} ({e})"));
         }
     };
 
-    let repo_manager = match RepoManager::from(&parsed) {
-        Ok(repo_manager) => repo_manager,
-        Err(e) => {
-            return LinkCheckResult::Invalid(format!("Error cloning repository: {e}"));
-        }
+    let renamed_branch = match parsed.branch() {
+        Some(branch) => find_renamed_branch(&parsed.clone_url(), branch).unwrap_or_else(|e| {
+            error!(
+                "Failed to list the branches of {}: {}",
+                parsed.clone_url(),
+                e
+            );
+            None
+        }),
+        None => None,
     };
+    let Some(renamed_branch) = renamed_branch else {
+        let branch = parsed.branch().unwrap_or_default();
+        return match find_file_location(&parsed) {
+            Ok(FileLocation::Moved(new_path)) => {
+                LinkCheckResult::GitHubFileMoved(moved_url(url, &parsed, branch, Some(&new_path)))
+            }
+            Ok(FileLocation::CaseMismatch(path)) => {
+                LinkCheckResult::GitHubCaseMismatch(moved_url(url, &parsed, branch, Some(&path)))
+            }
+            Err(result) => result,
+        };
+    };
+
+    let on_renamed_branch = GitHubUrl::new(
+        parsed.owner().to_string(),
+        parsed.repo().to_string(),
+        Some(renamed_branch.clone()),
+        parsed.file_path().map(str::to_string),
+    );
+    let new_path = match parsed.file_path() {
+        Some(_) => match find_file_location(&on_renamed_branch) {
+            Ok(FileLocation::Moved(path) | FileLocation::CaseMismatch(path)) => Some(path),
+            Err(result) => return result,
+        },
+        None => None,
+    };
+    LinkCheckResult::GitHubBranchRenamed(moved_url(
+        url,
+        &parsed,
+        &renamed_branch,
+        new_path.as_deref(),
+    ))
+}
+
+/// Where the file of a GitHub URL that returned a 404 is found.
+enum FileLocation {
+    /// The file was moved, or is reached through a symlink, to the contained path
+    Moved(String),
+    /// The file exists with different letter case at the contained path
+    CaseMismatch(String),
+}
+
+/// Clones the repository of a GitHub URL and finds the current path of its file.
+///
+/// # Returns
+/// The current location, or the `LinkCheckResult::Invalid` to report if it cannot be found.
+fn find_file_location(github_url: &GitHubUrl) -> Result<FileLocation, LinkCheckResult> {
+    let repo_manager = RepoManager::from_with_options(github_url, CloneOptions::full())
+        .map_err(|e| LinkCheckResult::Invalid(format!("Error cloning repository: {e}")))?;
+
+    let file_path = github_url.file_path().unwrap_or_default();
+    match find_path_in_repo(repo_manager.get_repo(), file_path) {
+        Ok(Some(PathMatch::CaseMismatch(path))) => return Ok(FileLocation::CaseMismatch(path)),
+        Ok(Some(PathMatch::Symlink(path))) => return Ok(FileLocation::Moved(path)),
+        Ok(Some(PathMatch::Exact) | None) => {}
+        Err(e) => error!("Failed to look up {}: {}", file_path, e),
+    }
 
-    match repo_manager.find_current_location(&parsed) {
-        Ok(Some(new_path)) => LinkCheckResult::GitHubFileMoved(new_path.to_string()),
-        Ok(None) => LinkCheckResult::Invalid(format!("File not found in repository: {url}")),
-        Err(e) => LinkCheckResult::Invalid(format!("Error finding file location: {e}")),
+    match repo_manager.find_current_location(github_url) {
+        Ok(Some(new_path)) => Ok(FileLocation::Moved(new_path)),
+        Ok(None) => Err(LinkCheckResult::Invalid(format!(
+            "File not found in repository: {}",
+            github_url.file_path().unwrap_or_default()
+        ))),
+        Err(e) => Err(LinkCheckResult::Invalid(format!(
+            "Error finding file location: {e}"
+        ))),
+    }
+}
+
+/// Returns the URL of a GitHub file or directory moved to `new_path` on `new_branch`,
+/// keeping the rest of the original URL (host, `tree`/`blob`, query and fragment) unchanged.
+fn moved_url(
+    url: &str,
+    github_url: &GitHubUrl,
+    new_branch: &str,
+    new_path: Option<&str>,
+) -> String {
+    let branch = github_url.branch().unwrap_or_default();
+    let (old_suffix, new_suffix) = match (github_url.file_path(), new_path) {
+        (Some(old_path), Some(new_path)) => (
+            format!("/{branch}/{old_path}"),
+            format!("/{new_branch}/{new_path}"),
+        ),
+        _ => (format!("/{branch}"), format!("/{new_branch}")),
+    };
+    let start = ["/tree", "/blob"].iter().find_map(|kind| {
+        url.find(&format!("{kind}{old_suffix}"))
+            .map(|i| i + kind.len())
+    });
+    match start {
+        Some(start) => format!(
+            "{}{new_suffix}{}",
+            &url[..start],
+            &url[start + old_suffix.len()..]
+        ),
+        None => format!(
+            "https://{}/{}/{}/blob{new_suffix}",
+            github_url.host(),
+            github_url.owner(),
+            github_url.repo()
+        ),
     }
 }
 
@@ -329,4 +689,69 @@ mod tests {
         assert!(!is_github_url("https://example.com/docs"));
         assert!(!is_github_url("https://notgithub.com"));
     }
+
+    #[test]
+    fn test_moved_url() {
+        let url = "https://github.com/reddevilmidzy/kingsac/tree/main/docs/api#auth";
+        let github_url = GitHubUrl::parse(url).unwrap();
+        assert_eq!(
+            moved_url(url, &github_url, "main", Some("reference/api")),
+            "https://github.com/reddevilmidzy/kingsac/tree/main/reference/api#auth"
+        );
+
+        let url = "https://github.com/master/kingsac/blob/master/README.md?plain=1";
+        let github_url = GitHubUrl::parse(url).unwrap();
+        assert_eq!(
+            moved_url(url, &github_url, "main", Some("README.md")),
+            "https://github.com/master/kingsac/blob/main/README.md?plain=1"
+        );
+
+        let url = "https://github.com/reddevilmidzy/kingsac/tree/master";
+        let github_url = GitHubUrl::parse(url).unwrap();
+        assert_eq!(
+            moved_url(url, &github_url, "main", None),
+            "https://github.com/reddevilmidzy/kingsac/tree/main"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_result_cache_requests_each_url_once() {
+        let github = crate::git::mock_github::MockGitHub::start().await;
+        github.mount_page("/guide", 200).await;
+        let mut link_checker = github.link_checker();
+        link_checker.results = Some(Mutex::new(HashMap::new()));
+
+        let link = github.site_url("/guide");
+        assert_eq!(link_checker.check_link(&link).await, LinkCheckResult::Valid);
+        assert_eq!(link_checker.check_link(&link).await, LinkCheckResult::Valid);
+
+        assert_eq!(github.request_bodies("GET", "^/guide$").await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_builder_sets_user_agent() {
+        use wiremock::matchers::{header, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/page"))
+            .and(header("user-agent", "docs-bot/1.0"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let link_checker = LinkChecker::builder()
+            .user_agent("docs-bot/1.0")
+            .concurrency(2)
+            .build()
+            .unwrap();
+        assert_eq!(link_checker.concurrency(), 2);
+        assert_eq!(
+            link_checker
+                .check_link(&format!("{}/page", server.uri()))
+                .await,
+            LinkCheckResult::Valid
+        );
+    }
 }
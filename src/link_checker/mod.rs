@@ -1,5 +1,21 @@
+mod baseline;
 mod checker;
+mod notifier;
+mod report;
+mod result_store;
+mod scan_cache;
 mod service;
+mod sitemap;
+mod suggestion;
 
-pub use checker::{LinkCheckResult, LinkChecker};
-pub use service::{InvalidLinkInfo, LinkCheckEvent, check_links};
+pub use baseline::Baseline;
+pub use checker::{LinkCheckResult, LinkChecker, LinkCheckerBuilder};
+pub use notifier::{DiscordNotifier, Notifier, NotifyError, SlackNotifier, WebhookNotifier};
+pub use report::{junit_report, markdown_report, sarif_report, text_report};
+pub use result_store::{ResultDelta, ResultStore, RunResults};
+pub use scan_cache::ScanCache;
+pub use service::{
+    InvalidLinkInfo, LINK_CHECK_TARGET, LinkCheckEvent, LinkCheckReport, LinkCheckSummaryEvent,
+    SUMMARY_TARGET, check_link_list, check_links, check_links_in_diff, check_links_incremental,
+};
+pub use suggestion::{LinkSuggester, SearchBackend};
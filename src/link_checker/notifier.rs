@@ -0,0 +1,576 @@
+use crate::{InvalidLinkInfo, LinkCheckReport, LinkCheckSummaryEvent, Locale, Message};
+use ring::hmac;
+use serde_json::{Value, json};
+use std::time::Duration;
+use tracing::warn;
+
+/// The maximum number of links listed in a notification, to stay within the message size
+/// limits of chat services
+const MAX_LISTED_LINKS: usize = 15;
+
+/// Sidebar color of Discord embeds when all links are valid
+const DISCORD_COLOR_VALID: u32 = 0x2EB67D;
+
+/// Sidebar color of Discord embeds when some links are not valid
+const DISCORD_COLOR_INVALID: u32 = 0xE01E5A;
+
+/// The header carrying the HMAC-SHA256 signature of webhook payloads
+const SIGNATURE_HEADER: &str = "X-Queensac-Signature-256";
+
+/// How often a webhook delivery is attempted before giving up
+const WEBHOOK_ATTEMPTS: u32 = 3;
+
+#[derive(Debug, thiserror::Error)]
+pub enum NotifyError {
+    #[error("Failed to send notification: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("Notification was rejected with status {0}: {1}")]
+    Rejected(u16, String),
+    #[error("Failed to serialize notification: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+/// A service the results of a link check are posted to, such as a chat or a webhook.
+pub trait Notifier {
+    /// Posts the results of checking a repository.
+    ///
+    /// # Arguments
+    /// * `repo` - The checked repository, e.g. `github.com/owner/repo`
+    /// * `report` - The results of the check
+    fn notify(
+        &self,
+        repo: &str,
+        report: &LinkCheckReport,
+    ) -> impl Future<Output = Result<(), NotifyError>>;
+}
+
+/// Posts link check results to a Slack channel through an incoming webhook.
+#[derive(Debug, Clone)]
+pub struct SlackNotifier {
+    webhook_url: String,
+    client: reqwest::Client,
+    locale: Locale,
+}
+
+impl SlackNotifier {
+    /// Creates a notifier for a Slack incoming webhook URL.
+    pub fn new(webhook_url: String) -> Self {
+        Self {
+            webhook_url,
+            client: reqwest::Client::new(),
+            locale: Locale::default(),
+        }
+    }
+
+    /// Sets the language of the messages.
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.locale = locale;
+    }
+}
+
+impl Notifier for SlackNotifier {
+    async fn notify(&self, repo: &str, report: &LinkCheckReport) -> Result<(), NotifyError> {
+        post_json(
+            &self.client,
+            &self.webhook_url,
+            &slack_message(repo, report, self.locale),
+        )
+        .await
+    }
+}
+
+/// Posts link check results to a Discord channel through a webhook.
+#[derive(Debug, Clone)]
+pub struct DiscordNotifier {
+    webhook_url: String,
+    client: reqwest::Client,
+    locale: Locale,
+}
+
+impl DiscordNotifier {
+    /// Creates a notifier for a Discord webhook URL.
+    pub fn new(webhook_url: String) -> Self {
+        Self {
+            webhook_url,
+            client: reqwest::Client::new(),
+            locale: Locale::default(),
+        }
+    }
+
+    /// Sets the language of the messages.
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.locale = locale;
+    }
+}
+
+impl Notifier for DiscordNotifier {
+    async fn notify(&self, repo: &str, report: &LinkCheckReport) -> Result<(), NotifyError> {
+        post_json(
+            &self.client,
+            &self.webhook_url,
+            &discord_message(repo, report, self.locale),
+        )
+        .await
+    }
+}
+
+/// Posts link check results as JSON to a user-provided URL, so that they can be processed
+/// by other systems.
+///
+/// If a secret is set, each payload is signed with HMAC-SHA256 and the signature is sent in
+/// the `X-Queensac-Signature-256` header as `sha256=<hex digest>`, like GitHub webhooks.
+/// Failed deliveries are retried with exponential backoff when the receiver is unavailable.
+#[derive(Debug, Clone)]
+pub struct WebhookNotifier {
+    url: String,
+    secret: Option<String>,
+    client: reqwest::Client,
+    /// The delay before the first retry, doubled for each further retry
+    retry_delay: Duration,
+}
+
+impl WebhookNotifier {
+    /// Creates a notifier for a webhook URL.
+    ///
+    /// # Arguments
+    /// * `url` - The URL the results are posted to
+    /// * `secret` - The secret payloads are signed with, if any
+    pub fn new(url: String, secret: Option<String>) -> Self {
+        Self {
+            url,
+            secret,
+            client: reqwest::Client::new(),
+            retry_delay: Duration::from_secs(1),
+        }
+    }
+
+    /// Posts a signed payload, retrying on request errors and server errors.
+    async fn deliver(&self, body: Vec<u8>) -> Result<(), NotifyError> {
+        let signature = self
+            .secret
+            .as_ref()
+            .map(|secret| format!("sha256={}", sign(secret, &body)));
+        let mut delay = self.retry_delay;
+        let mut attempt = 1;
+        loop {
+            let mut request = self
+                .client
+                .post(&self.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone());
+            if let Some(signature) = &signature {
+                request = request.header(SIGNATURE_HEADER, signature);
+            }
+
+            let error = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) => {
+                    let status = response.status();
+                    let text = response.text().await.unwrap_or_default();
+                    let retryable = status.is_server_error() || status.as_u16() == 429;
+                    let error = NotifyError::Rejected(status.as_u16(), text);
+                    if !retryable {
+                        return Err(error);
+                    }
+                    error
+                }
+                Err(e) => NotifyError::Request(e),
+            };
+            if attempt == WEBHOOK_ATTEMPTS {
+                return Err(error);
+            }
+            warn!(
+                "Webhook delivery attempt {} of {} failed, retrying in {:?}: {}",
+                attempt, WEBHOOK_ATTEMPTS, delay, error
+            );
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    async fn notify(&self, repo: &str, report: &LinkCheckReport) -> Result<(), NotifyError> {
+        let body = serde_json::to_vec(&webhook_payload(repo, report))?;
+        self.deliver(body).await
+    }
+}
+
+async fn post_json(client: &reqwest::Client, url: &str, body: &Value) -> Result<(), NotifyError> {
+    let response = client.post(url).json(body).send().await?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        let text = response.text().await.unwrap_or_default();
+        Err(NotifyError::Rejected(status.as_u16(), text))
+    }
+}
+
+/// Renders a report as a Slack message with Block Kit blocks.
+fn slack_message(repo: &str, report: &LinkCheckReport, locale: Locale) -> Value {
+    let summary = &report.summary;
+    let fields: Vec<Value> = summary_fields(summary, locale)
+        .into_iter()
+        .map(|(name, value)| json!({ "type": "mrkdwn", "text": format!("*{name}*\n{value}") }))
+        .collect();
+    let mut blocks = vec![
+        json!({
+            "type": "header",
+            "text": {
+                "type": "plain_text",
+                "text": locale.format(Message::CheckTitle, &[("repo", repo)])
+            }
+        }),
+        json!({ "type": "section", "fields": fields }),
+    ];
+    if !report.invalid_links.is_empty() {
+        let lines = listed_links(&report.invalid_links, locale, |link| {
+            let mut line = locale.format(
+                Message::LinkAt,
+                &[
+                    ("link", &format!("<{}>", escape_slack(&link.url))),
+                    (
+                        "location",
+                        &format!("`{}:{}`", escape_slack(&link.file_path), link.line_number),
+                    ),
+                ],
+            );
+            if let Some(collect_link) = &link.collect_link {
+                line.push_str(&format!(" → <{}>", escape_slack(collect_link)));
+            }
+            line
+        });
+        blocks.push(json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": lines.join("\n") }
+        }));
+    }
+
+    json!({ "text": headline(repo, summary, locale), "blocks": blocks })
+}
+
+/// Renders a report as a Discord message with an embed.
+fn discord_message(repo: &str, report: &LinkCheckReport, locale: Locale) -> Value {
+    let summary = &report.summary;
+    let fields: Vec<Value> = summary_fields(summary, locale)
+        .into_iter()
+        .map(|(name, value)| json!({ "name": name, "value": value.to_string(), "inline": true }))
+        .collect();
+    let (description, color) = if report.invalid_links.is_empty() {
+        (
+            locale.text(Message::AllLinksValid).to_string(),
+            DISCORD_COLOR_VALID,
+        )
+    } else {
+        let lines = listed_links(&report.invalid_links, locale, |link| {
+            let mut line = locale.format(
+                Message::LinkAt,
+                &[
+                    ("link", &link.url),
+                    (
+                        "location",
+                        &format!("`{}:{}`", link.file_path, link.line_number),
+                    ),
+                ],
+            );
+            if let Some(collect_link) = &link.collect_link {
+                line.push_str(&format!(" → {collect_link}"));
+            }
+            line
+        });
+        (lines.join("\n"), DISCORD_COLOR_INVALID)
+    };
+
+    json!({
+        "embeds": [{
+            "title": locale.format(Message::CheckTitle, &[("repo", repo)]),
+            "description": description,
+            "color": color,
+            "fields": fields,
+        }]
+    })
+}
+
+/// Renders a report as the JSON payload of generic webhooks.
+fn webhook_payload(repo: &str, report: &LinkCheckReport) -> Value {
+    let summary = &report.summary;
+    let links: Vec<Value> = report
+        .invalid_links
+        .iter()
+        .map(|link| {
+            json!({
+                "url": link.url,
+                "file_path": link.file_path,
+                "line_number": link.line_number,
+                "column": link.column,
+                "context": link.context.to_string(),
+                "fix": link.collect_link,
+                "suggestions": link.suggestions,
+            })
+        })
+        .collect();
+
+    json!({
+        "repository": repo,
+        "summary": {
+            "total": summary.total,
+            "valid": summary.valid,
+            "invalid": summary.invalid,
+            "redirect": summary.redirect,
+            "moved": summary.moved,
+            "undecodable_files": summary.undecodable_files,
+        },
+        "links": links,
+    })
+}
+
+/// Returns the hex-encoded HMAC-SHA256 of a payload.
+fn sign(secret: &str, payload: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    hmac::sign(&key, payload)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Summarizes a report in one line, for notification previews.
+fn headline(repo: &str, summary: &LinkCheckSummaryEvent, locale: Locale) -> String {
+    let not_valid = summary.total - summary.valid;
+    let total = summary.total.to_string();
+    if not_valid == 0 {
+        locale.format(
+            Message::AllValidHeadline,
+            &[("repo", repo), ("total", &total)],
+        )
+    } else {
+        locale.format(
+            Message::NeedAttentionHeadline,
+            &[
+                ("repo", repo),
+                ("count", &not_valid.to_string()),
+                ("total", &total),
+            ],
+        )
+    }
+}
+
+fn summary_fields(summary: &LinkCheckSummaryEvent, locale: Locale) -> Vec<(&'static str, usize)> {
+    vec![
+        (locale.text(Message::Total), summary.total),
+        (locale.text(Message::Valid), summary.valid),
+        (locale.text(Message::Invalid), summary.invalid),
+        (locale.text(Message::Redirected), summary.redirect),
+        (locale.text(Message::Moved), summary.moved),
+    ]
+}
+
+/// Renders up to `MAX_LISTED_LINKS` links, noting how many more were left out.
+fn listed_links(
+    links: &[InvalidLinkInfo],
+    locale: Locale,
+    render: impl Fn(&InvalidLinkInfo) -> String,
+) -> Vec<String> {
+    let mut lines: Vec<String> = links
+        .iter()
+        .take(MAX_LISTED_LINKS)
+        .map(|link| format!("• {}", render(link)))
+        .collect();
+    if links.len() > MAX_LISTED_LINKS {
+        let count = (links.len() - MAX_LISTED_LINKS).to_string();
+        lines.push(locale.format(Message::MoreLinks, &[("count", &count)]));
+    }
+    lines
+}
+
+/// Escapes the characters Slack reserves for its markup.
+fn escape_slack(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LinkCheckResult, LinkContext};
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn report() -> LinkCheckReport {
+        LinkCheckReport {
+            summary: LinkCheckSummaryEvent {
+                total: 3,
+                valid: 1,
+                invalid: 1,
+                redirect: 0,
+                moved: 1,
+                undecodable_files: 0,
+            },
+            invalid_links: vec![
+                InvalidLinkInfo {
+                    url: "https://example.com/gone".to_string(),
+                    file_path: "README.md".to_string(),
+                    line_number: 3,
+                    column: 0,
+                    char_column: 0,
+                    context: LinkContext::Markdown,
+                    collect_link: None,
+                    result: LinkCheckResult::Invalid("HTTP status code: 404 Not Found".to_string()),
+                    suggestions: Vec::new(),
+                },
+                InvalidLinkInfo {
+                    url: "https://example.com/a?b=1&c=<2>".to_string(),
+                    file_path: "docs/guide.md".to_string(),
+                    line_number: 7,
+                    column: 0,
+                    char_column: 0,
+                    context: LinkContext::Markdown,
+                    collect_link: Some("https://example.com/new".to_string()),
+                    result: LinkCheckResult::Redirect("https://example.com/new".to_string()),
+                    suggestions: Vec::new(),
+                },
+            ],
+            valid_links: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slack_notifier_posts_blocks() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/slack"))
+            .and(body_partial_json(json!({
+                "text": "2 of 3 links in github.com/owner/repo need attention",
+                "blocks": [{
+                    "type": "header",
+                    "text": { "text": "Link check of github.com/owner/repo" }
+                }]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let notifier = SlackNotifier::new(format!("{}/slack", mock_server.uri()));
+        notifier
+            .notify("github.com/owner/repo", &report())
+            .await
+            .unwrap();
+
+        let links = slack_message("github.com/owner/repo", &report(), Locale::En)["blocks"][2]["text"]["text"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            links,
+            "• <https://example.com/gone> in `README.md:3`\n\
+             • <https://example.com/a?b=1&amp;c=&lt;2&gt;> in `docs/guide.md:7` → <https://example.com/new>"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_discord_notifier_reports_rejection() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/discord"))
+            .and(body_partial_json(json!({
+                "embeds": [{
+                    "title": "Link check of github.com/owner/repo",
+                    "color": DISCORD_COLOR_INVALID,
+                }]
+            })))
+            .respond_with(ResponseTemplate::new(400).set_body_string("Invalid Webhook Token"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let notifier = DiscordNotifier::new(format!("{}/discord", mock_server.uri()));
+        let result = notifier.notify("github.com/owner/repo", &report()).await;
+        assert!(matches!(
+            result,
+            Err(NotifyError::Rejected(400, message)) if message == "Invalid Webhook Token"
+        ));
+    }
+
+    #[test]
+    fn test_sign_matches_known_digest() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_webhook_notifier_signs_payload_and_retries() {
+        let mock_server = MockServer::start().await;
+        let body =
+            serde_json::to_vec(&webhook_payload("github.com/owner/repo", &report())).unwrap();
+        let signature = format!("sha256={}", sign("secret", &body));
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(header(SIGNATURE_HEADER, signature.as_str()))
+            .and(body_partial_json(json!({
+                "repository": "github.com/owner/repo",
+                "summary": { "total": 3, "invalid": 1 },
+                "links": [{ "url": "https://example.com/gone", "fix": null }]
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut notifier = WebhookNotifier::new(
+            format!("{}/hook", mock_server.uri()),
+            Some("secret".to_string()),
+        );
+        notifier.retry_delay = Duration::from_millis(10);
+        notifier
+            .notify("github.com/owner/repo", &report())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_webhook_notifier_does_not_retry_client_errors() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(410))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let notifier = WebhookNotifier::new(mock_server.uri(), None);
+        let result = notifier.notify("github.com/owner/repo", &report()).await;
+        assert!(matches!(result, Err(NotifyError::Rejected(410, _))));
+    }
+
+    #[test]
+    fn test_listed_links_are_truncated() {
+        let mut report = report();
+        report.invalid_links = vec![report.invalid_links[0].clone(); MAX_LISTED_LINKS + 2];
+        let message = discord_message("github.com/owner/repo", &report, Locale::En);
+        let description = message["embeds"][0]["description"].as_str().unwrap();
+        assert_eq!(description.lines().count(), MAX_LISTED_LINKS + 1);
+        assert!(description.ends_with("…and 2 more"));
+
+        let message = discord_message("github.com/owner/repo", &report, Locale::Ko);
+        assert_eq!(
+            message["embeds"][0]["title"],
+            "github.com/owner/repo 링크 검사"
+        );
+        let description = message["embeds"][0]["description"].as_str().unwrap();
+        assert!(description.starts_with("• `README.md:3`: https://example.com/gone\n"));
+        assert!(description.ends_with("…외 2개"));
+    }
+}
@@ -0,0 +1,498 @@
+use crate::{InvalidLinkInfo, LinkCheckReport, LinkCheckResult, LinkInfo};
+use serde_json::{Value, json};
+
+/// The rules of SARIF reports, one per kind of link that is not valid, with their
+/// description and level
+const SARIF_RULES: [(&str, &str, &str); 6] = [
+    ("invalid", "The link is not reachable", "error"),
+    ("redirect", "The link redirects to another URL", "warning"),
+    (
+        "file_moved",
+        "The linked file was moved to another path in its repository",
+        "warning",
+    ),
+    (
+        "branch_renamed",
+        "The linked branch was renamed in its repository",
+        "warning",
+    ),
+    (
+        "case_mismatch",
+        "The linked path only exists with different letter case",
+        "warning",
+    ),
+    (
+        "sitemap_match",
+        "The linked page was found at another URL in the site's sitemap",
+        "warning",
+    ),
+];
+
+/// Renders the results of checked repositories as a SARIF 2.1.0 log, which GitHub code
+/// scanning and other static analysis tools can ingest.
+///
+/// Each repository becomes a run of its own, whose results point at the files and lines
+/// of the links that are not valid.
+///
+/// # Arguments
+/// * `reports` - The checked repositories, e.g. `github.com/owner/repo`, and their results
+///
+/// # Returns
+/// The SARIF log as pretty-printed JSON.
+pub fn sarif_report(reports: &[(String, LinkCheckReport)]) -> String {
+    let rules: Vec<Value> = SARIF_RULES
+        .iter()
+        .map(|(id, description, level)| {
+            json!({
+                "id": id,
+                "shortDescription": { "text": description },
+                "defaultConfiguration": { "level": level },
+            })
+        })
+        .collect();
+    let runs: Vec<Value> = reports
+        .iter()
+        .map(|(repo, report)| {
+            json!({
+                "tool": {
+                    "driver": {
+                        "name": "queensac",
+                        "informationUri": "https://github.com/reddevilmidzy/queensac",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules,
+                    }
+                },
+                "automationDetails": { "id": format!("queensac/{repo}/") },
+                "columnKind": "unicodeCodePoints",
+                "results": report.invalid_links.iter().map(sarif_result).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": runs,
+    });
+    format!("{log:#}")
+}
+
+fn sarif_result(link: &InvalidLinkInfo) -> Value {
+    let rule_id = link.result.status();
+    let level = SARIF_RULES
+        .iter()
+        .find(|(id, _, _)| *id == rule_id)
+        .map_or("warning", |(_, _, level)| level);
+    json!({
+        "ruleId": rule_id,
+        "level": level,
+        "message": { "text": link_message(link) },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": { "uri": link.file_path, "uriBaseId": "%SRCROOT%" },
+                "region": {
+                    "startLine": link.line_number,
+                    "startColumn": link.char_column + 1,
+                },
+            }
+        }],
+    })
+}
+
+/// Renders the results of checked repositories as a JUnit XML report, which CI systems
+/// such as Jenkins and GitLab CI display in their test summaries.
+///
+/// Each repository becomes a test suite and each link a test case, which fails if the
+/// link is not valid.
+///
+/// # Arguments
+/// * `reports` - The checked repositories, e.g. `github.com/owner/repo`, and their results
+///
+/// # Returns
+/// The JUnit XML document.
+pub fn junit_report(reports: &[(String, LinkCheckReport)]) -> String {
+    let tests: usize = reports.iter().map(|(_, report)| test_count(report)).sum();
+    let failures: usize = reports
+        .iter()
+        .map(|(_, report)| report.invalid_links.len())
+        .sum();
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <testsuites name=\"queensac\" tests=\"{tests}\" failures=\"{failures}\">\n"
+    );
+
+    for (repo, report) in reports {
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\">\n",
+            escape_xml(repo),
+            test_count(report),
+            report.invalid_links.len()
+        ));
+        for link in &report.valid_links {
+            xml.push_str(&format!("    {} />\n", junit_test_case(link)));
+        }
+        for link in &report.invalid_links {
+            let test_case = junit_test_case(&LinkInfo {
+                url: link.url.clone(),
+                file_path: link.file_path.clone(),
+                line_number: link.line_number,
+                column: link.column,
+                char_column: link.char_column,
+                context: link.context,
+            });
+            xml.push_str(&format!(
+                "    {}>\n      <failure type=\"{}\" message=\"{}\" />\n    </testcase>\n",
+                test_case,
+                link.result.status(),
+                escape_xml(&link_message(link))
+            ));
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Renders the results of checked repositories as plain text, with the counts of each
+/// repository followed by its links that are not valid.
+///
+/// # Arguments
+/// * `reports` - The checked repositories, e.g. `github.com/owner/repo`, and their results
+pub fn text_report(reports: &[(String, LinkCheckReport)]) -> String {
+    let mut text = String::new();
+    for (repo, report) in reports {
+        let summary = &report.summary;
+        text.push_str(&format!(
+            "{}: {} links, {} valid, {} invalid, {} redirected, {} moved\n",
+            repo, summary.total, summary.valid, summary.invalid, summary.redirect, summary.moved
+        ));
+        for link in &report.invalid_links {
+            text.push_str(&format!(
+                "  {}:{} {}: {}\n",
+                link.file_path,
+                link.line_number,
+                link.result.status(),
+                link_message(link)
+            ));
+        }
+    }
+    text
+}
+
+/// Renders the results of checked repositories as a standalone Markdown document, which
+/// can be pasted into an issue or published as a wiki page.
+///
+/// The document starts with a table of the counts of each repository, followed by the links
+/// that are not valid grouped by repository and file, with their suggested replacements.
+///
+/// # Arguments
+/// * `reports` - The checked repositories, e.g. `github.com/owner/repo`, and their results
+///
+/// # Returns
+/// The Markdown document.
+pub fn markdown_report(reports: &[(String, LinkCheckReport)]) -> String {
+    let mut markdown = String::from(
+        "# Link check report\n\n\
+         | Repository | Total | Valid | Invalid | Redirect | Moved |\n\
+         |---|---|---|---|---|---|\n",
+    );
+    for (repo, report) in reports {
+        let summary = &report.summary;
+        markdown.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            escape_markdown_cell(repo),
+            summary.total,
+            summary.valid,
+            summary.invalid,
+            summary.redirect,
+            summary.moved
+        ));
+    }
+
+    for (repo, report) in reports {
+        markdown.push_str(&format!("\n## {repo}\n"));
+        if report.invalid_links.is_empty() {
+            markdown.push_str("\nAll links are valid.\n");
+            continue;
+        }
+
+        let mut files: Vec<&str> = report
+            .invalid_links
+            .iter()
+            .map(|link| link.file_path.as_str())
+            .collect();
+        files.sort_unstable();
+        files.dedup();
+        for file in files {
+            markdown.push_str(&format!(
+                "\n### `{file}`\n\n\
+                 | Line | Link | Status | Suggested replacement |\n\
+                 |---|---|---|---|\n"
+            ));
+            let mut links: Vec<&InvalidLinkInfo> = report
+                .invalid_links
+                .iter()
+                .filter(|link| link.file_path == file)
+                .collect();
+            links.sort_by_key(|link| (link.line_number, link.column));
+            for link in links {
+                let status = match &link.result {
+                    LinkCheckResult::Invalid(reason) => format!("invalid: {reason}"),
+                    result => result.status().to_string(),
+                };
+                let replacement = match &link.collect_link {
+                    Some(replacement) => format!("`{replacement}`"),
+                    None => link
+                        .suggestions
+                        .iter()
+                        .map(|suggestion| format!("`{suggestion}` (unverified)"))
+                        .collect::<Vec<_>>()
+                        .join("<br>"),
+                };
+                markdown.push_str(&format!(
+                    "| {} | `{}` | {} | {} |\n",
+                    link.line_number,
+                    escape_markdown_cell(&link.url),
+                    escape_markdown_cell(&status),
+                    escape_markdown_cell(&replacement)
+                ));
+            }
+        }
+    }
+
+    markdown.push_str(&format!(
+        "\n---\n*Generated by [queensac](https://github.com/reddevilmidzy/queensac) {}*\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+    markdown
+}
+
+fn test_count(report: &LinkCheckReport) -> usize {
+    report.valid_links.len() + report.invalid_links.len()
+}
+
+/// Renders the opening tag of a test case, without its closing `>`.
+fn junit_test_case(link: &LinkInfo) -> String {
+    format!(
+        "<testcase classname=\"{}\" name=\"{}\" file=\"{}\" line=\"{}\"",
+        escape_xml(&link.file_path),
+        escape_xml(&link.url),
+        escape_xml(&link.file_path),
+        link.line_number
+    )
+}
+
+/// Describes why a link is not valid and how it can be fixed.
+fn link_message(link: &InvalidLinkInfo) -> String {
+    match (&link.result, &link.collect_link) {
+        (_, Some(replacement)) => format!("`{}` can be replaced with `{}`", link.url, replacement),
+        (LinkCheckResult::Invalid(reason), None) => {
+            format!("`{}` is not reachable: {}", link.url, reason)
+        }
+        (_, None) => format!("`{}` is not reachable", link.url),
+    }
+}
+
+/// Escapes the characters that would break a Markdown table cell.
+fn escape_markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Escapes the characters XML reserves in attribute values and text.
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LinkCheckSummaryEvent, LinkContext, find_link_in_content};
+
+    fn link(url: &str, result: LinkCheckResult, collect_link: Option<&str>) -> InvalidLinkInfo {
+        InvalidLinkInfo {
+            url: url.to_string(),
+            file_path: "docs/intro.md".to_string(),
+            line_number: 12,
+            column: 4,
+            char_column: 4,
+            context: LinkContext::Markdown,
+            collect_link: collect_link.map(str::to_string),
+            result,
+            suggestions: Vec::new(),
+        }
+    }
+
+    fn report() -> LinkCheckReport {
+        LinkCheckReport {
+            summary: LinkCheckSummaryEvent {
+                total: 3,
+                valid: 1,
+                invalid: 1,
+                redirect: 0,
+                moved: 1,
+                undecodable_files: 0,
+            },
+            invalid_links: vec![
+                link(
+                    "https://example.com/gone",
+                    LinkCheckResult::Invalid("HTTP status code: 404 Not Found".to_string()),
+                    None,
+                ),
+                link(
+                    "https://github.com/owner/repo/blob/main/old.md",
+                    LinkCheckResult::GitHubFileMoved(
+                        "https://github.com/owner/repo/blob/main/new.md".to_string(),
+                    ),
+                    Some("https://github.com/owner/repo/blob/main/new.md"),
+                ),
+            ],
+            valid_links: vec![LinkInfo {
+                url: "https://example.com/?a=1&b=2".to_string(),
+                file_path: "README.md".to_string(),
+                line_number: 1,
+                column: 0,
+                char_column: 0,
+                context: LinkContext::Markdown,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_sarif_report() {
+        let report = report();
+        let sarif: Value = serde_json::from_str(&sarif_report(&[(
+            "github.com/owner/repo".to_string(),
+            report,
+        )]))
+        .unwrap();
+
+        assert_eq!(sarif["version"], "2.1.0");
+        let run = &sarif["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "queensac");
+        assert_eq!(
+            run["tool"]["driver"]["rules"].as_array().unwrap().len(),
+            SARIF_RULES.len()
+        );
+        assert_eq!(
+            run["automationDetails"]["id"],
+            "queensac/github.com/owner/repo/"
+        );
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], "invalid");
+        assert_eq!(results[0]["level"], "error");
+        assert_eq!(
+            results[0]["message"]["text"],
+            "`https://example.com/gone` is not reachable: HTTP status code: 404 Not Found"
+        );
+        let location = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "docs/intro.md");
+        assert_eq!(location["region"]["startLine"], 12);
+        assert_eq!(location["region"]["startColumn"], 5);
+        assert_eq!(results[1]["ruleId"], "file_moved");
+        assert_eq!(results[1]["level"], "warning");
+    }
+
+    #[test]
+    fn test_sarif_columns_count_code_points() {
+        let found =
+            find_link_in_content("설명서: https://example.com/gone", "README.md".to_string())
+                .into_iter()
+                .next()
+                .unwrap();
+        assert_eq!(found.column, 11);
+        assert_eq!(found.char_column, 5);
+
+        let mut report = report();
+        report.invalid_links = vec![InvalidLinkInfo {
+            column: found.column,
+            char_column: found.char_column,
+            ..link(
+                &found.url,
+                LinkCheckResult::Invalid("HTTP status code: 404 Not Found".to_string()),
+                None,
+            )
+        }];
+        let sarif: Value = serde_json::from_str(&sarif_report(&[(
+            "github.com/owner/repo".to_string(),
+            report,
+        )]))
+        .unwrap();
+
+        let run = &sarif["runs"][0];
+        assert_eq!(run["columnKind"], "unicodeCodePoints");
+        let region = &run["results"][0]["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(region["startColumn"], 6);
+    }
+
+    #[test]
+    fn test_junit_report() {
+        let junit = junit_report(&[("github.com/owner/repo".to_string(), report())]);
+
+        assert!(junit.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+        assert!(junit.contains(
+            "<testsuites name=\"queensac\" tests=\"3\" failures=\"2\">\n  \
+             <testsuite name=\"github.com/owner/repo\" tests=\"3\" failures=\"2\" errors=\"0\" skipped=\"0\">\n"
+        ));
+        assert!(junit.contains(
+            "    <testcase classname=\"README.md\" name=\"https://example.com/?a=1&amp;b=2\" file=\"README.md\" line=\"1\" />\n"
+        ));
+        assert!(junit.contains(
+            "    <testcase classname=\"docs/intro.md\" name=\"https://example.com/gone\" file=\"docs/intro.md\" line=\"12\">\n      \
+             <failure type=\"invalid\" message=\"`https://example.com/gone` is not reachable: HTTP status code: 404 Not Found\" />\n    \
+             </testcase>\n"
+        ));
+        assert!(junit.ends_with("  </testsuite>\n</testsuites>\n"));
+    }
+
+    #[test]
+    fn test_text_report() {
+        assert_eq!(
+            text_report(&[("github.com/owner/repo".to_string(), report())]),
+            "github.com/owner/repo: 3 links, 1 valid, 1 invalid, 0 redirected, 1 moved\n  \
+             docs/intro.md:12 invalid: `https://example.com/gone` is not reachable: HTTP status code: 404 Not Found\n  \
+             docs/intro.md:12 file_moved: `https://github.com/owner/repo/blob/main/old.md` can be replaced with `https://github.com/owner/repo/blob/main/new.md`\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_report() {
+        let mut valid_report = report();
+        valid_report.invalid_links.clear();
+        let markdown = markdown_report(&[
+            ("github.com/owner/repo".to_string(), report()),
+            ("github.com/owner/docs".to_string(), valid_report),
+        ]);
+
+        assert!(markdown.starts_with(
+            "# Link check report\n\n\
+             | Repository | Total | Valid | Invalid | Redirect | Moved |\n\
+             |---|---|---|---|---|---|\n\
+             | github.com/owner/repo | 3 | 1 | 1 | 0 | 1 |\n\
+             | github.com/owner/docs | 3 | 1 | 1 | 0 | 1 |\n"
+        ));
+        assert!(markdown.contains(
+            "\n## github.com/owner/repo\n\n\
+             ### `docs/intro.md`\n\n\
+             | Line | Link | Status | Suggested replacement |\n\
+             |---|---|---|---|\n\
+             | 12 | `https://example.com/gone` | invalid: HTTP status code: 404 Not Found |  |\n\
+             | 12 | `https://github.com/owner/repo/blob/main/old.md` | file_moved | `https://github.com/owner/repo/blob/main/new.md` |\n"
+        ));
+        assert!(markdown.contains("\n## github.com/owner/docs\n\nAll links are valid.\n"));
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(
+            escape_xml(r#"<a href="x?y=1&z='2'">"#),
+            "&lt;a href=&quot;x?y=1&amp;z=&apos;2&apos;&quot;&gt;"
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use crate::{InvalidLinkInfo, LinkCheckReport, state_file_path};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, io, path::PathBuf};
+
+/// The results of a run, one report per checked repository.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunResults {
+    /// When the run finished
+    pub checked_at: DateTime<Utc>,
+    /// The checked repositories, e.g. `github.com/owner/repo`, and their results
+    pub reports: Vec<(String, LinkCheckReport)>,
+}
+
+/// How the links of a repository changed between two runs.
+#[derive(Debug, Clone)]
+pub struct ResultDelta {
+    /// The repository, e.g. `github.com/owner/repo`
+    pub repo: String,
+    /// The links that are not valid now but were not reported by the previous run
+    pub broken: Vec<InvalidLinkInfo>,
+    /// The URLs that were not valid in the previous run and are valid or gone now
+    pub fixed: Vec<String>,
+}
+
+impl RunResults {
+    /// Compares the results with those of a previous run.
+    ///
+    /// Links are compared by URL. Repositories that the previous run did not check are
+    /// compared against no results, so all of their links that are not valid are broken.
+    ///
+    /// # Returns
+    /// The changes of each repository of this run, including those without changes.
+    pub fn changes_since(&self, previous: &RunResults) -> Vec<ResultDelta> {
+        self.reports
+            .iter()
+            .map(|(repo, report)| {
+                let previous_urls: HashSet<&str> = previous
+                    .reports
+                    .iter()
+                    .filter(|(previous_repo, _)| previous_repo == repo)
+                    .flat_map(|(_, report)| &report.invalid_links)
+                    .map(|link| link.url.as_str())
+                    .collect();
+                let current_urls: HashSet<&str> = report
+                    .invalid_links
+                    .iter()
+                    .map(|link| link.url.as_str())
+                    .collect();
+                let mut fixed: Vec<String> = previous_urls
+                    .difference(&current_urls)
+                    .map(|url| url.to_string())
+                    .collect();
+                fixed.sort();
+                ResultDelta {
+                    repo: repo.clone(),
+                    broken: report
+                        .invalid_links
+                        .iter()
+                        .filter(|link| !previous_urls.contains(link.url.as_str()))
+                        .cloned()
+                        .collect(),
+                    fixed,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Keeps the results of the last run, so that they can be rendered again without checking
+/// the links again.
+///
+/// The results are persisted in a JSON state file.
+#[derive(Debug, Clone)]
+pub struct ResultStore {
+    state_path: PathBuf,
+}
+
+impl ResultStore {
+    /// Creates a result store kept in the given JSON file.
+    pub fn new(state_path: PathBuf) -> Self {
+        Self { state_path }
+    }
+
+    /// Returns the default state file, `queensac/last_results.json` in the user's state directory.
+    pub fn default_state_path() -> PathBuf {
+        state_file_path("last_results.json")
+    }
+
+    /// Returns the results of the last run, if any.
+    pub fn load(&self) -> io::Result<Option<RunResults>> {
+        match std::fs::read_to_string(&self.state_path) {
+            Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Replaces the results of the last run.
+    pub fn store(&self, results: &RunResults) -> io::Result<()> {
+        if let Some(parent) = self.state_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.state_path, serde_json::to_string(results)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LinkCheckResult, LinkCheckSummaryEvent, LinkContext};
+
+    fn report(invalid_urls: &[&str]) -> LinkCheckReport {
+        LinkCheckReport {
+            summary: LinkCheckSummaryEvent {
+                total: invalid_urls.len(),
+                valid: 0,
+                invalid: invalid_urls.len(),
+                redirect: 0,
+                moved: 0,
+                undecodable_files: 0,
+            },
+            invalid_links: invalid_urls
+                .iter()
+                .map(|url| InvalidLinkInfo {
+                    url: url.to_string(),
+                    file_path: "README.md".to_string(),
+                    line_number: 1,
+                    column: 0,
+                    char_column: 0,
+                    context: LinkContext::Markdown,
+                    collect_link: None,
+                    result: LinkCheckResult::Invalid("HTTP status code: 404".to_string()),
+                    suggestions: Vec::new(),
+                })
+                .collect(),
+            valid_links: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_changes_since() {
+        let previous = RunResults {
+            checked_at: Utc::now(),
+            reports: vec![(
+                "github.com/owner/repo".to_string(),
+                report(&["https://example.com/a", "https://example.com/b"]),
+            )],
+        };
+        let current = RunResults {
+            checked_at: Utc::now(),
+            reports: vec![
+                (
+                    "github.com/owner/repo".to_string(),
+                    report(&["https://example.com/b", "https://example.com/c"]),
+                ),
+                (
+                    "github.com/owner/docs".to_string(),
+                    report(&["https://example.com/d"]),
+                ),
+            ],
+        };
+
+        let changes = current.changes_since(&previous);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].repo, "github.com/owner/repo");
+        assert_eq!(changes[0].broken.len(), 1);
+        assert_eq!(changes[0].broken[0].url, "https://example.com/c");
+        assert_eq!(changes[0].fixed, vec!["https://example.com/a"]);
+        assert_eq!(changes[1].broken.len(), 1);
+        assert!(changes[1].fixed.is_empty());
+    }
+
+    #[test]
+    fn test_store_and_load_results() {
+        let state_path = std::env::temp_dir().join(format!(
+            "queensac_results_test_{}.json",
+            Utc::now().timestamp_nanos_opt().unwrap()
+        ));
+        let store = ResultStore::new(state_path.clone());
+        assert!(store.load().unwrap().is_none());
+
+        let results = RunResults {
+            checked_at: Utc::now(),
+            reports: vec![(
+                "github.com/owner/repo".to_string(),
+                LinkCheckReport {
+                    summary: LinkCheckSummaryEvent {
+                        total: 1,
+                        valid: 1,
+                        invalid: 0,
+                        redirect: 0,
+                        moved: 0,
+                        undecodable_files: 0,
+                    },
+                    invalid_links: Vec::new(),
+                    valid_links: Vec::new(),
+                },
+            )],
+        };
+        store.store(&results).unwrap();
+
+        let loaded = store.load().unwrap().unwrap();
+        assert_eq!(loaded.checked_at, results.checked_at);
+        assert_eq!(loaded.reports.len(), 1);
+        assert_eq!(loaded.reports[0].0, "github.com/owner/repo");
+        assert_eq!(loaded.reports[0].1.summary.valid, 1);
+
+        std::fs::remove_file(state_path).unwrap();
+    }
+}
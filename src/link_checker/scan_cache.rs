@@ -0,0 +1,72 @@
+use crate::{LinkCheckResult, LinkInfo, state_file_path};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, io, path::PathBuf};
+
+/// A link found in a file, together with the outcome of checking it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct CheckedLink {
+    pub(crate) link: LinkInfo,
+    pub(crate) result: LinkCheckResult,
+    pub(crate) suggestions: Vec<String>,
+}
+
+/// The results of the last scan of a repository branch.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct ScanState {
+    /// The commit that was scanned
+    pub(crate) commit: String,
+    /// The checked links of each scanned file, by repository-relative path
+    pub(crate) files: HashMap<String, Vec<CheckedLink>>,
+}
+
+/// Remembers the last scanned commit and its link check results for each repository
+/// branch, so that the next scan only has to check the files changed since.
+///
+/// The results are persisted in a JSON state file.
+#[derive(Debug, Clone)]
+pub struct ScanCache {
+    state_path: PathBuf,
+}
+
+impl ScanCache {
+    /// Creates a scan cache stored in the given JSON file.
+    pub fn new(state_path: PathBuf) -> Self {
+        Self { state_path }
+    }
+
+    /// Returns the default state file, `queensac/scan_cache.json` in the user's state directory.
+    pub fn default_state_path() -> PathBuf {
+        state_file_path("scan_cache.json")
+    }
+
+    /// Returns the last scan of a repository branch, if any.
+    ///
+    /// # Arguments
+    /// * `key` - Identifies the repository branch, e.g. `github.com/owner/repo@main`
+    pub(crate) fn load(&self, key: &str) -> io::Result<Option<ScanState>> {
+        Ok(self.read_states()?.remove(key))
+    }
+
+    /// Replaces the last scan of a repository branch.
+    ///
+    /// # Arguments
+    /// * `key` - Identifies the repository branch, e.g. `github.com/owner/repo@main`
+    /// * `state` - The results of the scan
+    pub(crate) fn store(&self, key: &str, state: ScanState) -> io::Result<()> {
+        let mut states = self.read_states()?;
+        states.insert(key.to_string(), state);
+
+        if let Some(parent) = self.state_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.state_path, serde_json::to_string(&states)?)
+    }
+
+    fn read_states(&self) -> io::Result<HashMap<String, ScanState>> {
+        match std::fs::read_to_string(&self.state_path) {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(HashMap::new()),
+            Err(e) => Err(e),
+        }
+    }
+}
@@ -1,6 +1,22 @@
+use futures::{StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use tracing::{error, info, instrument};
 
-use crate::{LinkCheckResult, LinkChecker, RepoManager, git};
+use crate::{
+    ExtractOptions, LinkCheckResult, LinkChecker, LinkContext, LinkInfo, LinkSuggester,
+    QueensacError, RepoManager, RepoSource, ScanCache, SourceFile, find_links_in_file,
+    find_links_in_files,
+    link_checker::scan_cache::{CheckedLink, ScanState},
+    read_head_files, read_tree_files,
+};
+
+/// The target of the events logged for each checked link and for the number of links a
+/// check is about to check, so that subscribers can filter them or follow the progress
+pub const LINK_CHECK_TARGET: &str = "queensac::link_check";
+
+/// The target of the summary event logged when a check is done
+pub const SUMMARY_TARGET: &str = "queensac::summary";
 
 #[derive(Debug)]
 pub struct LinkCheckEvent {
@@ -11,21 +27,44 @@ pub struct LinkCheckEvent {
     pub message: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LinkCheckSummaryEvent {
     pub total: usize,
     pub valid: usize,
     pub invalid: usize,
     pub redirect: usize,
     pub moved: usize,
+    /// The number of scanned files that are not valid UTF-8 and were decoded lossily
+    pub undecodable_files: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InvalidLinkInfo {
     pub url: String,
     pub file_path: String,
     pub line_number: usize,
+    /// The 0-based byte offset of the URL within its line
+    pub column: usize,
+    /// The 0-based offset of the URL within its line in Unicode code points
+    #[serde(default)]
+    pub char_column: usize,
+    pub context: LinkContext,
     pub collect_link: Option<String>,
+    /// The outcome of checking the link
+    pub result: LinkCheckResult,
+    /// Low-confidence replacement candidates for fully dead links, for reporting only
+    pub suggestions: Vec<String>,
+}
+
+/// The outcome of checking all links in a repository.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkCheckReport {
+    /// Counts of checked links by status
+    pub summary: LinkCheckSummaryEvent,
+    /// Every link that is not valid (invalid, redirected, or moved)
+    pub invalid_links: Vec<InvalidLinkInfo>,
+    /// Every link that is valid
+    pub valid_links: Vec<LinkInfo>,
 }
 
 #[derive(Debug)]
@@ -68,13 +107,14 @@ impl LinkCheckCounters {
         self.moved += 1;
     }
 
-    fn to_summary(&self) -> LinkCheckSummaryEvent {
+    fn to_summary(&self, undecodable_files: usize) -> LinkCheckSummaryEvent {
         LinkCheckSummaryEvent {
             total: self.total,
             valid: self.valid,
             invalid: self.invalid,
             redirect: self.redirect,
             moved: self.moved,
+            undecodable_files,
         }
     }
 }
@@ -83,11 +123,14 @@ impl LinkCheckCounters {
 ///
 /// # Parameters
 ///
-/// - `repo_manager`: A reference to the RepoManager instance containing the cloned repository to scan for links.
+/// - `source`: The repository to scan for links, such as a cloned `RepoManager` or a `GitHubTreeSource`.
+/// - `extract_options`: Options controlling which links are extracted from the repository.
+/// - `link_checker`: The checker used to validate each link.
+/// - `suggester`: If provided, used to look up replacement candidates for links that are fully dead.
 ///
 /// # Returns
 ///
-/// `Result<Vec<InvalidLinkInfo>, String>` containing information about each link that is not valid (invalid, redirected, or moved). The vector is empty if all links are valid. Returns an error if there was a problem processing the repository.
+/// `Result<LinkCheckReport, QueensacError>` containing the summary counts and information about each link that is not valid (invalid, redirected, or moved). `invalid_links` is empty if all links are valid. Returns an error if there was a problem processing the repository.
 ///
 /// # Examples
 ///
@@ -96,100 +139,430 @@ impl LinkCheckCounters {
 /// async fn example_check_links() {
 ///     let github_url = GitHubUrl::new("reddevilmidzy".to_string(), "kingsac".to_string(), Some("main".to_string()), None);
 ///     let repo_manager = RepoManager::from(&github_url).unwrap();
-///     let invalid = check_links(&repo_manager).await.unwrap();
-///     // `invalid` contains any links that failed validation
-///     println!("Found {} invalid links", invalid.len());
+///     let report = check_links(&repo_manager, &ExtractOptions::default(), &LinkChecker::default(), None)
+///         .await
+///         .unwrap();
+///     // `report.invalid_links` contains any links that failed validation
+///     println!("Found {} invalid links", report.invalid_links.len());
 /// }
 /// ```
 #[instrument(level = "info", skip_all)]
-pub async fn check_links(repo_manager: &RepoManager) -> Result<Vec<InvalidLinkInfo>, String> {
-    let result = git::extract_links_from_repo(repo_manager);
-    let links = match result {
-        Ok(links) => {
-            info!("Found {} links to check", links.len());
-            links
+pub async fn check_links(
+    source: &impl RepoSource,
+    extract_options: &ExtractOptions,
+    link_checker: &LinkChecker,
+    suggester: Option<&LinkSuggester>,
+) -> Result<LinkCheckReport, QueensacError> {
+    let files = source.read_files().await.map_err(|e| {
+        error!("Error processing repository: {}", e);
+        QueensacError::from(e)
+    })?;
+    let undecodable_files = count_undecodable_files(&files);
+    let links: Vec<LinkInfo> = find_links_in_files(files, extract_options)
+        .into_iter()
+        .collect();
+    let total = count_urls(&links);
+    info!(
+        target: LINK_CHECK_TARGET,
+        total,
+        "Found {} links to check",
+        total
+    );
+
+    let checked_links = check_all(links, link_checker, suggester).await;
+
+    Ok(create_report(checked_links.iter(), undecodable_files))
+}
+
+/// Checks the links of a cloned repository, reusing the results of the last scan for the
+/// files that have not changed since.
+///
+/// The last scanned commit and its results are kept in `scan_cache`. Links are only
+/// extracted from and checked in the files added or modified since that commit. If there
+/// is no previous scan, or the repository cannot be compared against it, all files are
+/// scanned.
+///
+/// # Parameters
+///
+/// - `repo_manager`: The cloned repository to scan for links.
+/// - `extract_options`: Options controlling which links are extracted from the repository.
+/// - `link_checker`: The checker used to validate each link.
+/// - `suggester`: If provided, used to look up replacement candidates for links that are fully dead.
+/// - `scan_cache`: Where the results of the last scan are kept.
+/// - `cache_key`: Identifies the repository branch in the scan cache, e.g. `github.com/owner/repo@main`.
+#[instrument(level = "info", skip_all)]
+pub async fn check_links_incremental(
+    repo_manager: &RepoManager,
+    extract_options: &ExtractOptions,
+    link_checker: &LinkChecker,
+    suggester: Option<&LinkSuggester>,
+    scan_cache: &ScanCache,
+    cache_key: &str,
+) -> Result<LinkCheckReport, QueensacError> {
+    let head = repo_manager.head_commit_id()?;
+    let previous = scan_cache.load(cache_key).unwrap_or_else(|e| {
+        error!("Failed to read scan cache: {}", e);
+        None
+    });
+    let changed_paths: Option<HashSet<String>> =
+        previous.as_ref().and_then(
+            |state| match repo_manager.changed_paths_since(&state.commit) {
+                Ok(paths) => Some(paths.into_iter().collect()),
+                Err(e) => {
+                    info!(
+                        "Cannot compare with last scanned commit {}: {}. Scanning all files",
+                        state.commit, e
+                    );
+                    None
+                }
+            },
+        );
+    let mut previous_files = previous.map(|state| state.files).unwrap_or_default();
+
+    let files = read_head_files(repo_manager.get_repo()).map_err(|e| {
+        error!("Error processing repository: {}", e);
+        QueensacError::from(e)
+    })?;
+    let undecodable_files = count_undecodable_files(&files);
+    let mut scanned_files = HashMap::new();
+    let mut changed_files = Vec::new();
+    for file in files {
+        match (&changed_paths, previous_files.remove(&file.path)) {
+            (Some(changed_paths), Some(checked_links)) if !changed_paths.contains(&file.path) => {
+                scanned_files.insert(file.path, checked_links);
+            }
+            _ => changed_files.push(file),
         }
-        Err(e) => {
-            error!("Error processing repository: {}", e);
-            return Err(e.to_string());
+    }
+    info!(
+        "Scanning {} changed files, reusing results for {} files",
+        changed_files.len(),
+        scanned_files.len()
+    );
+
+    let changed_links: Vec<(String, Vec<LinkInfo>)> = changed_files
+        .into_iter()
+        .map(|file| {
+            let links = find_links_in_file(&file, extract_options);
+            (file.path, links)
+        })
+        .collect();
+    let mut links = Vec::new();
+    for (path, file_links) in changed_links {
+        scanned_files.insert(path, Vec::new());
+        links.extend(file_links);
+    }
+    let total = count_urls(&links);
+    info!(
+        target: LINK_CHECK_TARGET,
+        total,
+        "Found {} links to check in changed files",
+        total
+    );
+
+    for checked in check_all(links, link_checker, suggester).await {
+        if let Some(checked_links) = scanned_files.get_mut(&checked.link.file_path) {
+            checked_links.push(checked);
         }
+    }
+
+    let report = create_report(scanned_files.values().flatten(), undecodable_files);
+    let state = ScanState {
+        commit: head,
+        files: scanned_files,
     };
+    if let Err(e) = scan_cache.store(cache_key, state) {
+        error!("Failed to write scan cache: {}", e);
+    }
+    Ok(report)
+}
 
-    let link_checker = LinkChecker::default();
-    let mut counters = LinkCheckCounters::new();
-    let mut invalid_links = Vec::new();
+/// Checks the links on the lines added or modified between two revisions of a repository,
+/// such as the links a pull request introduces.
+///
+/// Links are extracted from the files of `head`, and only those on changed lines are
+/// checked, so that existing broken links are not reported.
+///
+/// # Parameters
+///
+/// - `repo_manager`: The repository to scan for links.
+/// - `base`: The revision the changes were made on, e.g. `main`.
+/// - `head`: The revision with the changes, e.g. `HEAD`.
+/// - `extract_options`: Options controlling which links are extracted from the repository.
+/// - `link_checker`: The checker used to validate each link.
+/// - `suggester`: If provided, used to look up replacement candidates for links that are fully dead.
+#[instrument(level = "info", skip_all)]
+pub async fn check_links_in_diff(
+    repo_manager: &RepoManager,
+    base: &str,
+    head: &str,
+    extract_options: &ExtractOptions,
+    link_checker: &LinkChecker,
+    suggester: Option<&LinkSuggester>,
+) -> Result<LinkCheckReport, QueensacError> {
+    let changed_lines = repo_manager.changed_lines(base, head).map_err(|e| {
+        error!("Error comparing {}..{}: {}", base, head, e);
+        QueensacError::from(e)
+    })?;
+    let files = repo_manager
+        .resolve_commit(head)
+        .and_then(|commit| read_tree_files(repo_manager.get_repo(), &commit.tree()?))
+        .map_err(|e| {
+            error!("Error processing repository: {}", e);
+            QueensacError::from(e)
+        })?;
+    let files: Vec<SourceFile> = files
+        .into_iter()
+        .filter(|file| changed_lines.contains_key(&file.path))
+        .collect();
+    let undecodable_files = count_undecodable_files(&files);
 
-    for link in links {
-        let result = link_checker.check_link(&link.url).await;
+    let mut links = Vec::new();
+    for file in &files {
+        let lines = &changed_lines[&file.path];
+        links.extend(
+            find_links_in_file(file, extract_options)
+                .into_iter()
+                .filter(|link| lines.contains(&link.line_number)),
+        );
+    }
+    let total = count_urls(&links);
+    info!(
+        target: LINK_CHECK_TARGET,
+        total,
+        "Found {} links to check on lines changed in {} files",
+        total,
+        files.len()
+    );
 
-        counters.increment_total();
+    let checked_links = check_all(links, link_checker, suggester).await;
 
-        match &result {
-            LinkCheckResult::Valid => counters.increment_valid(),
-            LinkCheckResult::Invalid(_) => counters.increment_invalid(),
-            LinkCheckResult::Redirect(_) => counters.increment_redirect(),
-            LinkCheckResult::GitHubFileMoved(_) => counters.increment_moved(),
-        };
+    Ok(create_report(checked_links.iter(), undecodable_files))
+}
 
-        let status = match &result {
-            LinkCheckResult::Valid => "valid",
-            LinkCheckResult::Invalid(_) => "invalid",
-            LinkCheckResult::Redirect(_) => "redirect",
-            LinkCheckResult::GitHubFileMoved(_) => "file_moved",
-        };
+/// Checks links that do not come from a repository, such as URLs given on the command line.
+///
+/// Each URL is checked once, even if it is listed several times.
+///
+/// # Parameters
+///
+/// - `links`: The links to check, located in whatever they were read from, e.g. stdin.
+/// - `link_checker`: The checker used to validate each link.
+/// - `suggester`: If provided, used to look up replacement candidates for links that are fully dead.
+#[instrument(level = "info", skip_all)]
+pub async fn check_link_list(
+    links: Vec<LinkInfo>,
+    link_checker: &LinkChecker,
+    suggester: Option<&LinkSuggester>,
+) -> LinkCheckReport {
+    let total = count_urls(&links);
+    info!(
+        target: LINK_CHECK_TARGET,
+        total,
+        "Found {} links to check",
+        total
+    );
 
-        let message: Option<String> = match &result {
-            LinkCheckResult::Valid => None,
-            LinkCheckResult::Invalid(msg) => Some(msg.clone()),
-            LinkCheckResult::Redirect(url) => Some(format!("Redirected to: {url}")),
-            LinkCheckResult::GitHubFileMoved(msg) => Some(format!("Moved to: {msg}")),
-        };
+    let checked_links = check_all(links, link_checker, suggester).await;
+
+    create_report(checked_links.iter(), 0)
+}
+
+/// Checks each URL once, with up to the checker's concurrency in flight, and returns the
+/// result for every link, keeping their order.
+async fn check_all(
+    links: Vec<LinkInfo>,
+    link_checker: &LinkChecker,
+    suggester: Option<&LinkSuggester>,
+) -> Vec<CheckedLink> {
+    let mut seen_urls = HashSet::new();
+    let unique_links: Vec<LinkInfo> = links
+        .iter()
+        .filter(|link| seen_urls.insert(link.url.as_str()))
+        .cloned()
+        .collect();
+    let results: HashMap<String, CheckedLink> = stream::iter(unique_links)
+        .map(|link| check_link(link, link_checker, suggester))
+        .buffered(link_checker.concurrency())
+        .map(|checked| (checked.link.url.clone(), checked))
+        .collect()
+        .await;
 
-        let message_str = message.as_deref().unwrap_or("");
+    links
+        .into_iter()
+        .map(|link| {
+            let checked = &results[&link.url];
+            CheckedLink {
+                link,
+                result: checked.result.clone(),
+                suggestions: checked.suggestions.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Counts the distinct URLs of the given links, which is the number of links checked.
+fn count_urls(links: &[LinkInfo]) -> usize {
+    links
+        .iter()
+        .map(|link| link.url.as_str())
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// Checks a link and looks up replacement candidates if it is fully dead.
+async fn check_link(
+    link: LinkInfo,
+    link_checker: &LinkChecker,
+    suggester: Option<&LinkSuggester>,
+) -> CheckedLink {
+    let result = link_checker.check_link(&link.url).await;
+
+    let status = result.status();
+
+    let message: Option<String> = match &result {
+        LinkCheckResult::Valid => None,
+        LinkCheckResult::Invalid(msg) => Some(msg.clone()),
+        LinkCheckResult::Redirect(url) => Some(format!("Redirected to: {url}")),
+        LinkCheckResult::GitHubFileMoved(msg) => Some(format!("Moved to: {msg}")),
+        LinkCheckResult::GitHubBranchRenamed(url) => Some(format!("Branch renamed: {url}")),
+        LinkCheckResult::GitHubCaseMismatch(url) => Some(format!("Case mismatch: {url}")),
+        LinkCheckResult::SitemapMatch(url) => Some(format!("Found in sitemap: {url}")),
+    };
+
+    let message_str = message.as_deref().unwrap_or("");
+    info!(
+        target: LINK_CHECK_TARGET,
+        url = %link.url,
+        file_path = %link.file_path,
+        line_number = link.line_number as u32,
+        context = %link.context,
+        status = %status,
+        message = %message_str,
+        "link check"
+    );
+
+    let suggestions = match (&result, suggester) {
+        (LinkCheckResult::Invalid(_), Some(suggester)) => suggester.suggest(&link.url).await,
+        _ => Vec::new(),
+    };
+    if !suggestions.is_empty() {
         info!(
             url = %link.url,
-            file_path = %link.file_path,
-            line_number = link.line_number as u32,
-            status = %status,
-            message = %message_str,
-            "link check"
+            suggestions = %suggestions.join(", "),
+            "link suggestions"
         );
+    }
 
-        let collect_link = match &result {
+    CheckedLink {
+        link,
+        result,
+        suggestions,
+    }
+}
+
+/// Counts the files that were decoded lossily, logging each of them.
+fn count_undecodable_files(files: &[SourceFile]) -> usize {
+    let undecodable: Vec<&str> = files
+        .iter()
+        .filter(|file| file.lossy)
+        .map(|file| file.path.as_str())
+        .collect();
+    for path in &undecodable {
+        info!("{} is not valid UTF-8, decoding it lossily", path);
+    }
+    undecodable.len()
+}
+
+/// Summarizes checked links into a report.
+///
+/// Each URL is counted once, while every occurrence of a link that is not valid is
+/// listed, so that all copies of it can be fixed.
+fn create_report<'a>(
+    checked_links: impl Iterator<Item = &'a CheckedLink>,
+    undecodable_files: usize,
+) -> LinkCheckReport {
+    let mut counters = LinkCheckCounters::new();
+    let mut invalid_links = Vec::new();
+    let mut valid_links = Vec::new();
+    let mut seen_urls = HashSet::new();
+
+    for checked in checked_links {
+        if seen_urls.insert(checked.link.url.as_str()) {
+            counters.increment_total();
+
+            match &checked.result {
+                LinkCheckResult::Valid => counters.increment_valid(),
+                LinkCheckResult::Invalid(_) => counters.increment_invalid(),
+                LinkCheckResult::Redirect(_) => counters.increment_redirect(),
+                LinkCheckResult::GitHubFileMoved(_)
+                | LinkCheckResult::GitHubBranchRenamed(_)
+                | LinkCheckResult::GitHubCaseMismatch(_)
+                | LinkCheckResult::SitemapMatch(_) => counters.increment_moved(),
+            };
+        }
+
+        let collect_link = match &checked.result {
             LinkCheckResult::Valid => None,
             LinkCheckResult::Invalid(_) => None,
             LinkCheckResult::Redirect(url) => Some(url.clone()),
             LinkCheckResult::GitHubFileMoved(url) => Some(url.clone()),
+            LinkCheckResult::GitHubBranchRenamed(url) => Some(url.clone()),
+            LinkCheckResult::GitHubCaseMismatch(url) => Some(url.clone()),
+            // A sitemap match is only found by the slug, so it is suggested but never applied
+            LinkCheckResult::SitemapMatch(_) => None,
         };
 
-        if !matches!(result, LinkCheckResult::Valid) {
+        let mut suggestions = checked.suggestions.clone();
+        if let LinkCheckResult::SitemapMatch(url) = &checked.result
+            && !suggestions.contains(url)
+        {
+            suggestions.insert(0, url.clone());
+        }
+
+        if matches!(checked.result, LinkCheckResult::Valid) {
+            valid_links.push(checked.link.clone());
+        } else {
             invalid_links.push(InvalidLinkInfo {
-                url: link.url,
-                file_path: link.file_path,
-                line_number: link.line_number,
+                url: checked.link.url.clone(),
+                file_path: checked.link.file_path.clone(),
+                line_number: checked.link.line_number,
+                column: checked.link.column,
+                char_column: checked.link.char_column,
+                context: checked.link.context,
                 collect_link,
+                result: checked.result.clone(),
+                suggestions,
             });
         }
     }
 
-    let summary = counters.to_summary();
+    let summary = counters.to_summary(undecodable_files);
     info!(
+        target: SUMMARY_TARGET,
         total = summary.total,
         valid = summary.valid,
         invalid = summary.invalid,
         redirect = summary.redirect,
         moved = summary.moved,
+        undecodable_files = summary.undecodable_files,
         "link check summary"
     );
 
-    return Ok(invalid_links);
+    LinkCheckReport {
+        summary,
+        invalid_links,
+        valid_links,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::GitHubUrl;
+    use crate::git::mock_github::{FixtureRepo, MockGitHub};
 
     use super::*;
+    use serial_test::serial;
 
     #[tokio::test]
     async fn test_stream_link_checks_runs() {
@@ -200,9 +573,180 @@ mod tests {
             None,
         );
         let repo_manager = RepoManager::from(&github_url).unwrap();
-        let invalid_links = check_links(&repo_manager).await;
-        assert!(invalid_links.is_ok());
-        let invalid_links = invalid_links.unwrap();
-        assert_eq!(invalid_links.len(), 1);
+        let report = check_links(
+            &repo_manager,
+            &ExtractOptions::default(),
+            &LinkChecker::default(),
+            None,
+        )
+        .await;
+        assert!(report.is_ok());
+        let report = report.unwrap();
+        assert_eq!(report.invalid_links.len(), 1);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_check_links_incremental_only_checks_changed_files() {
+        let github = MockGitHub::start().await;
+        github.mount_page("/guide", 200).await;
+        github.mount_page("/faq", 404).await;
+        github.mount_page("/api", 200).await;
+
+        let fixture = FixtureRepo::new(&[
+            (
+                "README.md",
+                &format!("[guide]({})\n", github.site_url("/guide")),
+            ),
+            ("FAQ.md", &format!("[faq]({})\n", github.site_url("/faq"))),
+        ]);
+        let repo_manager = fixture.clone_as("incrementalsac");
+        let state_path = std::env::temp_dir().join(format!(
+            "queensac_scan_cache_test_{}.json",
+            chrono::Utc::now().timestamp_nanos_opt().unwrap()
+        ));
+        let scan_cache = ScanCache::new(state_path.clone());
+        let link_checker = github.link_checker();
+        let options = ExtractOptions::default();
+        let key = "example.com/incrementalsac@main";
+
+        let report = check_links_incremental(
+            &repo_manager,
+            &options,
+            &link_checker,
+            None,
+            &scan_cache,
+            key,
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.summary.total, 2);
+        assert_eq!(report.invalid_links.len(), 1);
+
+        let faq = repo_manager.get_repo_path().join("FAQ.md");
+        std::fs::write(&faq, format!("[api]({})\n", github.site_url("/api"))).unwrap();
+        repo_manager.add_file("FAQ.md").await.unwrap();
+        repo_manager
+            .commit("Update FAQ", "queensac", "queensac@example.com")
+            .await
+            .unwrap();
+
+        let report = check_links_incremental(
+            &repo_manager,
+            &options,
+            &link_checker,
+            None,
+            &scan_cache,
+            key,
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.summary.total, 2);
+        assert_eq!(report.summary.valid, 2);
+        assert!(report.invalid_links.is_empty());
+        assert_eq!(github.request_bodies("GET", "^/guide$").await.len(), 1);
+        assert_eq!(github.request_bodies("GET", "^/api$").await.len(), 1);
+
+        std::fs::remove_file(state_path).unwrap();
+    }
+
+    #[test]
+    fn test_sitemap_match_is_only_suggested() {
+        let checked = CheckedLink {
+            link: LinkInfo {
+                url: "https://example.com/docs/old/intro".to_string(),
+                file_path: "README.md".to_string(),
+                line_number: 1,
+                column: 0,
+                char_column: 0,
+                context: LinkContext::Markdown,
+            },
+            result: LinkCheckResult::SitemapMatch("https://example.com/guide/intro".to_string()),
+            suggestions: Vec::new(),
+        };
+
+        let report = create_report([checked].iter(), 0);
+        assert_eq!(report.summary.moved, 1);
+        let link = &report.invalid_links[0];
+        assert_eq!(link.collect_link, None);
+        assert_eq!(link.suggestions, vec!["https://example.com/guide/intro"]);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_check_link_list() {
+        let github = MockGitHub::start().await;
+        github.mount_page("/guide", 200).await;
+        github.mount_page("/faq", 404).await;
+
+        let link = |path: &str, line_number: usize| LinkInfo {
+            url: github.site_url(path),
+            file_path: "<stdin>".to_string(),
+            line_number,
+            column: 0,
+            char_column: 0,
+            context: LinkContext::Text,
+        };
+        let report = check_link_list(
+            vec![link("/guide", 1), link("/faq", 2), link("/guide", 3)],
+            &github.link_checker(),
+            None,
+        )
+        .await;
+
+        assert_eq!(report.summary.total, 2);
+        assert_eq!(report.summary.valid, 1);
+        assert_eq!(report.invalid_links.len(), 1);
+        assert_eq!(report.invalid_links[0].url, github.site_url("/faq"));
+        assert_eq!(github.request_bodies("GET", "^/guide$").await.len(), 1);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_check_links_in_diff_only_checks_changed_lines() {
+        let github = MockGitHub::start().await;
+        github.mount_page("/old", 404).await;
+        github.mount_page("/new", 404).await;
+        github.mount_page("/api", 200).await;
+
+        let fixture = FixtureRepo::new(&[(
+            "README.md",
+            &format!("[old]({})\n", github.site_url("/old")),
+        )]);
+        let repo_manager = fixture.clone_as("diffsac");
+        let readme = repo_manager.get_repo_path().join("README.md");
+        std::fs::write(
+            &readme,
+            format!(
+                "[old]({})\n[new]({})\n",
+                github.site_url("/old"),
+                github.site_url("/new")
+            ),
+        )
+        .unwrap();
+        let faq = repo_manager.get_repo_path().join("FAQ.md");
+        std::fs::write(&faq, format!("[api]({})\n", github.site_url("/api"))).unwrap();
+        repo_manager.add_all().await.unwrap();
+        repo_manager
+            .commit("Add links", "queensac", "queensac@example.com")
+            .await
+            .unwrap();
+
+        let report = check_links_in_diff(
+            &repo_manager,
+            "origin/main",
+            "HEAD",
+            &ExtractOptions::default(),
+            &github.link_checker(),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.summary.total, 2);
+        assert_eq!(report.summary.valid, 1);
+        assert_eq!(report.invalid_links.len(), 1);
+        assert_eq!(report.invalid_links[0].url, github.site_url("/new"));
+        assert_eq!(report.invalid_links[0].line_number, 2);
+        assert!(github.request_bodies("GET", "^/old$").await.is_empty());
     }
 }
@@ -0,0 +1,162 @@
+use crate::link_checker::suggestion::slug_of;
+use regex::Regex;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use url::Url;
+
+/// The maximum number of sitemap files fetched per host, including nested sitemap indexes.
+const MAX_SITEMAPS_PER_HOST: usize = 20;
+
+/// Caches the URLs listed in each host's sitemap, so that each sitemap is fetched only once.
+#[derive(Debug, Default)]
+pub(crate) struct SitemapCache {
+    entries: Mutex<HashMap<String, Arc<Vec<String>>>>,
+}
+
+impl SitemapCache {
+    /// Looks for the page a dead URL most likely moved to on the same host.
+    ///
+    /// # Returns
+    ///
+    /// `Some(url)` if exactly one URL in the host's sitemap has the same slug as the dead URL,
+    /// `None` if there is no match, the match is ambiguous, or the sitemap could not be fetched.
+    pub(crate) async fn find_match(&self, client: &reqwest::Client, url: &str) -> Option<String> {
+        let dead_url = Url::parse(url).ok()?;
+        let origin = dead_url.origin().ascii_serialization();
+        let urls = self.urls_for(client, &origin).await;
+
+        find_unique_slug_match(&dead_url, &urls)
+    }
+
+    /// Returns the URLs listed in the sitemap of the given origin, fetching it on first use.
+    pub(crate) async fn urls_for(
+        &self,
+        client: &reqwest::Client,
+        origin: &str,
+    ) -> Arc<Vec<String>> {
+        if let Some(urls) = self.entries.lock().unwrap().get(origin) {
+            return urls.clone();
+        }
+
+        let urls = Arc::new(fetch_sitemap(client, origin).await);
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(origin.to_string(), urls.clone());
+        urls
+    }
+}
+
+/// Fetches `/sitemap.xml` from the given origin, following nested sitemap indexes.
+async fn fetch_sitemap(client: &reqwest::Client, origin: &str) -> Vec<String> {
+    let mut urls = Vec::new();
+    let mut pending = vec![format!("{origin}/sitemap.xml")];
+    let mut fetched = 0;
+
+    while let Some(sitemap_url) = pending.pop() {
+        if fetched == MAX_SITEMAPS_PER_HOST {
+            break;
+        }
+        fetched += 1;
+
+        let content = match client.get(&sitemap_url).send().await {
+            Ok(res) if res.status().is_success() => match res.text().await {
+                Ok(content) => content,
+                Err(_) => continue,
+            },
+            _ => continue,
+        };
+
+        for loc in parse_locations(&content) {
+            if loc.ends_with(".xml") {
+                pending.push(loc);
+            } else {
+                urls.push(loc);
+            }
+        }
+    }
+
+    urls
+}
+
+/// Extracts the `<loc>` entries of a sitemap or sitemap index.
+fn parse_locations(content: &str) -> Vec<String> {
+    let loc_regex = Regex::new(r"<loc>\s*([^<\s]+)\s*</loc>").unwrap();
+
+    loc_regex
+        .captures_iter(content)
+        .map(|caps| caps[1].replace("&amp;", "&"))
+        .collect()
+}
+
+/// Returns the only URL on the dead URL's host whose slug equals the dead URL's slug.
+fn find_unique_slug_match(dead_url: &Url, urls: &[String]) -> Option<String> {
+    let slug = slug_of(dead_url)?;
+    let mut matches = urls.iter().filter(|url| {
+        Url::parse(url).is_ok_and(|candidate| {
+            candidate.host_str() == dead_url.host_str()
+                && candidate.path() != dead_url.path()
+                && slug_of(&candidate).as_deref() == Some(slug.as_str())
+        })
+    });
+
+    let found = matches.next()?;
+    match matches.next() {
+        Some(_) => None,
+        None => Some(found.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_locations() {
+        let sitemap = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+          <url><loc>https://example.com/docs/intro</loc></url>
+          <url>
+            <loc>
+              https://example.com/search?q=a&amp;page=2
+            </loc>
+          </url>
+        </urlset>"#;
+
+        assert_eq!(
+            parse_locations(sitemap),
+            vec![
+                "https://example.com/docs/intro".to_string(),
+                "https://example.com/search?q=a&page=2".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_unique_slug_match() {
+        let dead_url = Url::parse("https://example.com/docs/getting-started").unwrap();
+        let urls = vec![
+            "https://example.com/guide/getting-started".to_string(),
+            "https://example.com/guide/installation".to_string(),
+            "https://other.com/getting-started".to_string(),
+        ];
+
+        assert_eq!(
+            find_unique_slug_match(&dead_url, &urls),
+            Some("https://example.com/guide/getting-started".to_string())
+        );
+    }
+
+    #[test]
+    fn test_find_unique_slug_match_ambiguous() {
+        let dead_url = Url::parse("https://example.com/docs/intro").unwrap();
+        let urls = vec![
+            "https://example.com/v1/intro".to_string(),
+            "https://example.com/v2/intro".to_string(),
+        ];
+
+        assert_eq!(find_unique_slug_match(&dead_url, &urls), None);
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{git::find_link_in_content, link_checker::sitemap::SitemapCache};
+use url::{Url, form_urlencoded};
+
+/// The maximum number of suggestions returned for a single dead link.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Where candidate replacement URLs are looked up.
+#[derive(Debug, Clone)]
+pub enum SearchBackend {
+    /// Fetches `/sitemap.xml` from the dead link's host, once per host
+    Sitemap,
+    /// Fetches a search URL; `{query}` in the template is replaced with the words of the
+    /// dead link's slug, percent-encoded and joined with `+`
+    Template(String),
+}
+
+/// Proposes low-confidence replacement candidates for links that are fully dead,
+/// i.e. links without a redirect, rename, or other known new location.
+///
+/// Candidates are URLs on the same host as the dead link, ranked by how many words
+/// they share with its slug. They are meant to be listed in reports only and are
+/// never applied as fixes.
+pub struct LinkSuggester {
+    client: reqwest::Client,
+    backend: SearchBackend,
+    sitemaps: SitemapCache,
+}
+
+impl LinkSuggester {
+    /// Creates a `LinkSuggester` that queries the given backend.
+    pub fn new(backend: SearchBackend) -> Result<Self, reqwest::Error> {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(5))
+            .build()?;
+
+        Ok(LinkSuggester {
+            client,
+            backend,
+            sitemaps: SitemapCache::default(),
+        })
+    }
+
+    /// Returns candidate replacement URLs for a dead link, best match first.
+    ///
+    /// Returns an empty list if the link has no slug, the backend could not be queried,
+    /// or no candidate shares a word with the slug.
+    pub async fn suggest(&self, url: &str) -> Vec<String> {
+        let Ok(dead_url) = Url::parse(url) else {
+            return Vec::new();
+        };
+        let Some(slug) = slug_of(&dead_url) else {
+            return Vec::new();
+        };
+
+        let candidates = match &self.backend {
+            SearchBackend::Sitemap => {
+                let origin = dead_url.origin().ascii_serialization();
+                self.sitemaps.urls_for(&self.client, &origin).await.to_vec()
+            }
+            SearchBackend::Template(template) => {
+                let search_url = search_url(template, &slug);
+                let content = match self.client.get(&search_url).send().await {
+                    Ok(res) if res.status().is_success() => match res.text().await {
+                        Ok(content) => content,
+                        Err(_) => return Vec::new(),
+                    },
+                    _ => return Vec::new(),
+                };
+                find_link_in_content(&content, String::new())
+                    .into_iter()
+                    .map(|link| link.url)
+                    .collect()
+            }
+        };
+
+        rank_candidates(&dead_url, &slug, candidates)
+    }
+}
+
+/// Fills the `{query}` placeholder of a search URL template with the words of a slug.
+fn search_url(template: &str, slug: &str) -> String {
+    let query = slug_words(slug)
+        .iter()
+        .map(|word| form_urlencoded::byte_serialize(word.as_bytes()).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("+");
+
+    template.replace("{query}", &query)
+}
+
+/// Returns the last non-empty path segment of a URL, without its file extension.
+pub(crate) fn slug_of(url: &Url) -> Option<String> {
+    let segment = url.path_segments()?.rfind(|s| !s.is_empty())?;
+    let slug = segment.split('.').next().unwrap_or(segment);
+
+    (!slug.is_empty()).then(|| slug.to_lowercase())
+}
+
+/// Splits a slug into lowercase words on `-`, `_`, and `+`.
+pub(crate) fn slug_words(slug: &str) -> Vec<String> {
+    slug.split(['-', '_', '+'])
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect()
+}
+
+/// Ranks candidate URLs by the fraction of the dead slug's words they share.
+///
+/// Only URLs on the same host as the dead link are considered.
+fn rank_candidates(dead_url: &Url, slug: &str, candidates: Vec<String>) -> Vec<String> {
+    let dead_words = slug_words(slug);
+    let mut scored: Vec<(f64, String)> = candidates
+        .into_iter()
+        .filter_map(|url| {
+            let candidate = Url::parse(&url).ok()?;
+            if candidate.host_str() != dead_url.host_str() || candidate == *dead_url {
+                return None;
+            }
+            let words = slug_words(&slug_of(&candidate)?);
+            let shared = dead_words.iter().filter(|w| words.contains(w)).count();
+            (shared > 0).then(|| (shared as f64 / dead_words.len() as f64, url))
+        })
+        .collect();
+
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, url)| url)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slug_of() {
+        let url = Url::parse("https://example.com/docs/getting-started.html").unwrap();
+        assert_eq!(slug_of(&url), Some("getting-started".to_string()));
+
+        let url = Url::parse("https://example.com/docs/Install_Guide/").unwrap();
+        assert_eq!(slug_of(&url), Some("install_guide".to_string()));
+
+        let url = Url::parse("https://example.com/").unwrap();
+        assert_eq!(slug_of(&url), None);
+    }
+
+    #[test]
+    fn test_rank_candidates() {
+        let dead_url = Url::parse("https://example.com/docs/getting-started").unwrap();
+        let urls = [
+            "https://example.com/guide/getting-started",
+            "https://example.com/guide/started-with-rust",
+            "https://example.com/blog/release-notes",
+            "https://other.com/getting-started",
+        ]
+        .map(String::from)
+        .to_vec();
+
+        let candidates = rank_candidates(&dead_url, "getting-started", urls);
+        assert_eq!(
+            candidates,
+            vec![
+                "https://example.com/guide/getting-started".to_string(),
+                "https://example.com/guide/started-with-rust".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_url_encodes_query_words() {
+        let template = "https://example.com/search?q={query}&lang=en";
+
+        assert_eq!(
+            search_url(template, "getting-started"),
+            "https://example.com/search?q=getting+started&lang=en"
+        );
+        assert_eq!(
+            search_url(template, "q&a_c#_100%"),
+            "https://example.com/search?q=q%26a+c%23+100%25&lang=en"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sitemap_is_fetched_once_per_host() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let sitemap = format!(
+            "<urlset><url><loc>{0}/guide/getting-started</loc></url>\
+             <url><loc>{0}/guide/release-notes</loc></url></urlset>",
+            server.uri()
+        );
+        Mock::given(method("GET"))
+            .and(path("/sitemap.xml"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(sitemap))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let suggester = LinkSuggester::new(SearchBackend::Sitemap).unwrap();
+        assert_eq!(
+            suggester
+                .suggest(&format!("{}/docs/getting-started", server.uri()))
+                .await,
+            vec![format!("{}/guide/getting-started", server.uri())]
+        );
+        assert_eq!(
+            suggester
+                .suggest(&format!("{}/docs/release-notes", server.uri()))
+                .await,
+            vec![format!("{}/guide/release-notes", server.uri())]
+        );
+    }
+}
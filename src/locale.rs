@@ -0,0 +1,209 @@
+/// The language of the text queensac writes for people, such as pull request descriptions
+/// and notifications.
+///
+/// Messages are looked up by key in a catalog per language. Templates contain `{name}`
+/// placeholders, which are filled in by [`Locale::format`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    En,
+    Ko,
+}
+
+/// The keys of the message catalogs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    /// Title of change requests
+    FixTitle,
+    /// Heading of change request descriptions
+    FixHeading,
+    /// Introduction of change request descriptions, with `{noun}`
+    FixIntro,
+    /// Summary of the changes in change request descriptions
+    FixChanges,
+    /// Review instructions in change request descriptions
+    FixReview,
+    /// Heading of the replacements that were not applied
+    SkippedHeading,
+    /// Introduction of the replacements that were not applied
+    SkippedIntro,
+    /// Footer of change request descriptions, with `{abbreviation}`
+    GeneratedBy,
+    /// A GitHub or Bitbucket pull request
+    PullRequest,
+    /// A GitLab merge request
+    MergeRequest,
+    /// Title of notifications, with `{repo}`
+    CheckTitle,
+    /// Headline of notifications when all links are valid, with `{repo}` and `{total}`
+    AllValidHeadline,
+    /// Headline of notifications when some links are not valid, with `{repo}`, `{count}`
+    /// and `{total}`
+    NeedAttentionHeadline,
+    /// Body of notifications when all links are valid
+    AllLinksValid,
+    /// A listed link, with `{link}` and its `{location}`
+    LinkAt,
+    /// The number of links left out of a list, with `{count}`
+    MoreLinks,
+    Total,
+    Valid,
+    Invalid,
+    Redirected,
+    Moved,
+}
+
+impl Locale {
+    /// Returns the language code of the locale.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Ko => "ko",
+        }
+    }
+
+    /// Returns the template of a message in this locale.
+    pub fn text(&self, message: Message) -> &'static str {
+        match self {
+            Self::En => english(message),
+            Self::Ko => korean(message),
+        }
+    }
+
+    /// Returns a message in this locale with its placeholders filled in.
+    ///
+    /// # Arguments
+    /// * `message` - The key of the message
+    /// * `args` - The placeholder names and their values
+    pub fn format(&self, message: Message, args: &[(&str, &str)]) -> String {
+        args.iter()
+            .fold(self.text(message).to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{name}}}"), value)
+            })
+    }
+}
+
+impl std::fmt::Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Locale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "en" => Ok(Self::En),
+            "ko" => Ok(Self::Ko),
+            other => Err(format!("Unsupported locale: {other}")),
+        }
+    }
+}
+
+fn english(message: Message) -> &'static str {
+    match message {
+        Message::FixTitle => "fix: Update broken links",
+        Message::FixHeading => "## 🔗 Link Fixes",
+        Message::FixIntro => {
+            "This {noun} was automatically generated to fix broken links in the repository."
+        }
+        Message::FixChanges => {
+            "### What was changed?
+- Updated broken links to their correct destinations
+- All changes were automatically detected and fixed"
+        }
+        Message::FixReview => {
+            "### How to review?
+1. Check that the new links are correct and accessible
+2. Verify that the changes don't break any existing functionality
+3. Ensure the commit messages are descriptive"
+        }
+        Message::SkippedHeading => "### Skipped replacements",
+        Message::SkippedIntro => {
+            "The following links are broken, but their suggested replacement could not be verified:"
+        }
+        Message::GeneratedBy => {
+            "*This {abbreviation} was generated by the [queens.ac](https://github.com/reddevilmidzy/queensac)*"
+        }
+        Message::PullRequest => "pull request",
+        Message::MergeRequest => "merge request",
+        Message::CheckTitle => "Link check of {repo}",
+        Message::AllValidHeadline => "All {total} links in {repo} are valid",
+        Message::NeedAttentionHeadline => "{count} of {total} links in {repo} need attention",
+        Message::AllLinksValid => "All links are valid.",
+        Message::LinkAt => "{link} in {location}",
+        Message::MoreLinks => "…and {count} more",
+        Message::Total => "Total",
+        Message::Valid => "Valid",
+        Message::Invalid => "Invalid",
+        Message::Redirected => "Redirected",
+        Message::Moved => "Moved",
+    }
+}
+
+fn korean(message: Message) -> &'static str {
+    match message {
+        Message::FixTitle => "fix: 깨진 링크 수정",
+        Message::FixHeading => "## 🔗 링크 수정",
+        Message::FixIntro => {
+            "이 {noun}는 저장소의 깨진 링크를 고치기 위해 자동으로 생성되었습니다."
+        }
+        Message::FixChanges => {
+            "### 무엇이 바뀌었나요?
+- 깨진 링크를 올바른 주소로 수정했습니다
+- 모든 변경 사항은 자동으로 감지되고 수정되었습니다"
+        }
+        Message::FixReview => {
+            "### 어떻게 리뷰하나요?
+1. 새 링크가 올바르고 접근 가능한지 확인해 주세요
+2. 변경 사항이 기존 기능을 망가뜨리지 않는지 확인해 주세요
+3. 커밋 메시지가 변경 내용을 잘 설명하는지 확인해 주세요"
+        }
+        Message::SkippedHeading => "### 적용하지 않은 수정",
+        Message::SkippedIntro => "다음 링크는 깨졌지만, 제안된 대체 링크를 검증하지 못했습니다:",
+        Message::GeneratedBy => {
+            "*이 {abbreviation}는 [queens.ac](https://github.com/reddevilmidzy/queensac)가 생성했습니다*"
+        }
+        Message::PullRequest => "풀 리퀘스트",
+        Message::MergeRequest => "머지 리퀘스트",
+        Message::CheckTitle => "{repo} 링크 검사",
+        Message::AllValidHeadline => "{repo}의 링크 {total}개가 모두 유효합니다",
+        Message::NeedAttentionHeadline => "{repo}의 링크 {total}개 중 {count}개를 확인해야 합니다",
+        Message::AllLinksValid => "모든 링크가 유효합니다.",
+        Message::LinkAt => "{location}: {link}",
+        Message::MoreLinks => "…외 {count}개",
+        Message::Total => "전체",
+        Message::Valid => "유효",
+        Message::Invalid => "무효",
+        Message::Redirected => "리다이렉트",
+        Message::Moved => "이동",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_fills_placeholders() {
+        assert_eq!(
+            Locale::En.format(
+                Message::NeedAttentionHeadline,
+                &[("repo", "owner/repo"), ("count", "2"), ("total", "5")]
+            ),
+            "2 of 5 links in owner/repo need attention"
+        );
+        assert_eq!(
+            Locale::Ko.format(Message::MoreLinks, &[("count", "3")]),
+            "…외 3개"
+        );
+    }
+
+    #[test]
+    fn test_parse_locale() {
+        assert_eq!("ko".parse::<Locale>(), Ok(Locale::Ko));
+        assert_eq!("EN".parse::<Locale>(), Ok(Locale::En));
+        assert!("fr".parse::<Locale>().is_err());
+    }
+}
@@ -1,41 +1,710 @@
-use chrono::{FixedOffset, Utc};
-use clap::Parser;
+use chrono::{DateTime, FixedOffset, Local, TimeDelta, Utc};
+use chrono_tz::Tz;
+use clap::{
+    ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum,
+    builder::TypedValueParser, error::ErrorKind, parser::ValueSource,
+};
+use indicatif::{ProgressBar, ProgressStyle};
 use queensac::{
-    FileChange, GitHubAppConfig, GitHubUrl, InvalidLinkInfo, PullRequestGenerator, RepoManager,
-    check_links,
+    Baseline, BitbucketPullRequestGenerator, BitbucketUrl, ChangeRequestBackend, CommitSigner,
+    Config, DiscordNotifier, ExtractOptions, FileChange, FixPolicy, GitHubAppConfig,
+    GitHubTreeSource, GitHubUrl, GitLabUrl, InvalidLinkInfo, LINK_CHECK_TARGET, LinkCheckReport,
+    LinkCheckResult, LinkCheckSummaryEvent, LinkChecker, LinkContext, LinkInfo, LinkSuggester,
+    Locale, MergeRequestGenerator, Notifier, PrCooldown, PrError, PullRequestGenerator,
+    QueensacError, RepoCache, RepoManager, ResultDelta, ResultStore, RunResults, SUMMARY_TARGET,
+    ScanCache, SearchBackend, SlackNotifier, WebhookNotifier, check_link_list, check_links,
+    check_links_in_diff, check_links_incremental, junit_report, markdown_report,
+    remove_active_temp_dirs, sarif_report, sweep_stale_clones, text_report,
+};
+use regex::Regex;
+use std::{
+    fmt,
+    io::{BufRead, IsTerminal, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+use tracing::{
+    Event, Subscriber, error,
+    field::{Field, Visit},
+    info,
+};
+use tracing_subscriber::{
+    EnvFilter, Layer,
+    fmt::{MakeWriter, format::Writer, time::FormatTime},
+    layer::{Context, SubscriberExt},
+    util::SubscriberInitExt,
 };
-use std::fmt;
-use tracing::{Level, error, info};
-use tracing_subscriber::fmt::{format::Writer, time::FormatTime};
 
 #[derive(Debug, Parser)]
-#[command(name = "queensac", about = "Link checker for a GitHub repo")]
+#[command(
+    name = "queensac",
+    about = "Link checker for a GitHub, GitLab, or Bitbucket repo"
+)]
 struct Args {
-    #[arg(long = "repo", short = 'r', help = "GitHub repository URL")]
-    repo: String,
-    #[arg(long = "branch", short = 'b', help = "Target branch to check")]
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(
+        long = "repo",
+        global = true,
+        short = 'r',
+        help = "GitHub, GitLab, or Bitbucket repository URL, or owner/repo[@branch] for GitHub. Can be repeated to check several repositories"
+    )]
+    repo: Vec<String>,
+    #[arg(
+        long = "local",
+        global = true,
+        conflicts_with_all = ["repo", "no_clone", "incremental", "show_diff"],
+        help = "Check an existing local checkout, including uncommitted changes, instead of cloning"
+    )]
+    local: Option<PathBuf>,
+    #[arg(
+        long = "config",
+        global = true,
+        short = 'c',
+        help = "Config file to read options from [default: queensac.toml in the checked directory]"
+    )]
+    config: Option<PathBuf>,
+    #[arg(
+        long = "branch",
+        global = true,
+        short = 'b',
+        help = "Target branch to check"
+    )]
     branch: Option<String>,
     #[arg(
         long = "dry-run",
+        global = true,
         short = 'd',
         default_value_t = false,
-        help = "Dry run mode"
+        help = "Dry run mode, like the scan command"
     )]
     dry_run: bool,
+    #[arg(
+        long = "show-diff",
+        global = true,
+        default_value_t = false,
+        help = "Print the diff a pull request would make in dry run mode"
+    )]
+    show_diff: bool,
+    #[arg(
+        long = "no-clone",
+        global = true,
+        default_value_t = false,
+        conflicts_with = "show_diff",
+        help = "Read the files through the GitHub API instead of cloning the repository in dry run mode"
+    )]
+    no_clone: bool,
+    #[arg(
+        long = "incremental",
+        global = true,
+        default_value_t = false,
+        conflicts_with = "no_clone",
+        help = "Only check files changed since the last scan and reuse the results for the rest"
+    )]
+    incremental: bool,
+    #[arg(
+        long = "diff",
+        global = true,
+        conflicts_with_all = ["no_clone", "incremental"],
+        help = "Only check links on lines added or modified in a revision range, e.g. `main..HEAD`"
+    )]
+    diff: Option<DiffRange>,
+    #[arg(
+        long = "pr",
+        global = true,
+        conflicts_with_all = ["diff", "no_clone", "incremental"],
+        help = "Only check links on lines added or modified by a GitHub pull request, compared against --branch or the default branch"
+    )]
+    pr: Option<u64>,
+    #[arg(
+        long = "scan-cache",
+        global = true,
+        requires = "incremental",
+        help = "File the results of the last scan are kept in [default: $XDG_STATE_HOME/queensac/scan_cache.json]"
+    )]
+    scan_cache: Option<PathBuf>,
+    #[arg(
+        long = "recurse-submodules",
+        global = true,
+        default_value_t = false,
+        conflicts_with_all = ["no_clone", "local"],
+        help = "Also check the links in submodules, each cloned at its recorded commit"
+    )]
+    recurse_submodules: bool,
+    #[arg(
+        long = "repo-cache",
+        global = true,
+        default_value_t = false,
+        conflicts_with_all = ["no_clone", "local"],
+        help = "Keep mirrors of checked repositories on disk and only fetch new commits on later runs"
+    )]
+    repo_cache: bool,
+    #[arg(
+        long = "repo-cache-dir",
+        global = true,
+        requires = "repo_cache",
+        help = "Directory the repository mirrors are kept in [default: $XDG_CACHE_HOME/queensac/repos]"
+    )]
+    repo_cache_dir: Option<PathBuf>,
+    #[arg(
+        long = "repo-cache-size",
+        global = true,
+        requires = "repo_cache",
+        default_value_t = 2048,
+        help = "Disk budget of the repository mirrors in megabytes, beyond which the least recently used are removed"
+    )]
+    repo_cache_size: u64,
+    #[arg(
+        long = "interactive",
+        global = true,
+        default_value_t = false,
+        conflicts_with = "dry_run",
+        help = "Review each proposed fix before the pull request is created"
+    )]
+    interactive: bool,
+    #[arg(
+        long = "suggest",
+        global = true,
+        default_value_t = false,
+        help = "Suggest replacement candidates for dead links from the site's sitemap"
+    )]
+    suggest: bool,
+    #[arg(
+        long = "search-url",
+        global = true,
+        help = "Search URL template used for suggestions, `{query}` is replaced with the link slug"
+    )]
+    search_url: Option<String>,
+    #[arg(
+        long = "sitemap",
+        global = true,
+        default_value_t = false,
+        help = "Suggest pages with the same slug in the site's sitemap for 404 links"
+    )]
+    sitemap: bool,
+    #[arg(
+        long = "skip-string-literals",
+        global = true,
+        default_value_t = false,
+        help = "Skip links inside string literals of source files"
+    )]
+    skip_string_literals: bool,
+    #[arg(
+        long = "exclude",
+        global = true,
+        value_parser = Regex::new,
+        help = "Pattern of URLs that are not checked, can be repeated"
+    )]
+    exclude: Vec<Regex>,
+    #[arg(
+        long = "timeout",
+        global = true,
+        help = "Seconds to wait for a response to each request [default: 5]"
+    )]
+    timeout: Option<u64>,
+    #[arg(
+        long = "concurrency",
+        global = true,
+        value_parser = clap::value_parser!(u64).range(1..).map(|n| n as usize),
+        help = "Number of links checked at the same time [default: 8]"
+    )]
+    concurrency: Option<usize>,
+    #[arg(
+        long = "retries",
+        global = true,
+        help = "Times a request that failed without a response is retried [default: 2]"
+    )]
+    retries: Option<u32>,
+    #[arg(
+        long = "per-host-limit",
+        global = true,
+        value_parser = clap::value_parser!(u64).range(1..).map(|n| n as usize),
+        help = "Maximum number of requests in flight to each host"
+    )]
+    per_host_limit: Option<usize>,
+    #[arg(
+        long = "report-only",
+        global = true,
+        value_delimiter = ',',
+        default_value = "string",
+        help = "Link contexts (markdown, config, text, comment, string) that are reported but never fixed"
+    )]
+    report_only: Vec<LinkContext>,
+    #[arg(
+        long = "health-issue",
+        global = true,
+        default_value_t = false,
+        help = "Create or update a pinned link health issue in the repository, also when only scanning"
+    )]
+    health_issue: bool,
+    #[arg(
+        long = "check-run",
+        global = true,
+        default_value_t = false,
+        help = "Publish the results as a check run on the base branch with annotations for broken links"
+    )]
+    check_run: bool,
+    #[arg(
+        long = "request-reviews",
+        global = true,
+        default_value_t = false,
+        help = "Request reviews from the CODEOWNERS of the files a pull request changes"
+    )]
+    request_reviews: bool,
+    #[arg(
+        long = "sign-off",
+        global = true,
+        default_value_t = false,
+        help = "Add a Signed-off-by trailer to fix commits"
+    )]
+    sign_off: bool,
+    #[arg(
+        long = "trailer",
+        global = true,
+        help = "Trailer in `Key: value` form to add to fix commits, can be repeated"
+    )]
+    trailers: Vec<String>,
+    #[arg(
+        long = "cooldown-days",
+        global = true,
+        help = "Open at most one fix pull request per repository within this many days"
+    )]
+    cooldown_days: Option<i64>,
+    #[arg(
+        long = "results-file",
+        global = true,
+        help = "File the results of the last run are kept in for the report command [default: $XDG_STATE_HOME/queensac/last_results.json]"
+    )]
+    results_file: Option<PathBuf>,
+    #[arg(
+        long = "state-file",
+        global = true,
+        requires = "cooldown_days",
+        help = "File the pull request cooldown state is kept in [default: $XDG_STATE_HOME/queensac/pr_cooldown.json]"
+    )]
+    state_file: Option<PathBuf>,
+    #[arg(
+        long = "slack-webhook",
+        global = true,
+        help = "Slack incoming webhook URL the results of each checked repository are posted to [default: $QUEENSAC_SLACK_WEBHOOK_URL]"
+    )]
+    slack_webhook: Option<String>,
+    #[arg(
+        long = "discord-webhook",
+        global = true,
+        help = "Discord webhook URL the results of each checked repository are posted to [default: $QUEENSAC_DISCORD_WEBHOOK_URL]"
+    )]
+    discord_webhook: Option<String>,
+    #[arg(
+        long = "webhook-url",
+        global = true,
+        help = "URL the results of each checked repository are posted to as JSON [default: $QUEENSAC_WEBHOOK_URL]"
+    )]
+    webhook_url: Option<String>,
+    #[arg(
+        long = "locale",
+        global = true,
+        default_value = "en",
+        help = "Language (en, ko) of pull request descriptions and chat notifications"
+    )]
+    locale: Locale,
+    #[arg(
+        long = "quiet",
+        global = true,
+        short = 'q',
+        default_value_t = false,
+        conflicts_with = "verbose",
+        help = "Only log errors and the summary of each check"
+    )]
+    quiet: bool,
+    #[arg(
+        long = "no-color",
+        global = true,
+        default_value_t = false,
+        help = "Do not color the logs, also done when NO_COLOR is set or logs are not written to a terminal"
+    )]
+    no_color: bool,
+    #[arg(
+        long = "log-format",
+        global = true,
+        value_enum,
+        default_value_t = LogFormat::Pretty,
+        help = "Format of the logs"
+    )]
+    log_format: LogFormat,
+    #[arg(
+        long = "log-timezone",
+        global = true,
+        default_value = "UTC",
+        help = "Timezone of the log timestamps, an IANA name such as Asia/Seoul, an offset such as +09:00, or local"
+    )]
+    log_timezone: LogTimezone,
+    #[arg(
+        long = "verbose",
+        global = true,
+        short = 'v',
+        action = clap::ArgAction::Count,
+        help = "Log every checked link instead of showing a progress bar, and debug output when repeated"
+    )]
+    verbose: u8,
+    #[arg(
+        long = "format",
+        global = true,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Format of the results printed to stdout, logs go to stderr for formats other than text"
+    )]
+    format: OutputFormat,
+    #[arg(
+        long = "output",
+        global = true,
+        help = "Also write the results as a Markdown report to this file"
+    )]
+    output: Option<PathBuf>,
+    #[arg(
+        long = "baseline",
+        global = true,
+        help = "Leave the failures acknowledged in this baseline file out of the results"
+    )]
+    baseline: Option<PathBuf>,
+    #[arg(
+        long = "write-baseline",
+        global = true,
+        conflicts_with = "baseline",
+        help = "Write the failures of the run to this baseline file to acknowledge them"
+    )]
+    write_baseline: Option<PathBuf>,
+    #[arg(
+        long = "fail-on",
+        global = true,
+        value_enum,
+        default_value_t = FailOn::Error,
+        help = "Which links make the run exit with code 1"
+    )]
+    fail_on: FailOn,
+}
+
+/// What a run does. Without a command, links are checked and fixed unless `--dry-run` is
+/// given.
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Check the links of repositories without proposing fixes
+    Scan,
+    /// Check the links of repositories and open pull requests fixing them
+    Fix,
+    /// Print the results of the last scan or fix in the format given by --format
+    Report,
+    /// Scan the repositories repeatedly and log the links that broke or were fixed
+    Watch(WatchArgs),
+    /// Check URLs without a repository, e.g. to triage a link quickly
+    CheckUrl(CheckUrlArgs),
+}
+
+#[derive(Debug, clap::Args)]
+struct WatchArgs {
+    #[arg(
+        long = "interval",
+        default_value = "1h",
+        value_parser = parse_interval,
+        help = "Time between the starts of two scans, e.g. 30m, 6h or 1d"
+    )]
+    interval: Duration,
+}
+
+#[derive(Debug, clap::Args)]
+struct CheckUrlArgs {
+    #[arg(required_unless_present = "stdin", help = "URLs to check")]
+    urls: Vec<String>,
+    #[arg(
+        long = "stdin",
+        default_value_t = false,
+        help = "Also check the URLs read from stdin, one per line"
+    )]
+    stdin: bool,
+}
+
+impl Args {
+    /// Parses the command line and fills in the options it leaves out from the config file.
+    ///
+    /// The config file is given by `--config`, or else looked up in the local checkout or
+    /// the current directory.
+    fn load() -> Self {
+        let matches = Args::command().get_matches();
+        let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        let config = match &args.config {
+            Some(path) => Config::load(path).map(Some),
+            None => Config::discover(args.local.as_deref().unwrap_or(Path::new("."))),
+        };
+        match config {
+            Ok(Some(config)) => {
+                if let Err(e) = args.apply_config(config, &matches) {
+                    Args::command().error(ErrorKind::InvalidValue, e).exit();
+                }
+            }
+            Ok(None) => {}
+            Err(e) => Args::command().error(ErrorKind::Io, e).exit(),
+        }
+        if let Err(e) = args.apply_command() {
+            e.exit();
+        }
+        args
+    }
+
+    /// Applies the mode of the `scan` and `fix` commands, and checks the options that
+    /// depend on whether fixes are proposed.
+    fn apply_command(&mut self) -> Result<(), clap::Error> {
+        let error = |kind, message: &str| Args::command().error(kind, message);
+        match self.command {
+            Some(Command::Scan | Command::Watch(_)) => self.dry_run = true,
+            Some(Command::Fix) if self.dry_run => {
+                return Err(error(
+                    ErrorKind::ArgumentConflict,
+                    "`fix` opens pull requests, use `scan` to only check links",
+                ));
+            }
+            _ => {}
+        }
+        if matches!(
+            self.command,
+            None | Some(Command::Scan | Command::Fix | Command::Watch(_))
+        ) && self.repo.is_empty()
+            && self.local.is_none()
+        {
+            return Err(error(
+                ErrorKind::MissingRequiredArgument,
+                "`--repo` or `--local` is required",
+            ));
+        }
+        if self.dry_run && self.interactive {
+            return Err(error(
+                ErrorKind::ArgumentConflict,
+                "`--interactive` reviews fixes, which `scan` and `--dry-run` do not propose",
+            ));
+        }
+        if !self.dry_run && (self.show_diff || self.no_clone) {
+            return Err(error(
+                ErrorKind::MissingRequiredArgument,
+                "`--show-diff` and `--no-clone` only work with `scan` or `--dry-run`",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Applies the options of a config file that were not given on the command line.
+    ///
+    /// Exclude patterns and trailers are added to the ones given on the command line.
+    fn apply_config(&mut self, config: Config, matches: &ArgMatches) -> Result<(), String> {
+        let unset = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+        self.exclude.extend(config.exclude);
+        self.trailers.extend(config.pull_request.trailers);
+        if let Some(timeout) = config.timeout
+            && unset("timeout")
+        {
+            self.timeout = Some(timeout);
+        }
+        if let Some(concurrency) = config.concurrency
+            && unset("concurrency")
+        {
+            self.concurrency = Some(concurrency);
+        }
+        if let Some(retries) = config.retries
+            && unset("retries")
+        {
+            self.retries = Some(retries);
+        }
+        if let Some(per_host_limit) = config.per_host_limit
+            && unset("per_host_limit")
+        {
+            self.per_host_limit = Some(per_host_limit);
+        }
+        if let Some(format) = config.format
+            && unset("format")
+        {
+            self.format = OutputFormat::from_str(&format, true)
+                .map_err(|e| format!("Invalid format in config file: {e}"))?;
+        }
+        if let Some(log_format) = config.log.format
+            && unset("log_format")
+        {
+            self.log_format = LogFormat::from_str(&log_format, true)
+                .map_err(|e| format!("Invalid log format in config file: {e}"))?;
+        }
+        if let Some(timezone) = config.log.timezone
+            && unset("log_timezone")
+        {
+            self.log_timezone = timezone
+                .parse()
+                .map_err(|e| format!("Invalid log timezone in config file: {e}"))?;
+        }
+        if let Some(fail_on) = config.fail_on
+            && unset("fail_on")
+        {
+            self.fail_on = FailOn::from_str(&fail_on, true)
+                .map_err(|e| format!("Invalid fail-on in config file: {e}"))?;
+        }
+        if let Some(locale) = config.locale
+            && unset("locale")
+        {
+            self.locale = locale;
+        }
+        if let Some(report_only) = config.report_only
+            && unset("report_only")
+        {
+            self.report_only = report_only;
+        }
+        if let Some(search_url) = config.search_url
+            && unset("search_url")
+        {
+            self.search_url = Some(search_url);
+        }
+        if let Some(cooldown_days) = config.pull_request.cooldown_days
+            && unset("cooldown_days")
+        {
+            self.cooldown_days = Some(cooldown_days);
+        }
+        for (id, flag, value) in [
+            (
+                "skip_string_literals",
+                &mut self.skip_string_literals,
+                config.skip_string_literals,
+            ),
+            ("sitemap", &mut self.sitemap, config.sitemap),
+            ("suggest", &mut self.suggest, config.suggest),
+            ("sign_off", &mut self.sign_off, config.pull_request.sign_off),
+            (
+                "request_reviews",
+                &mut self.request_reviews,
+                config.pull_request.request_reviews,
+            ),
+        ] {
+            if let Some(value) = value
+                && unset(id)
+            {
+                *flag = value;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A range of revisions whose changed lines are checked, written `base..head` like in git.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DiffRange {
+    base: String,
+    /// `HEAD` if the range leaves it out, e.g. `main..`
+    head: String,
+}
+
+impl std::str::FromStr for DiffRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (base, head) = s
+            .split_once("...")
+            .or_else(|| s.split_once(".."))
+            .ok_or_else(|| format!("Expected a range like `main..HEAD`, got `{s}`"))?;
+        if base.is_empty() {
+            return Err(format!("The range `{s}` has no base revision"));
+        }
+        Ok(Self {
+            base: base.to_string(),
+            head: if head.is_empty() { "HEAD" } else { head }.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for DiffRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.base, self.head)
+    }
+}
+
+/// The format the logs are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    /// Human-readable lines
+    Pretty,
+    /// A JSON object per event with the fields flattened, for log collectors
+    Json,
+}
+
+/// The format the results of a run are printed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Only log the results
+    Text,
+    /// A SARIF 2.1.0 log for code scanning
+    Sarif,
+    /// A JUnit XML report for CI test summaries
+    Junit,
+}
+
+/// Exit code when links that fail the `--fail-on` threshold are found
+const EXIT_BROKEN_LINKS: i32 = 1;
+
+/// Exit code when a repository could not be checked, or the run is misconfigured
+const EXIT_RUNTIME_ERROR: i32 = 2;
+
+/// The links that make a run fail, so that CI pipelines can gate merges on link health.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FailOn {
+    /// Fail on links that are not reachable
+    Error,
+    /// Also fail on redirected links
+    Redirect,
+    /// Fail on any link that is not valid, including moved links
+    Warning,
+}
+
+impl FailOn {
+    /// Returns whether the results of a check fail the run.
+    fn is_failing(self, summary: &LinkCheckSummaryEvent) -> bool {
+        match self {
+            Self::Error => summary.invalid > 0,
+            Self::Redirect => summary.invalid + summary.redirect > 0,
+            Self::Warning => summary.total > summary.valid,
+        }
+    }
 }
 
+/// Returns the code a run exits with, or `None` if it succeeded.
+///
+/// # Arguments
+/// * `fail_on` - The links that make the run fail
+/// * `reports` - The results of the checked repositories
+/// * `all_checked` - Whether every repository could be checked
+fn exit_code(
+    fail_on: FailOn,
+    reports: &[(String, LinkCheckReport)],
+    all_checked: bool,
+) -> Option<i32> {
+    if !all_checked {
+        Some(EXIT_RUNTIME_ERROR)
+    } else if reports
+        .iter()
+        .any(|(_, report)| fail_on.is_failing(&report.summary))
+    {
+        Some(EXIT_BROKEN_LINKS)
+    } else {
+        None
+    }
+}
+
+/// Clones older than this are left behind by killed runs and are removed at startup
+const STALE_CLONE_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
 fn main() {
     dotenvy::dotenv().ok();
-    tracing_subscriber::fmt()
-        .with_max_level(Level::INFO)
-        .with_target(false)
-        .with_level(true)
-        .with_ansi(true)
-        .with_timer(KoreanTime)
-        .pretty()
-        .init();
+    let args = Args::load();
+    init_logging(&args);
 
-    let args = Args::parse();
+    match sweep_stale_clones(STALE_CLONE_AGE) {
+        Ok(0) => {}
+        Ok(removed) => info!("Removed {} stale clones of earlier runs", removed),
+        Err(e) => error!("Failed to remove stale clones: {}", e),
+    }
+    remove_clones_on_shutdown();
 
     let rt = tokio::runtime::Builder::new_current_thread()
         .enable_all()
@@ -44,96 +713,1718 @@ fn main() {
 
     // TODO: refactor this to use a more idiomatic way
     rt.block_on(async {
-        let github_url = GitHubUrl::parse(&args.repo).unwrap_or_else(|| {
-            error!("Failed to parse GitHub URL: {}", args.repo);
-            std::process::exit(1);
+        if let Some(Command::Report) = &args.command {
+            print_last_results(&args);
+            return;
+        }
+        let suggester = match (args.suggest, &args.search_url) {
+            (_, Some(template)) => Some(SearchBackend::Template(template.clone())),
+            (true, None) => Some(SearchBackend::Sitemap),
+            (false, None) => None,
+        }
+        .map(|backend| {
+            LinkSuggester::new(backend).unwrap_or_else(|e| {
+                error!("Failed to create link suggester: {}", e);
+                std::process::exit(EXIT_RUNTIME_ERROR);
+            })
         });
-        let repo_manager = RepoManager::from(&github_url).unwrap_or_else(|e| {
-            error!("Failed to clone repository: {}", e);
-            std::process::exit(1);
+        let mut builder = LinkChecker::builder()
+            .sitemap_lookup(args.sitemap)
+            .result_cache(true);
+        if let Some(seconds) = args.timeout {
+            builder = builder.timeout(Duration::from_secs(seconds));
+        }
+        if let Some(concurrency) = args.concurrency {
+            builder = builder.concurrency(concurrency);
+        }
+        if let Some(retries) = args.retries {
+            builder = builder.retries(retries);
+        }
+        if let Some(limit) = args.per_host_limit {
+            builder = builder.per_host_limit(limit);
+        }
+        let link_checker = builder.build().unwrap_or_else(|e| {
+            error!("Failed to create link checker: {}", e);
+            std::process::exit(EXIT_RUNTIME_ERROR);
         });
-        let result = check_links(&repo_manager).await;
-        match result {
-            Ok(invalid_links) => {
-                if invalid_links.is_empty() {
-                    info!("All links are valid");
-                    return;
-                }
-                if args.dry_run {
-                    info!("Dry run mode, skipping pull request creation");
-                    return;
-                }
-
-                let app_config = GitHubAppConfig::from_env().unwrap_or_else(|e| {
-                    error!("GitHub App configuration not found: {}. Please set QUEENSAC_APP_ID and QUEENSAC_APP_PRIVATE_KEY environment variables.", e);
-                    std::process::exit(1);
-                });
-
-                // TODO find base branch from repository.
-                let base_branch = args.branch.unwrap_or("main".to_string());
-
-                let pr_generator = PullRequestGenerator::new(repo_manager, app_config, base_branch).await.unwrap_or_else(|e| {
-                    error!("Failed to create PR generator: {}", e);
-                    std::process::exit(1);
-                });
-                let fixes = find_valid_links(invalid_links).await;
-                if fixes.is_empty() {
-                    info!("No fixable links found. Skipping pull request creation");
-                    return;
-                }
-                let pr_url = pr_generator.create_fix_pr(fixes).await;
-                match pr_url {
-                    Ok(url) => {
-                        info!("Successfully created PR: {}", url);
-                    }
-                    Err(e) => {
-                        error!("Failed to create PR: {}", e);
-                        std::process::exit(1);
-                    }
-                }
+        let extract_options = ExtractOptions {
+            check_string_literals: !args.skip_string_literals,
+            exclude: args.exclude.clone(),
+        };
+        if let Some(Command::CheckUrl(check)) = &args.command {
+            let report = check_urls(check, &link_checker, suggester.as_ref()).await;
+            if args.format == OutputFormat::Text {
+                print_url_results(&report);
             }
-            Err(e) => {
-                error!("Failed to check links: {}", e);
-                std::process::exit(1);
+            let reports = [("check-url".to_string(), report)];
+            write_output(&args, &reports);
+            print_report(args.format, &reports);
+            if let Some(code) = exit_code(args.fail_on, &reports, true) {
+                std::process::exit(code);
             }
+            return;
+        }
+        let notifiers = Notifiers::from_args(&args);
+        let baseline = args.baseline.as_ref().map(|path| {
+            Baseline::load(path).unwrap_or_else(|e| {
+                error!("Failed to read the baseline {}: {}", path.display(), e);
+                std::process::exit(EXIT_RUNTIME_ERROR);
+            })
+        });
+        let checks = LinkChecks {
+            extract_options: &extract_options,
+            link_checker: &link_checker,
+            suggester: suggester.as_ref(),
+            notifiers: &notifiers,
+            baseline: baseline.as_ref(),
+        };
+        if let Some(Command::Watch(watch)) = &args.command {
+            watch_repositories(&args, watch.interval, &checks).await;
+        }
+
+        let results = check_repositories(&args, &checks).await;
+        if results.len() > 1 {
+            log_combined_report(&results);
+        }
+        let checked = checked_reports(&results);
+        store_results(&args, &checked);
+        if let Some(path) = &args.write_baseline {
+            write_baseline(path, &checked);
+        }
+        write_output(&args, &checked);
+        print_report(args.format, &checked);
+        if let Some(code) = exit_code(args.fail_on, &checked, checked.len() == results.len()) {
+            std::process::exit(code);
         }
     });
 }
 
-async fn find_valid_links(invalid_links: Vec<InvalidLinkInfo>) -> Vec<FileChange> {
-    let mut fixes = Vec::new();
+/// Removes the clones of the running checks and exits when the process is interrupted or
+/// terminated, since the clones are otherwise only removed when they are dropped.
+///
+/// The signals are handled on their own thread, so that they are handled even while the
+/// main thread is blocked cloning a repository.
+fn remove_clones_on_shutdown() {
+    std::thread::spawn(|| {
+        let rt = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(rt) => rt,
+            Err(e) => {
+                error!("Failed to listen for shutdown signals: {}", e);
+                return;
+            }
+        };
+        rt.block_on(async {
+            tokio::spawn(async {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    shut_down(130);
+                }
+            });
+            #[cfg(unix)]
+            tokio::spawn(async {
+                use tokio::signal::unix::{SignalKind, signal};
 
-    for invalid_link in invalid_links {
-        if let Some(url) = invalid_link.collect_link {
-            fixes.push(FileChange {
-                file_path: invalid_link.file_path,
-                old_content: invalid_link.url,
-                new_content: url,
-                line_number: invalid_link.line_number,
+                if let Ok(mut terminate) = signal(SignalKind::terminate())
+                    && terminate.recv().await.is_some()
+                {
+                    shut_down(143);
+                }
             });
+            std::future::pending::<()>().await
+        });
+    });
+}
+
+fn shut_down(exit_code: i32) -> ! {
+    info!("Shutting down, removing temporary clones");
+    remove_active_temp_dirs();
+    std::process::exit(exit_code);
+}
+
+/// The link checking setup shared by all checked repositories.
+struct LinkChecks<'a> {
+    extract_options: &'a ExtractOptions,
+    link_checker: &'a LinkChecker,
+    suggester: Option<&'a LinkSuggester>,
+    notifiers: &'a Notifiers,
+    /// Failures left out of the results
+    baseline: Option<&'a Baseline>,
+}
+
+/// The services the results of each checked repository are posted to.
+struct Notifiers {
+    slack: Option<SlackNotifier>,
+    discord: Option<DiscordNotifier>,
+    webhook: Option<WebhookNotifier>,
+}
+
+impl Notifiers {
+    /// Sets up the notifiers configured by arguments or environment variables.
+    ///
+    /// Generic webhook payloads are signed with `QUEENSAC_WEBHOOK_SECRET` if it is set.
+    fn from_args(args: &Args) -> Self {
+        let webhook_url = |arg: &Option<String>, var: &str| {
+            arg.clone()
+                .or_else(|| std::env::var(var).ok())
+                .filter(|url| !url.is_empty())
+        };
+        Self {
+            slack: webhook_url(&args.slack_webhook, "QUEENSAC_SLACK_WEBHOOK_URL").map(|url| {
+                let mut slack = SlackNotifier::new(url);
+                slack.set_locale(args.locale);
+                slack
+            }),
+            discord: webhook_url(&args.discord_webhook, "QUEENSAC_DISCORD_WEBHOOK_URL").map(
+                |url| {
+                    let mut discord = DiscordNotifier::new(url);
+                    discord.set_locale(args.locale);
+                    discord
+                },
+            ),
+            webhook: webhook_url(&args.webhook_url, "QUEENSAC_WEBHOOK_URL").map(|url| {
+                let secret = std::env::var("QUEENSAC_WEBHOOK_SECRET")
+                    .ok()
+                    .filter(|secret| !secret.is_empty());
+                WebhookNotifier::new(url, secret)
+            }),
         }
     }
 
-    fixes
+    /// Posts the results of checking a repository to every configured service.
+    async fn notify(&self, repo: &str, report: &LinkCheckReport) {
+        if let Some(slack) = &self.slack {
+            send_notification(slack, "Slack", repo, report).await;
+        }
+        if let Some(discord) = &self.discord {
+            send_notification(discord, "Discord", repo, report).await;
+        }
+        if let Some(webhook) = &self.webhook {
+            send_notification(webhook, "the webhook", repo, report).await;
+        }
+    }
 }
 
-/// The offset in seconds for Korean Standard Time (UTC+9)
-const KST_OFFSET: i32 = 9 * 3600;
+async fn send_notification(
+    notifier: &impl Notifier,
+    service: &str,
+    repo: &str,
+    report: &LinkCheckReport,
+) {
+    match notifier.notify(repo, report).await {
+        Ok(()) => info!("Posted the results of {} to {}", repo, service),
+        Err(e) => error!(
+            "Failed to post the results of {} to {}: {}",
+            repo, service, e
+        ),
+    }
+}
 
-/// A time formatter that outputs timestamps in Korean Standard Time (KST)
-///
-/// This struct implements the `FormatTime` trait to format timestamps in KST
-/// with millisecond precision and timezone offset.
-///
-/// # Format
-/// The output format is: `YYYY-MM-DDThh:mm:ss.sss+09:00`
+/// Keeps the results of a run for the `report` command.
+fn store_results(args: &Args, reports: &[(String, LinkCheckReport)]) {
+    let results = RunResults {
+        checked_at: Utc::now(),
+        reports: reports.to_vec(),
+    };
+    if let Err(e) = result_store(args).store(&results) {
+        error!("Failed to store the results of the run: {}", e);
+    }
+}
+
+fn result_store(args: &Args) -> ResultStore {
+    ResultStore::new(
+        args.results_file
+            .clone()
+            .unwrap_or_else(ResultStore::default_state_path),
+    )
+}
+
+/// Prints the results of the last run for the `report` command, and exits with the code
+/// the run would exit with.
+fn print_last_results(args: &Args) {
+    let results = match result_store(args).load() {
+        Ok(Some(results)) => results,
+        Ok(None) => {
+            error!("No results yet, run `queensac scan` or `queensac fix` first");
+            std::process::exit(EXIT_RUNTIME_ERROR);
+        }
+        Err(e) => {
+            error!("Failed to read the results of the last run: {}", e);
+            std::process::exit(EXIT_RUNTIME_ERROR);
+        }
+    };
+    info!("Results of the run at {}", results.checked_at);
+    write_output(args, &results.reports);
+    match args.format {
+        OutputFormat::Text => print!("{}", text_report(&results.reports)),
+        format => print_report(format, &results.reports),
+    }
+    if let Some(code) = exit_code(args.fail_on, &results.reports, true) {
+        std::process::exit(code);
+    }
+}
+
+/// Checks the URLs given to `check-url`, along with those read from stdin if enabled.
 ///
-/// It is used internally to format log timestamps in KST.
-struct KoreanTime;
+/// Blank lines and lines starting with `#` are skipped on stdin.
+async fn check_urls(
+    check: &CheckUrlArgs,
+    link_checker: &LinkChecker,
+    suggester: Option<&LinkSuggester>,
+) -> LinkCheckReport {
+    let listed = |url: &str, file_path: &str, line_number: usize| LinkInfo {
+        url: url.to_string(),
+        file_path: file_path.to_string(),
+        line_number,
+        column: 0,
+        char_column: 0,
+        context: LinkContext::Text,
+    };
+    let mut links: Vec<LinkInfo> = check
+        .urls
+        .iter()
+        .enumerate()
+        .map(|(index, url)| listed(url, "<args>", index + 1))
+        .collect();
+    if check.stdin {
+        for (index, line) in std::io::stdin().lock().lines().enumerate() {
+            let line = line.unwrap_or_else(|e| {
+                error!("Failed to read URLs from stdin: {}", e);
+                std::process::exit(EXIT_RUNTIME_ERROR);
+            });
+            let url = line.trim();
+            if !url.is_empty() && !url.starts_with('#') {
+                links.push(listed(url, "<stdin>", index + 1));
+            }
+        }
+    }
+    check_link_list(links, link_checker, suggester).await
+}
 
-impl FormatTime for KoreanTime {
-    fn format_time(&self, w: &mut Writer<'_>) -> Result<(), fmt::Error> {
-        let now = Utc::now().with_timezone(&FixedOffset::east_opt(KST_OFFSET).unwrap());
-        write!(w, "{}", now.format("%Y-%m-%dT%H:%M:%S%.3f%:z"))
+/// Prints the status of each URL checked by `check-url`, with the replacement or reason
+/// for those that are not valid.
+fn print_url_results(report: &LinkCheckReport) {
+    for link in &report.valid_links {
+        println!("valid    {}", link.url);
+    }
+    for link in &report.invalid_links {
+        let detail = match (&link.result, &link.collect_link) {
+            (_, Some(replacement)) => format!(" -> {replacement}"),
+            (LinkCheckResult::Invalid(reason), None) => format!(" ({reason})"),
+            (_, None) => String::new(),
+        };
+        println!("{:<8} {}{}", link.result.status(), link.url, detail);
+        for suggestion in &link.suggestions {
+            println!("         maybe {suggestion}");
+        }
+    }
+}
+
+/// Writes the results of the checked repositories as a Markdown report to the file given
+/// by `--output`, if any.
+fn write_output(args: &Args, reports: &[(String, LinkCheckReport)]) {
+    let Some(path) = &args.output else {
+        return;
+    };
+    match std::fs::write(path, markdown_report(reports)) {
+        Ok(()) => info!("Wrote the report to {}", path.display()),
+        Err(e) => error!("Failed to write the report to {}: {}", path.display(), e),
+    }
+}
+
+/// Prints the results of the checked repositories to stdout in the given format.
+fn print_report(format: OutputFormat, reports: &[(String, LinkCheckReport)]) {
+    match format {
+        OutputFormat::Text => {}
+        OutputFormat::Sarif => println!("{}", sarif_report(reports)),
+        OutputFormat::Junit => print!("{}", junit_report(reports)),
+    }
+}
+
+/// Checks the local checkout or the repositories given by the arguments.
+///
+/// # Returns
+/// The checked repositories and their results without the failures acknowledged by the
+/// baseline, `None` for those that could not be checked.
+async fn check_repositories(
+    args: &Args,
+    checks: &LinkChecks<'_>,
+) -> Vec<(String, Option<LinkCheckReport>)> {
+    if let Some(path) = &args.local {
+        let mut results = vec![(
+            path.display().to_string(),
+            check_local(path, args, checks).await,
+        )];
+        apply_baseline(&mut results, checks.baseline);
+        return results;
+    }
+
+    let hosts: Vec<RepoHost> = args
+        .repo
+        .iter()
+        .map(|repo_url| {
+            RepoHost::parse(repo_url).unwrap_or_else(|| {
+                error!("Failed to parse repository URL: {}", repo_url);
+                std::process::exit(EXIT_RUNTIME_ERROR);
+            })
+        })
+        .collect();
+    let mut results = Vec::new();
+    for host in hosts {
+        let key = host.key();
+        info!("Checking {}", key);
+        let report = if args.no_clone {
+            check_without_clone(&host, checks).await
+        } else {
+            check_target(host, args, checks).await
+        };
+        results.push((key, report));
+    }
+    apply_baseline(&mut results, checks.baseline);
+    results
+}
+
+/// Leaves the failures acknowledged by the baseline, if any, out of the results.
+fn apply_baseline(results: &mut [(String, Option<LinkCheckReport>)], baseline: Option<&Baseline>) {
+    let Some(baseline) = baseline else {
+        return;
+    };
+    for (key, report) in results {
+        if let Some(report) = report {
+            let acknowledged = baseline.apply(report);
+            if acknowledged > 0 {
+                info!(
+                    "{}: {} links that are not valid are acknowledged by the baseline",
+                    key, acknowledged
+                );
+            }
+        }
+    }
+}
+
+/// Writes the failures of the checked repositories to a baseline file.
+fn write_baseline(path: &Path, reports: &[(String, LinkCheckReport)]) {
+    let baseline = Baseline::from_reports(reports);
+    match baseline.store(path) {
+        Ok(()) => info!(
+            "Wrote a baseline of {} links to {}",
+            baseline.len(),
+            path.display()
+        ),
+        Err(e) => {
+            error!("Failed to write the baseline {}: {}", path.display(), e);
+            std::process::exit(EXIT_RUNTIME_ERROR);
+        }
+    }
+}
+
+/// Returns the results of the repositories that could be checked.
+fn checked_reports(
+    results: &[(String, Option<LinkCheckReport>)],
+) -> Vec<(String, LinkCheckReport)> {
+    results
+        .iter()
+        .filter_map(|(key, report)| Some((key.clone(), report.clone()?)))
+        .collect()
+}
+
+/// Checks a local checkout, for which fixes are never proposed.
+///
+/// # Returns
+/// The results of the check, or `None` if the links could not be checked.
+async fn check_local(path: &Path, args: &Args, checks: &LinkChecks<'_>) -> Option<LinkCheckReport> {
+    let repo_manager = RepoManager::open_local(path).unwrap_or_else(|e| {
+        error!("Failed to open local repository: {}", e);
+        std::process::exit(EXIT_RUNTIME_ERROR);
+    });
+    let result = match diff_range(args, &repo_manager) {
+        Some(range) => check_diff(&repo_manager, &range, checks).await,
+        None => {
+            check_links(
+                &repo_manager,
+                checks.extract_options,
+                checks.link_checker,
+                checks.suggester,
+            )
+            .await
+        }
+    };
+    match result {
+        Ok(report) => {
+            if report.invalid_links.is_empty() {
+                info!("All links are valid");
+            } else {
+                info!(
+                    "Found {} links that are not valid",
+                    report.invalid_links.len()
+                );
+            }
+            checks
+                .notifiers
+                .notify(&path.display().to_string(), &report)
+                .await;
+            info!("Local mode, skipping pull request creation");
+            Some(report)
+        }
+        Err(e) => {
+            error!("Failed to check links: {}", e);
+            None
+        }
+    }
+}
+
+/// Scans the repositories every interval until the process is stopped, logging the links
+/// that broke or were fixed since the previous scan.
+///
+/// The results of each scan are stored like those of `scan`, and the first scan is
+/// compared against the stored results of the last run.
+async fn watch_repositories(args: &Args, interval: Duration, checks: &LinkChecks<'_>) -> ! {
+    let store = result_store(args);
+    let mut previous = store.load().unwrap_or_else(|e| {
+        error!("Failed to read the results of the last run: {}", e);
+        None
+    });
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+        // The links checked by the previous scan have to be requested again
+        checks.link_checker.clear_result_cache();
+        let results = check_repositories(args, checks).await;
+        let current = RunResults {
+            checked_at: Utc::now(),
+            reports: checked_reports(&results),
+        };
+        match &previous {
+            Some(previous) => log_changes(&current.changes_since(previous), previous.checked_at),
+            None => info!("First scan, changes are logged from the next scan on"),
+        }
+        if let Err(e) = store.store(&current) {
+            error!("Failed to store the results of the run: {}", e);
+        }
+        write_output(args, &current.reports);
+        print_report(args.format, &current.reports);
+        previous = Some(current);
+        info!("Next scan in {} seconds", interval.as_secs());
+    }
+}
+
+/// Logs the links that broke or were fixed in each repository since the previous scan.
+fn log_changes(changes: &[ResultDelta], since: DateTime<Utc>) {
+    for change in changes {
+        info!(
+            "{}: {} newly broken and {} fixed links since {}",
+            change.repo,
+            change.broken.len(),
+            change.fixed.len(),
+            since
+        );
+        for link in &change.broken {
+            info!(
+                "Broken: {} in {}:{}",
+                link.url, link.file_path, link.line_number
+            );
+        }
+        for url in &change.fixed {
+            info!("Fixed: {}", url);
+        }
+    }
+}
+
+/// Parses a duration written as a number and a unit, `s`, `m`, `h` or `d`, e.g. `30m`.
+fn parse_interval(s: &str) -> Result<Duration, String> {
+    let unit_start = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (amount, unit) = s.split_at(unit_start);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("Expected a duration like `30m` or `1h`, got `{s}`"))?;
+    let unit_seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(format!("Unknown unit in `{s}`, use s, m, h or d")),
+    };
+    if amount == 0 {
+        return Err("The interval must be longer than zero".to_string());
+    }
+    let seconds = amount
+        .checked_mul(unit_seconds)
+        .ok_or_else(|| format!("The interval `{s}` is too long"))?;
+    Ok(Duration::from_secs(seconds))
+}
+
+/// Checks a repository through the GitHub API without cloning it, for dry runs.
+///
+/// # Returns
+/// The results of the check, or `None` if the repository could not be checked.
+async fn check_without_clone(host: &RepoHost, checks: &LinkChecks<'_>) -> Option<LinkCheckReport> {
+    let RepoHost::GitHub(github_url) = host else {
+        error!("--no-clone is only supported for GitHub repositories");
+        return None;
+    };
+    let source = match GitHubTreeSource::new(github_url.clone()) {
+        Ok(source) => source,
+        Err(e) => {
+            error!("Failed to create GitHub source: {}", e);
+            return None;
+        }
+    };
+    let result = check_links(
+        &source,
+        checks.extract_options,
+        checks.link_checker,
+        checks.suggester,
+    )
+    .await;
+    info!("Dry run mode, skipping pull request creation");
+    match result {
+        Ok(report) => {
+            checks.notifiers.notify(&host.key(), &report).await;
+            if report.invalid_links.is_empty() {
+                info!("All links are valid");
+            }
+            Some(report)
+        }
+        Err(e) => {
+            error!("Failed to check links: {}", e);
+            None
+        }
+    }
+}
+
+/// Clones a repository, checks its links and proposes fixes as configured.
+///
+/// # Returns
+/// The results of the check, or `None` if the repository could not be checked.
+async fn check_target(
+    host: RepoHost,
+    args: &Args,
+    checks: &LinkChecks<'_>,
+) -> Option<LinkCheckReport> {
+    let repo_cache = args.repo_cache.then(|| {
+        RepoCache::new(
+            args.repo_cache_dir
+                .clone()
+                .unwrap_or_else(RepoCache::default_root),
+            args.repo_cache_size.saturating_mul(1024 * 1024),
+        )
+    });
+    let mut repo_manager = match host.clone_repository(repo_cache.as_ref()) {
+        Ok(repo_manager) => repo_manager,
+        Err(e) => {
+            error!("Failed to clone repository: {}", e);
+            return None;
+        }
+    };
+    if args.recurse_submodules {
+        repo_manager.enable_submodule_recursion();
+    }
+    match CommitSigner::from_env() {
+        Ok(Some(signer)) => repo_manager.set_commit_signer(signer),
+        Ok(None) => {}
+        Err(e) => {
+            error!("Invalid commit signing configuration: {}", e);
+            std::process::exit(EXIT_RUNTIME_ERROR);
+        }
+    }
+    let result = if let Some(range) = diff_range(args, &repo_manager) {
+        check_diff(&repo_manager, &range, checks).await
+    } else if args.incremental {
+        let scan_cache = ScanCache::new(
+            args.scan_cache
+                .clone()
+                .unwrap_or_else(ScanCache::default_state_path),
+        );
+        let cache_key = format!(
+            "{}@{}",
+            host.key(),
+            args.branch.as_deref().or(host.branch()).unwrap_or("HEAD")
+        );
+        check_links_incremental(
+            &repo_manager,
+            checks.extract_options,
+            checks.link_checker,
+            checks.suggester,
+            &scan_cache,
+            &cache_key,
+        )
+        .await
+    } else {
+        check_links(
+            &repo_manager,
+            checks.extract_options,
+            checks.link_checker,
+            checks.suggester,
+        )
+        .await
+    };
+    match result {
+        Ok(report) => {
+            checks.notifiers.notify(&host.key(), &report).await;
+            let checked = report.clone();
+            let base_branch = args.branch.clone().unwrap_or_else(|| {
+                repo_manager.default_branch().unwrap_or_else(|e| {
+                    error!("Failed to detect the default branch, using main: {}", e);
+                    "main".to_string()
+                })
+            });
+            let invalid_links = report.invalid_links;
+
+            if invalid_links.is_empty() {
+                info!("All links are valid");
+            }
+            let fix_policy = FixPolicy {
+                report_only: args.report_only.clone(),
+            };
+            let cooldown = args.cooldown_days.map(|days| {
+                PrCooldown::new(
+                    args.state_file
+                        .clone()
+                        .unwrap_or_else(PrCooldown::default_state_path),
+                    TimeDelta::days(days),
+                )
+            });
+            let proposal = Proposal {
+                fix_policy: &fix_policy,
+                cooldown: cooldown.as_ref(),
+                repo_key: &host.key(),
+                interactive: args.interactive,
+            };
+            if args.dry_run {
+                if args.show_diff {
+                    let fixes = find_valid_links(invalid_links.clone(), &fix_policy).await;
+                    match PullRequestGenerator::preview(&repo_manager, fixes).await {
+                        Ok(diff) => println!("{diff}"),
+                        Err(e) => error!("Failed to preview fixes: {}", e),
+                    }
+                }
+                // The results are still published, only fixes are not proposed
+                if matches!(host, RepoHost::GitHub(_)) && (args.health_issue || args.check_run) {
+                    let pr_generator = github_pr_generator(repo_manager, base_branch, args).await;
+                    publish_results(&pr_generator, args, &report.summary, &invalid_links).await;
+                }
+                info!("Dry run mode, skipping pull request creation");
+                return Some(checked);
+            }
+            match host {
+                RepoHost::GitHub(_) => {}
+                RepoHost::GitLab(gitlab_url) => {
+                    let mut mr_generator = MergeRequestGenerator::from_env(
+                        repo_manager,
+                        gitlab_url,
+                        base_branch,
+                    )
+                    .unwrap_or_else(|e| {
+                        error!(
+                            "Failed to create MR generator: {}. Please set QUEENSAC_GITLAB_TOKEN.",
+                            e
+                        );
+                        std::process::exit(EXIT_RUNTIME_ERROR);
+                    });
+                    mr_generator.set_locale(args.locale);
+                    propose_fixes(&mr_generator, invalid_links, &proposal).await;
+                    return Some(checked);
+                }
+                RepoHost::Bitbucket(bitbucket_url) => {
+                    let mut pr_generator = BitbucketPullRequestGenerator::from_env(
+                        repo_manager,
+                        bitbucket_url,
+                        base_branch,
+                    )
+                    .unwrap_or_else(|e| {
+                        error!("Failed to create PR generator: {}. Please set QUEENSAC_BITBUCKET_USERNAME and QUEENSAC_BITBUCKET_APP_PASSWORD.", e);
+                        std::process::exit(EXIT_RUNTIME_ERROR);
+                    });
+                    pr_generator.set_locale(args.locale);
+                    propose_fixes(&pr_generator, invalid_links, &proposal).await;
+                    return Some(checked);
+                }
+            }
+            if invalid_links.is_empty() && !args.health_issue && !args.check_run {
+                if let Ok(app_config) = GitHubAppConfig::from_env()
+                    && let Ok(pr_generator) =
+                        PullRequestGenerator::new(repo_manager, app_config, base_branch).await
+                {
+                    close_stale_prs(&pr_generator, "All links in the repository are valid now.")
+                        .await;
+                }
+                return Some(checked);
+            }
+
+            let pr_generator = github_pr_generator(repo_manager, base_branch, args).await;
+            publish_results(&pr_generator, args, &report.summary, &invalid_links).await;
+            propose_fixes(&pr_generator, invalid_links, &proposal).await;
+            Some(checked)
+        }
+        Err(e) => {
+            error!("Failed to check links: {}", e);
+            None
+        }
+    }
+}
+
+/// Creates the generator of GitHub pull requests, exiting if the credentials are missing
+/// or the options are invalid.
+async fn github_pr_generator(
+    repo_manager: RepoManager,
+    base_branch: String,
+    args: &Args,
+) -> PullRequestGenerator {
+    let mut builder = PullRequestGenerator::builder(repo_manager, base_branch)
+        .locale(args.locale)
+        .codeowner_reviews(args.request_reviews)
+        .sign_off(args.sign_off);
+    for trailer in &args.trailers {
+        builder = builder.trailer(trailer);
+    }
+    builder.build().await.unwrap_or_else(|e| {
+        match e {
+            PrError::InvalidTrailer(_) => error!("{}", e),
+            e => error!("Failed to create PR generator: {}. Please set QUEENSAC_APP_ID and QUEENSAC_APP_PRIVATE_KEY, or QUEENSAC_GITHUB_TOKEN for the fork-based workflow.", e),
+        }
+        std::process::exit(EXIT_RUNTIME_ERROR);
+    })
+}
+
+/// Publishes the results of a check to the repository as configured, such as in the
+/// link health issue or a check run. This happens in dry runs too, as no fixes are proposed.
+async fn publish_results(
+    pr_generator: &PullRequestGenerator,
+    args: &Args,
+    summary: &LinkCheckSummaryEvent,
+    invalid_links: &[InvalidLinkInfo],
+) {
+    if args.health_issue {
+        match pr_generator
+            .update_health_issue(summary, invalid_links)
+            .await
+        {
+            Ok(url) => info!("Updated link health issue: {}", url),
+            Err(e) => error!("Failed to update link health issue: {}", e),
+        }
+    }
+    if args.check_run {
+        match pr_generator.publish_check_run(summary, invalid_links).await {
+            Ok(url) => info!("Published check run: {}", url),
+            Err(e) => error!("Failed to publish check run: {}", e),
+        }
+    }
+}
+
+/// Returns the revision range whose changed lines are checked, given by `--diff` or by the
+/// pull request of `--pr`.
+fn diff_range(args: &Args, repo_manager: &RepoManager) -> Option<DiffRange> {
+    if let Some(range) = &args.diff {
+        return Some(range.clone());
+    }
+    let number = args.pr?;
+    let base = args.branch.clone().unwrap_or_else(|| {
+        repo_manager.default_branch().unwrap_or_else(|e| {
+            error!("Failed to detect the default branch, using main: {}", e);
+            "main".to_string()
+        })
+    });
+    Some(DiffRange {
+        base: format!("origin/{base}"),
+        head: format!("refs/pull/{number}/head"),
+    })
+}
+
+/// Checks the links on the lines changed in a revision range.
+async fn check_diff(
+    repo_manager: &RepoManager,
+    range: &DiffRange,
+    checks: &LinkChecks<'_>,
+) -> Result<LinkCheckReport, QueensacError> {
+    info!("Checking the links changed in {}", range);
+    check_links_in_diff(
+        repo_manager,
+        &range.base,
+        &range.head,
+        checks.extract_options,
+        checks.link_checker,
+        checks.suggester,
+    )
+    .await
+}
+
+/// Logs the results of all checked repositories and their totals.
+fn log_combined_report(results: &[(String, Option<LinkCheckReport>)]) {
+    let mut total = LinkCheckSummaryEvent {
+        total: 0,
+        valid: 0,
+        invalid: 0,
+        redirect: 0,
+        moved: 0,
+        undecodable_files: 0,
+    };
+    for (key, report) in results {
+        match report.as_ref().map(|report| &report.summary) {
+            Some(summary) => {
+                info!(
+                    "{}: {} links, {} valid, {} invalid, {} redirected, {} moved",
+                    key,
+                    summary.total,
+                    summary.valid,
+                    summary.invalid,
+                    summary.redirect,
+                    summary.moved
+                );
+                total.total += summary.total;
+                total.valid += summary.valid;
+                total.invalid += summary.invalid;
+                total.redirect += summary.redirect;
+                total.moved += summary.moved;
+                total.undecodable_files += summary.undecodable_files;
+            }
+            None => error!("{}: failed to check links", key),
+        }
+    }
+    info!(
+        "Total of {} repositories: {} links, {} valid, {} invalid, {} redirected, {} moved",
+        results.len(),
+        total.total,
+        total.valid,
+        total.invalid,
+        total.redirect,
+        total.moved
+    );
+}
+
+/// The hosting service of the checked repository, which decides how fixes are proposed.
+enum RepoHost {
+    GitHub(GitHubUrl),
+    GitLab(GitLabUrl),
+    Bitbucket(BitbucketUrl),
+}
+
+impl RepoHost {
+    /// Parses a repository URL, or the `owner/repo[@branch]` shorthand for GitHub.
+    fn parse(url: &str) -> Option<Self> {
+        parse_github_shorthand(url)
+            .or_else(|| GitHubUrl::parse(url).ok())
+            .map(Self::GitHub)
+            .or_else(|| GitLabUrl::parse(url).map(Self::GitLab))
+            .or_else(|| BitbucketUrl::parse(url).map(Self::Bitbucket))
+    }
+
+    /// Identifies the repository across hosts, e.g. `github.com/owner/repo`.
+    fn key(&self) -> String {
+        match self {
+            Self::GitHub(url) => format!("{}/{}/{}", url.host(), url.owner(), url.repo()),
+            Self::GitLab(url) => format!("{}/{}", url.host(), url.project_path()),
+            Self::Bitbucket(url) => format!("bitbucket.org/{}/{}", url.workspace(), url.repo()),
+        }
+    }
+
+    /// Returns the branch given in the repository URL, if any.
+    fn branch(&self) -> Option<&str> {
+        match self {
+            Self::GitHub(url) => url.branch(),
+            Self::GitLab(url) => url.branch(),
+            Self::Bitbucket(url) => url.branch(),
+        }
+    }
+
+    /// Clones the repository, through its mirror in the repository cache if one is given.
+    fn clone_repository(&self, cache: Option<&RepoCache>) -> Result<RepoManager, git2::Error> {
+        match (self, cache) {
+            (Self::GitHub(url), Some(cache)) => cache.clone_repository(url),
+            (Self::GitLab(url), Some(cache)) => cache.clone_gitlab(url),
+            (Self::Bitbucket(url), Some(cache)) => cache.clone_bitbucket(url),
+            (Self::GitHub(url), None) => RepoManager::from(url),
+            (Self::GitLab(url), None) => RepoManager::from_gitlab(url),
+            (Self::Bitbucket(url), None) => RepoManager::from_bitbucket(url),
+        }
+    }
+}
+
+/// Parses the `owner/repo[@branch]` shorthand of a GitHub repository.
+fn parse_github_shorthand(target: &str) -> Option<GitHubUrl> {
+    let (repo_path, branch) = match target.split_once('@') {
+        Some((repo_path, branch)) => (repo_path, Some(branch.to_string())),
+        None => (target, None),
+    };
+    let (owner, repo) = repo_path.split_once('/')?;
+    let is_name = |name: &str| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    if !is_name(owner) || !is_name(repo) || branch.as_deref() == Some("") {
+        return None;
+    }
+    Some(GitHubUrl::new(
+        owner.to_string(),
+        repo.to_string(),
+        branch,
+        None,
+    ))
+}
+
+async fn find_valid_links(
+    invalid_links: Vec<InvalidLinkInfo>,
+    fix_policy: &FixPolicy,
+) -> Vec<FileChange> {
+    let mut fixes = Vec::new();
+
+    for invalid_link in invalid_links {
+        if !fix_policy.allows_fix(invalid_link.context) {
+            info!(
+                "Reporting only {} link in {}:{}",
+                invalid_link.context, invalid_link.file_path, invalid_link.line_number
+            );
+            continue;
+        }
+        if let Some(url) = invalid_link.collect_link {
+            fixes.push(FileChange {
+                file_path: invalid_link.file_path,
+                old_content: invalid_link.url,
+                new_content: url,
+                line_number: invalid_link.line_number,
+                column: invalid_link.column,
+            });
+        }
+    }
+
+    fixes
+}
+
+/// Asks the user to accept, reject or edit each proposed fix.
+///
+/// Quitting or reaching the end of the input rejects the remaining fixes.
+///
+/// # Returns
+/// The accepted fixes, with edited replacements applied.
+fn select_fixes(
+    fixes: Vec<FileChange>,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> std::io::Result<Vec<FileChange>> {
+    let total = fixes.len();
+    let mut accepted = Vec::new();
+
+    for (index, mut fix) in fixes.into_iter().enumerate() {
+        writeln!(
+            output,
+            "[{}/{}] {}:{}\n  - {}\n  + {}",
+            index + 1,
+            total,
+            fix.file_path,
+            fix.line_number,
+            fix.old_content,
+            fix.new_content
+        )?;
+        loop {
+            write!(output, "Apply this fix? [y]es/[n]o/[e]dit/[q]uit: ")?;
+            output.flush()?;
+            let Some(answer) = read_answer(input)? else {
+                return Ok(accepted);
+            };
+            match answer.as_str() {
+                "y" | "yes" => {
+                    accepted.push(fix);
+                    break;
+                }
+                "n" | "no" => break,
+                "e" | "edit" => {
+                    write!(output, "Replacement URL: ")?;
+                    output.flush()?;
+                    match read_answer(input)? {
+                        Some(url) if !url.is_empty() => {
+                            fix.new_content = url;
+                            accepted.push(fix);
+                            break;
+                        }
+                        Some(_) => continue,
+                        None => return Ok(accepted),
+                    }
+                }
+                "q" | "quit" => return Ok(accepted),
+                _ => continue,
+            }
+        }
+    }
+
+    Ok(accepted)
+}
+
+/// Reads a trimmed line of input, or `None` at the end of the input.
+fn read_answer(input: &mut impl BufRead) -> std::io::Result<Option<String>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+/// How fixes are proposed for a repository.
+struct Proposal<'a> {
+    fix_policy: &'a FixPolicy,
+    /// Limits how often pull requests are opened, if configured
+    cooldown: Option<&'a PrCooldown>,
+    /// Identifies the repository in the cooldown state
+    repo_key: &'a str,
+    /// Whether the user reviews each fix before the pull request is created
+    interactive: bool,
+}
+
+/// Opens a pull request fixing the fixable links, or closes outdated queensac pull
+/// requests if there is nothing left to fix.
+async fn propose_fixes(
+    backend: &impl ChangeRequestBackend,
+    invalid_links: Vec<InvalidLinkInfo>,
+    proposal: &Proposal<'_>,
+) {
+    let fixes = find_valid_links(invalid_links, proposal.fix_policy).await;
+    if fixes.is_empty() {
+        info!("No fixable links found. Skipping pull request creation");
+        close_stale_prs(backend, "The repository no longer has fixable links.").await;
+        return;
+    }
+    if let Some(cooldown) = proposal.cooldown {
+        match cooldown.next_allowed(proposal.repo_key, Utc::now()) {
+            Ok(Some(next_allowed)) => {
+                info!(
+                    "A pull request was opened recently, skipping until {}",
+                    next_allowed
+                );
+                return;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                error!("Failed to read pull request cooldown: {}", e);
+                std::process::exit(EXIT_RUNTIME_ERROR);
+            }
+        }
+    }
+    let fixes = if proposal.interactive {
+        match select_fixes(fixes, &mut std::io::stdin().lock(), &mut std::io::stderr()) {
+            Ok(fixes) if fixes.is_empty() => {
+                info!("No fixes accepted. Skipping pull request creation");
+                return;
+            }
+            Ok(fixes) => fixes,
+            Err(e) => {
+                error!("Failed to read fix selection: {}", e);
+                std::process::exit(EXIT_RUNTIME_ERROR);
+            }
+        }
+    } else {
+        fixes
+    };
+    match backend.create_fix_pr(fixes).await {
+        Ok(url) => {
+            info!("Successfully created PR: {}", url);
+            if let Some(cooldown) = proposal.cooldown
+                && let Err(e) = cooldown.record(proposal.repo_key, Utc::now())
+            {
+                error!("Failed to record pull request cooldown: {}", e);
+            }
+        }
+        Err(e) => {
+            error!("Failed to create PR: {}", e);
+            std::process::exit(EXIT_RUNTIME_ERROR);
+        }
+    }
+}
+
+/// Closes outdated queensac pull requests, logging instead of failing on errors.
+async fn close_stale_prs(backend: &impl ChangeRequestBackend, reason: &str) {
+    match backend.close_stale_prs(None, reason).await {
+        Ok(closed) if closed > 0 => info!("Closed {} stale pull requests", closed),
+        Ok(_) => {}
+        Err(e) => error!("Failed to close stale pull requests: {}", e),
+    }
+}
+
+/// Sets up logging for the output tier chosen by the arguments.
+///
+/// By default, checks show a progress bar on interactive terminals and the logs leave out
+/// the checked links. `-v` logs every checked link, `-vv` also logs debug output, and
+/// `--quiet` only logs errors and the summary of each check.
+fn init_logging(args: &Args) {
+    // Keep stdout free for the report when it is machine-readable
+    let log_to_stderr = args.format != OutputFormat::Text;
+    let is_terminal = if log_to_stderr {
+        std::io::stderr().is_terminal()
+    } else {
+        std::io::stdout().is_terminal()
+    };
+    let no_color =
+        args.no_color || std::env::var_os("NO_COLOR").is_some_and(|no_color| !no_color.is_empty());
+    let progress = ProgressLayer::new();
+    let filter = match (args.quiet, args.verbose) {
+        (true, _) => format!("warn,{SUMMARY_TARGET}=info"),
+        (false, 0) => format!("info,{LINK_CHECK_TARGET}=warn"),
+        (false, 1) => "info".to_string(),
+        (false, _) => "info,queensac=debug".to_string(),
+    };
+    let fmt = tracing_subscriber::fmt::layer()
+        .with_writer(ProgressWriter {
+            bar: progress.bar.clone(),
+            to_stderr: log_to_stderr,
+        })
+        .with_target(false)
+        .with_level(true)
+        .with_ansi(is_terminal && !no_color)
+        .with_timer(ConfiguredTime(args.log_timezone));
+    let fmt = match args.log_format {
+        LogFormat::Json => fmt.json().flatten_event(true).with_ansi(false).boxed(),
+        // The multi-line pretty format is hard to follow in CI logs
+        LogFormat::Pretty if args.verbose > 0 && is_terminal => fmt.pretty().boxed(),
+        LogFormat::Pretty => fmt.compact().boxed(),
+    };
+
+    let registry = tracing_subscriber::registry().with(fmt.with_filter(EnvFilter::new(filter)));
+    if args.quiet || args.verbose > 0 || args.log_format == LogFormat::Json {
+        registry.init();
+    } else {
+        registry.with(progress).init();
+    }
+}
+
+/// Shows the progress of each check as a bar on stderr, with the number of checked links
+/// by status.
+///
+/// The bar follows the events the checks log under [`LINK_CHECK_TARGET`], and is hidden
+/// when stderr is not a terminal.
+struct ProgressLayer {
+    bar: Arc<Mutex<ProgressBar>>,
+    counts: Mutex<StatusCounts>,
+}
+
+/// The number of checked links by status.
+#[derive(Debug, Default)]
+struct StatusCounts {
+    valid: usize,
+    invalid: usize,
+    redirect: usize,
+    moved: usize,
+}
+
+impl fmt::Display for StatusCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} valid, {} invalid, {} redirected, {} moved",
+            self.valid, self.invalid, self.redirect, self.moved
+        )
+    }
+}
+
+impl ProgressLayer {
+    fn new() -> Self {
+        Self {
+            bar: Arc::new(Mutex::new(ProgressBar::hidden())),
+            counts: Mutex::new(StatusCounts::default()),
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for ProgressLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let target = event.metadata().target();
+        if target != LINK_CHECK_TARGET && target != SUMMARY_TARGET {
+            return;
+        }
+        let mut bar = self.bar.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut counts = self.counts.lock().unwrap_or_else(PoisonError::into_inner);
+        if target == SUMMARY_TARGET {
+            bar.finish_and_clear();
+            return;
+        }
+
+        let mut fields = ProgressFields::default();
+        event.record(&mut fields);
+        if let Some(total) = fields.total {
+            // Each check, e.g. of another repository, gets a bar of its own
+            bar.finish_and_clear();
+            *bar = ProgressBar::new(total).with_style(
+                ProgressStyle::with_template(
+                    "{spinner} [{elapsed_precise}] {bar:30} {pos}/{len} links, {msg}",
+                )
+                .unwrap_or_else(|_| ProgressStyle::default_bar()),
+            );
+            *counts = StatusCounts::default();
+            bar.set_message(counts.to_string());
+        }
+        if let Some(status) = fields.status {
+            match status.as_str() {
+                "valid" => counts.valid += 1,
+                "invalid" => counts.invalid += 1,
+                "redirect" => counts.redirect += 1,
+                _ => counts.moved += 1,
+            }
+            bar.inc(1);
+            bar.set_message(counts.to_string());
+        }
+    }
+}
+
+/// The fields of link check events the progress bar follows.
+#[derive(Debug, Default)]
+struct ProgressFields {
+    /// The number of links a check is about to check
+    total: Option<u64>,
+    /// The status of a checked link
+    status: Option<String>,
+}
+
+impl Visit for ProgressFields {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == "total" {
+            self.total = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "status" {
+            self.status = Some(format!("{value:?}"));
+        }
+    }
+}
+
+/// Writes logs to stdout or stderr, hiding the progress bar while writing so that the
+/// logs do not mix with it.
+#[derive(Clone)]
+struct ProgressWriter {
+    bar: Arc<Mutex<ProgressBar>>,
+    to_stderr: bool,
+}
+
+impl<'a> MakeWriter<'a> for ProgressWriter {
+    type Writer = ProgressWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+impl Write for ProgressWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let bar = self.bar.lock().unwrap_or_else(PoisonError::into_inner);
+        bar.suspend(|| {
+            if self.to_stderr {
+                std::io::stderr().write(buf)
+            } else {
+                std::io::stdout().write(buf)
+            }
+        })
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.to_stderr {
+            std::io::stderr().flush()
+        } else {
+            std::io::stdout().flush()
+        }
+    }
+}
+
+/// The timezone log timestamps are written in.
+#[derive(Debug, Clone, Copy)]
+enum LogTimezone {
+    Utc,
+    /// The timezone of the system running queensac
+    Local,
+    /// A fixed offset from UTC, e.g. `+09:00`
+    Fixed(FixedOffset),
+    /// An IANA timezone, e.g. `Asia/Seoul`, which follows daylight saving time
+    Named(Tz),
+}
+
+impl std::str::FromStr for LogTimezone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("utc") || s == "Z" {
+            return Ok(Self::Utc);
+        }
+        if s.eq_ignore_ascii_case("local") {
+            return Ok(Self::Local);
+        }
+        if let Ok(offset) = s.parse::<FixedOffset>() {
+            return Ok(Self::Fixed(offset));
+        }
+        s.parse::<Tz>().map(Self::Named).map_err(|_| {
+            format!(
+                "Unknown timezone `{s}`, expected an IANA name such as `Asia/Seoul`, an offset such as `+09:00`, or `local`"
+            )
+        })
+    }
+}
+
+/// A time formatter that outputs timestamps in the timezone given by `--log-timezone`
+///
+/// # Format
+/// The output format is: `YYYY-MM-DDThh:mm:ss.sss+hh:mm`, e.g.
+/// `2025-01-01T09:00:00.000+09:00` for `Asia/Seoul`
+struct ConfiguredTime(LogTimezone);
+
+impl ConfiguredTime {
+    /// Formats a point in time in the configured timezone.
+    fn format(&self, time: DateTime<Utc>) -> String {
+        let time = match self.0 {
+            LogTimezone::Utc => time.fixed_offset(),
+            LogTimezone::Local => time.with_timezone(&Local).fixed_offset(),
+            LogTimezone::Fixed(offset) => time.with_timezone(&offset),
+            LogTimezone::Named(timezone) => time.with_timezone(&timezone).fixed_offset(),
+        };
+        time.format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string()
+    }
+}
+
+impl FormatTime for ConfiguredTime {
+    fn format_time(&self, w: &mut Writer<'_>) -> Result<(), fmt::Error> {
+        write!(w, "{}", self.format(Utc::now()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix(old_url: &str, new_url: &str) -> FileChange {
+        FileChange {
+            file_path: "README.md".to_string(),
+            old_content: old_url.to_string(),
+            new_content: new_url.to_string(),
+            line_number: 1,
+            column: 0,
+        }
+    }
+
+    fn select(answers: &str) -> Vec<FileChange> {
+        let fixes = vec![
+            fix("https://example.com/a", "https://example.com/new-a"),
+            fix("https://example.com/b", "https://example.com/new-b"),
+        ];
+        let mut output = Vec::new();
+        select_fixes(fixes, &mut answers.as_bytes(), &mut output).unwrap()
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("45s"), Ok(Duration::from_secs(45)));
+        assert_eq!(parse_interval("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_interval("6h"), Ok(Duration::from_secs(6 * 60 * 60)));
+        assert_eq!(parse_interval("1d"), Ok(Duration::from_secs(24 * 60 * 60)));
+    }
+
+    #[test]
+    fn test_parse_interval_rejects_invalid() {
+        assert!(
+            parse_interval("0m")
+                .unwrap_err()
+                .contains("longer than zero")
+        );
+        assert!(
+            parse_interval("18446744073709551615d")
+                .unwrap_err()
+                .contains("too long")
+        );
+        assert!(parse_interval("99999999999999999999s").is_err());
+        assert!(parse_interval("30").unwrap_err().contains("Unknown unit"));
+        assert!(parse_interval("30w").unwrap_err().contains("Unknown unit"));
+        assert!(parse_interval("m").is_err());
+        assert!(parse_interval("").is_err());
+        assert!(parse_interval("-5m").is_err());
+    }
+
+    #[test]
+    fn test_diff_range() {
+        let range = |base: &str, head: &str| DiffRange {
+            base: base.to_string(),
+            head: head.to_string(),
+        };
+        assert_eq!("main..feature".parse(), Ok(range("main", "feature")));
+        assert_eq!("main...feature".parse(), Ok(range("main", "feature")));
+        assert_eq!("origin/main..".parse(), Ok(range("origin/main", "HEAD")));
+        assert_eq!(
+            "v1.0..v1.1".parse::<DiffRange>().unwrap().to_string(),
+            "v1.0..v1.1"
+        );
+    }
+
+    #[test]
+    fn test_diff_range_rejects_invalid() {
+        assert!(
+            "..feature"
+                .parse::<DiffRange>()
+                .unwrap_err()
+                .contains("no base revision")
+        );
+        assert!(
+            "main"
+                .parse::<DiffRange>()
+                .unwrap_err()
+                .contains("Expected a range")
+        );
+        assert!("".parse::<DiffRange>().is_err());
+        assert!("..".parse::<DiffRange>().is_err());
+    }
+
+    fn report(valid: usize, invalid: usize, redirect: usize, moved: usize) -> LinkCheckReport {
+        LinkCheckReport {
+            summary: LinkCheckSummaryEvent {
+                total: valid + invalid + redirect + moved,
+                valid,
+                invalid,
+                redirect,
+                moved,
+                undecodable_files: 0,
+            },
+            invalid_links: Vec::new(),
+            valid_links: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_fail_on_exit_codes() {
+        let cases = [
+            // (fail_on, valid, invalid, redirect, moved, expected exit code)
+            (FailOn::Error, 3, 0, 0, 0, None),
+            (FailOn::Error, 2, 1, 0, 0, Some(EXIT_BROKEN_LINKS)),
+            (FailOn::Error, 2, 0, 1, 0, None),
+            (FailOn::Error, 2, 0, 0, 1, None),
+            (FailOn::Redirect, 3, 0, 0, 0, None),
+            (FailOn::Redirect, 2, 1, 0, 0, Some(EXIT_BROKEN_LINKS)),
+            (FailOn::Redirect, 2, 0, 1, 0, Some(EXIT_BROKEN_LINKS)),
+            (FailOn::Redirect, 2, 0, 0, 1, None),
+            (FailOn::Warning, 3, 0, 0, 0, None),
+            (FailOn::Warning, 2, 1, 0, 0, Some(EXIT_BROKEN_LINKS)),
+            (FailOn::Warning, 2, 0, 1, 0, Some(EXIT_BROKEN_LINKS)),
+            (FailOn::Warning, 2, 0, 0, 1, Some(EXIT_BROKEN_LINKS)),
+        ];
+        for (fail_on, valid, invalid, redirect, moved, expected) in cases {
+            let reports = [(
+                "github.com/owner/repo".to_string(),
+                report(valid, invalid, redirect, moved),
+            )];
+            assert_eq!(
+                exit_code(fail_on, &reports, true),
+                expected,
+                "{fail_on:?} with {invalid} invalid, {redirect} redirected and {moved} moved links"
+            );
+        }
+
+        assert_eq!(EXIT_BROKEN_LINKS, 1);
+        assert_eq!(EXIT_RUNTIME_ERROR, 2);
+        // A repository that could not be checked fails the run regardless of the links
+        let reports = [("github.com/owner/repo".to_string(), report(3, 0, 0, 0))];
+        assert_eq!(
+            exit_code(FailOn::Error, &reports, false),
+            Some(EXIT_RUNTIME_ERROR)
+        );
+        assert_eq!(exit_code(FailOn::Error, &[], true), None);
+    }
+
+    #[test]
+    fn test_log_timezone() {
+        assert!(matches!("UTC".parse(), Ok(LogTimezone::Utc)));
+        assert!(matches!("utc".parse(), Ok(LogTimezone::Utc)));
+        assert!(matches!("Z".parse(), Ok(LogTimezone::Utc)));
+        assert!(matches!("local".parse(), Ok(LogTimezone::Local)));
+        assert!(matches!("Local".parse(), Ok(LogTimezone::Local)));
+        assert!(
+            matches!("+09:00".parse(), Ok(LogTimezone::Fixed(offset)) if offset.local_minus_utc() == 9 * 3600)
+        );
+        assert!(matches!(
+            "Asia/Seoul".parse(),
+            Ok(LogTimezone::Named(chrono_tz::Asia::Seoul))
+        ));
+        assert!(matches!(
+            "America/New_York".parse(),
+            Ok(LogTimezone::Named(chrono_tz::America::New_York))
+        ));
+
+        for invalid in ["Mars/Olympus_Mons", "+25:00", "", "9"] {
+            let error = invalid.parse::<LogTimezone>().unwrap_err();
+            assert!(error.contains("Unknown timezone"), "{invalid}: {error}");
+        }
+    }
+
+    #[test]
+    fn test_log_timezone_defaults_to_utc() {
+        let args = Args::try_parse_from(["queensac", "scan"]).unwrap();
+        assert!(matches!(args.log_timezone, LogTimezone::Utc));
+    }
+
+    #[test]
+    fn test_configured_time() {
+        let time = DateTime::parse_from_rfc3339("2025-01-01T00:00:00.123Z")
+            .unwrap()
+            .to_utc();
+        let format = |timezone: &str| ConfiguredTime(timezone.parse().unwrap()).format(time);
+
+        assert_eq!(format("UTC"), "2025-01-01T00:00:00.123+00:00");
+        assert_eq!(format("+09:00"), "2025-01-01T09:00:00.123+09:00");
+        assert_eq!(format("Asia/Seoul"), "2025-01-01T09:00:00.123+09:00");
+        // Daylight saving time is followed by named timezones only
+        assert_eq!(format("America/New_York"), "2024-12-31T19:00:00.123-05:00");
+        let summer = DateTime::parse_from_rfc3339("2025-07-01T00:00:00Z")
+            .unwrap()
+            .to_utc();
+        assert_eq!(
+            ConfiguredTime("America/New_York".parse().unwrap()).format(summer),
+            "2025-06-30T20:00:00.000-04:00"
+        );
+        assert_eq!(
+            format("local"),
+            time.with_timezone(&Local)
+                .format("%Y-%m-%dT%H:%M:%S%.3f%:z")
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_github_shorthand() {
+        let url = parse_github_shorthand("reddevilmidzy/queensac").unwrap();
+        assert_eq!(url.owner(), "reddevilmidzy");
+        assert_eq!(url.repo(), "queensac");
+        assert_eq!(url.branch(), None);
+
+        let url = parse_github_shorthand("owner/my-repo.rs@feature/links").unwrap();
+        assert_eq!(url.owner(), "owner");
+        assert_eq!(url.repo(), "my-repo.rs");
+        assert_eq!(url.branch(), Some("feature/links"));
+    }
+
+    #[test]
+    fn test_parse_github_shorthand_rejects_other_targets() {
+        for target in [
+            "https://github.com/owner/repo",
+            "github.com/owner/repo",
+            "owner/repo/extra",
+            "owner/repo@",
+            "owner/",
+            "/repo",
+            "owner",
+            "",
+        ] {
+            assert!(parse_github_shorthand(target).is_none(), "{target}");
+        }
+    }
+
+    /// Parses a command line and applies a config file to it, like `Args::load`.
+    fn args_with_config(command_line: &[&str], config: &str) -> Args {
+        let matches = Args::command().try_get_matches_from(command_line).unwrap();
+        let mut args = Args::from_arg_matches(&matches).unwrap();
+        args.apply_config(Config::parse(config).unwrap(), &matches)
+            .unwrap();
+        args
+    }
+
+    const CONFIG: &str = r#"
+        exclude = ["^https://localhost"]
+        timeout = 10
+        concurrency = 16
+        retries = 4
+        format = "sarif"
+        fail-on = "warning"
+
+        [log]
+        timezone = "+09:00"
+    "#;
+
+    #[test]
+    fn test_config_fills_in_unset_flags() {
+        let args = args_with_config(&["queensac", "scan", "--local", "."], CONFIG);
+        assert_eq!(args.timeout, Some(10));
+        assert_eq!(args.concurrency, Some(16));
+        assert_eq!(args.retries, Some(4));
+        assert_eq!(args.format, OutputFormat::Sarif);
+        assert_eq!(args.fail_on, FailOn::Warning);
+        assert!(matches!(args.log_timezone, LogTimezone::Fixed(_)));
+        assert_eq!(args.exclude.len(), 1);
+    }
+
+    #[test]
+    fn test_flags_override_config() {
+        let args = args_with_config(
+            &[
+                "queensac",
+                "scan",
+                "--local",
+                ".",
+                "--timeout",
+                "3",
+                "--concurrency",
+                "2",
+                "--format",
+                "junit",
+                "--fail-on",
+                "error",
+                "--log-timezone",
+                "UTC",
+                "--exclude",
+                "example\\.com",
+            ],
+            CONFIG,
+        );
+        assert_eq!(args.timeout, Some(3));
+        assert_eq!(args.concurrency, Some(2));
+        assert_eq!(args.format, OutputFormat::Junit);
+        assert_eq!(args.fail_on, FailOn::Error);
+        assert!(matches!(args.log_timezone, LogTimezone::Utc));
+        // Options that were not given still come from the config file
+        assert_eq!(args.retries, Some(4));
+        // Exclude patterns of both are used
+        assert_eq!(args.exclude.len(), 2);
+    }
+
+    #[test]
+    fn test_invalid_config_value() {
+        let matches = Args::command()
+            .try_get_matches_from(["queensac", "scan", "--local", "."])
+            .unwrap();
+        let mut args = Args::from_arg_matches(&matches).unwrap();
+        let error = args
+            .apply_config(Config::parse("fail-on = \"never\"").unwrap(), &matches)
+            .unwrap_err();
+        assert!(error.contains("Invalid fail-on in config file"));
+    }
+
+    #[test]
+    fn test_select_fixes_accept_and_skip() {
+        let accepted = select("y\nn\n");
+        assert_eq!(accepted.len(), 1);
+        assert_eq!(accepted[0].old_content, "https://example.com/a");
+
+        let accepted = select("no\nyes\n");
+        assert_eq!(accepted.len(), 1);
+        assert_eq!(accepted[0].old_content, "https://example.com/b");
+    }
+
+    #[test]
+    fn test_select_fixes_edit() {
+        let accepted = select("e\nhttps://example.com/edited\nn\n");
+        assert_eq!(accepted.len(), 1);
+        assert_eq!(accepted[0].new_content, "https://example.com/edited");
+    }
+
+    #[test]
+    fn test_select_fixes_quit() {
+        assert_eq!(select("y\nq\n").len(), 1);
+        assert!(select("quit\n").is_empty());
+        // Reaching the end of the input rejects the remaining fixes
+        assert_eq!(select("y\n").len(), 1);
+    }
+
+    #[test]
+    fn test_select_fixes_bad_input() {
+        let fixes = vec![fix("https://example.com/a", "https://example.com/new-a")];
+        let mut output = Vec::new();
+        let accepted = select_fixes(fixes, &mut "maybe\n\ny\n".as_bytes(), &mut output).unwrap();
+        assert_eq!(accepted.len(), 1);
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("[1/1] README.md:1\n"));
+        assert_eq!(output.matches("Apply this fix?").count(), 3);
     }
 }